@@ -0,0 +1,352 @@
+//! Embedding the renderer in another `wgpu` application: the fractal is
+//! rendered into a texture every frame, which is then sampled by a quad
+//! spinning in a 3D scene.
+//!
+//! ```bash
+//! cargo run --example spinning_quad
+//! ```
+
+use std::{sync::Arc, time::Instant};
+
+use mandelbrot_explorer_wgpu::{
+    MandelbrotRenderer, Uniforms, renderer::request_device,
+};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource,
+    BindingType, Buffer, BufferBindingType, BufferDescriptor, BufferUsages,
+    Color, ColorTargetState, ColorWrites, CommandEncoderDescriptor, Device,
+    Extent3d, FilterMode, FragmentState, Instance, LoadOp, MultisampleState,
+    Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, RenderPipelineDescriptor, RequestAdapterOptions,
+    SamplerBindingType, SamplerDescriptor, ShaderStages, StoreOp, Surface,
+    SurfaceConfiguration, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension, VertexState, include_wgsl,
+};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    window::{Window, WindowId},
+};
+
+/// Side of the square texture the fractal is rendered into.
+const FRACTAL_SIZE: u32 = 512;
+
+/// Format of the texture the fractal is rendered into.
+const FRACTAL_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// How fast the quad spins, in radians per second.
+const SPIN_SPEED: f32 = 0.8;
+
+/// How fast the fractal zooms in, in [`Uniforms::zooms`] per second.
+const ZOOM_SPEED: f64 = 1.0;
+
+/// The window and everything rendering into it.
+struct Scene {
+    window: Arc<Window>,
+    surface: Surface<'static>,
+    device: Device,
+    queue: Queue,
+    config: SurfaceConfiguration,
+    renderer: MandelbrotRenderer,
+    /// The texture the fractal is rendered into and the quad samples.
+    fractal_view: TextureView,
+    pipeline: RenderPipeline,
+    /// The angle of the quad and the aspect ratio of the window.
+    params: Buffer,
+    bind_group: BindGroup,
+    start: Instant,
+}
+
+impl Scene {
+    /// Creates the [`Scene`] rendering into the `window`.
+    fn new(window: Arc<Window>) -> Self {
+        let instance = Instance::default();
+        let surface = instance
+            .create_surface(window.clone())
+            .expect("failed to create the surface");
+        let adapter = pollster::block_on(instance.request_adapter(
+            &RequestAdapterOptions {
+                compatible_surface: Some(&surface),
+                ..Default::default()
+            },
+        ))
+        .expect("no suitable adapter");
+        let (device, queue, precision) =
+            pollster::block_on(request_device(&adapter))
+                .expect("failed to create the device");
+
+        let size = window.inner_size();
+        let config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .expect("the surface isn't supported by the adapter");
+        surface.configure(&device, &config);
+
+        let renderer =
+            MandelbrotRenderer::new(&device, &queue, precision, FRACTAL_FORMAT);
+        let fractal = device.create_texture(&TextureDescriptor {
+            label: Some("Fractal Texture"),
+            size: Extent3d {
+                width: FRACTAL_SIZE,
+                height: FRACTAL_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: FRACTAL_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let fractal_view =
+            fractal.create_view(&TextureViewDescriptor::default());
+
+        let (pipeline, params, bind_group) =
+            create_quad_pipeline(&device, &fractal_view, config.format);
+
+        Self {
+            window,
+            surface,
+            device,
+            queue,
+            config,
+            renderer,
+            fractal_view,
+            pipeline,
+            params,
+            bind_group,
+            start: Instant::now(),
+        }
+    }
+
+    /// Reconfigures the surface for the new size of the window.
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    /// Renders the fractal into its texture and the spinning quad sampling
+    /// it into the window.
+    #[allow(clippy::cast_precision_loss, reason = "window sizes are small")]
+    fn render(&mut self) {
+        let time = self.start.elapsed().as_secs_f64();
+        self.renderer.set_uniforms(&Uniforms {
+            offset: [-0.743_643_887_037_151, 0.131_825_904_205_33],
+            zooms: (Uniforms::default().zooms - ZOOM_SPEED * time).max(-60.0),
+            time,
+            ..*self.renderer.uniforms()
+        });
+        self.renderer.render_to_viewport(
+            &self.fractal_view,
+            [0, 0, FRACTAL_SIZE, FRACTAL_SIZE],
+        );
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "the angle wraps around"
+        )]
+        let angle = time as f32 * SPIN_SPEED;
+        let aspect = self.config.width as f32 / self.config.height as f32;
+        self.queue.write_buffer(
+            &self.params,
+            0,
+            bytemuck::cast_slice(&[angle, aspect]),
+        );
+
+        let Ok(frame) = self.surface.get_current_texture() else {
+            self.surface.configure(&self.device, &self.config);
+            return;
+        };
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        {
+            let mut render_pass =
+                encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Quad Render Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color {
+                                r: 0.02,
+                                g: 0.02,
+                                b: 0.03,
+                                a: 1.0,
+                            }),
+                            store: StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+        self.window.request_redraw();
+    }
+}
+
+/// Creates the pipeline drawing the quad into targets of the given `format`,
+/// sampling the `fractal`, along with the buffer of its parameters and its
+/// bind group.
+fn create_quad_pipeline(
+    device: &Device,
+    fractal: &TextureView,
+    format: TextureFormat,
+) -> (RenderPipeline, Buffer, BindGroup) {
+    let shader =
+        device.create_shader_module(include_wgsl!("spinning_quad.wgsl"));
+
+    let bind_group_layout =
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Quad Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: true,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+    let params = device.create_buffer(&BufferDescriptor {
+        label: Some("Quad Params Buffer"),
+        size: size_of::<[f32; 2]>() as u64,
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let sampler = device.create_sampler(&SamplerDescriptor {
+        label: Some("Fractal Sampler"),
+        address_mode_u: AddressMode::ClampToEdge,
+        address_mode_v: AddressMode::ClampToEdge,
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    });
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("Quad Bind Group"),
+        layout: &bind_group_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(fractal),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::Sampler(&sampler),
+            },
+        ],
+    });
+
+    let pipeline_layout =
+        device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Quad Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+    let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Quad Pipeline"),
+        vertex: VertexState {
+            module: &shader,
+            entry_point: None,
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: &shader,
+            entry_point: None,
+            compilation_options: PipelineCompilationOptions::default(),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        layout: Some(&pipeline_layout),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+
+    (pipeline, params, bind_group)
+}
+
+/// Creates the [`Scene`] once the event loop is running.
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = event_loop
+            .create_window(
+                Window::default_attributes().with_title("Spinning fractal"),
+            )
+            .expect("failed to create the window");
+        self.scene = Some(Scene::new(Arc::new(window)));
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(scene) = &mut self.scene else { return };
+        match event {
+            WindowEvent::Resized(size) => {
+                scene.resize(size.width, size.height);
+            }
+            WindowEvent::RedrawRequested => scene.render(),
+            WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().expect("failed to create the event loop");
+    event_loop
+        .run_app(&mut App::default())
+        .expect("the event loop failed");
+}
@@ -0,0 +1,46 @@
+struct Params {
+    // Rotation of the quad around the vertical axis, in radians.
+    angle: f32,
+    // Width of the window divided by its height.
+    aspect: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var fractal: texture_2d<f32>;
+@group(0) @binding(2)
+var fractal_sampler: sampler;
+
+struct Interpolators {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// Draws the two triangles of a unit quad spinning in front of the camera.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> Interpolators {
+    let corners = array(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let uv = corners[vertex_index];
+    let corner = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    // Rotated around the vertical axis and moved away from the camera.
+    let x = corner.x * cos(params.angle);
+    let z = corner.x * sin(params.angle) + 3.0;
+    // Perspective projection with a vertical field of view of 2 * atan(0.5).
+    let pos = vec4<f32>(x * 2.0 / params.aspect, corner.y * 2.0, 0.5 * z, z);
+    return Interpolators(pos, uv);
+}
+
+@fragment
+fn fg_main(i: Interpolators) -> @location(0) vec4f {
+    return textureSample(fractal, fractal_sampler, i.uv);
+}
@@ -0,0 +1,285 @@
+//! Automatic tuning of the maximum iterations: a compute pass finds the
+//! highest iteration count at which the orbits of a grid of pixels escape,
+//! with an atomic maximum in a storage buffer that is read back on a later
+//! frame, and [`Uniforms::max_iter`] is lowered to leave [`HEADROOM`] above
+//! it. Views without late escaping orbits then don't pay for iterations they
+//! don't need, e.g. for the points inside the set whose cycles aren't
+//! detected, while the maximum the user chose is kept as a ceiling.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferAsyncError,
+    BufferBindingType, BufferDescriptor, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, MapMode, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PollType, Queue, ShaderStages,
+};
+
+use crate::{
+    AppState, MandelbrotRenderer, Uniforms,
+    stats::{INSIDE_STRIDE, WORKGROUP_SIZE},
+};
+use mandelbrot_explorer_wgpu::renderer::same_orbits;
+
+/// How many times the highest escaping iteration count the maximum
+/// iterations are tuned to, since the pixels between the measured ones can
+/// escape later.
+const HEADROOM: u32 = 2;
+
+/// The fewest maximum iterations the tuning lowers to.
+const MIN_MAX_ITER: u32 = 64;
+
+/// Resources for measuring the highest escaping iteration count.
+#[derive(Debug)]
+pub struct AutoMaxIter {
+    pipeline: ComputePipeline,
+    /// The atomic maximum the compute pass writes to.
+    max_escape_iter: Buffer,
+    max_escape_iter_bind_group: BindGroup,
+    /// The maximum copied for reading it back.
+    readback: Buffer,
+    /// The result of mapping the `readback` buffer, while a measurement is in
+    /// flight.
+    pending: Option<Receiver<Result<(), BufferAsyncError>>>,
+    /// The highest escaping iteration count of the last finished
+    /// measurement, until it's applied in the same frame.
+    measured: Option<u32>,
+    /// The uniforms of the last measurement, so that a still view is
+    /// measured once.
+    uniforms: Option<Uniforms>,
+    /// The maximum iterations chosen by the user, which aren't exceeded.
+    ceiling: u32,
+    /// The maximum iterations last tuned to, to tell when the user changes
+    /// them.
+    tuned: u32,
+}
+
+impl AutoMaxIter {
+    /// Creates a new [`AutoMaxIter`] measuring the orbits rendered by the
+    /// `renderer`, tuning at most to `max_iter`.
+    pub fn new(
+        device: &Device,
+        renderer: &MandelbrotRenderer,
+        max_iter: u32,
+    ) -> Self {
+        let layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Max Escape Iter Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Max Escape Iter Pipeline Layout"),
+                bind_group_layouts: &[renderer.bind_group_layout(), &layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("cs_max_escape_iter"),
+                layout: Some(&pipeline_layout),
+                module: renderer.shader(),
+                entry_point: Some("cs_max_escape_iter"),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            });
+
+        let max_escape_iter = device.create_buffer(&BufferDescriptor {
+            label: Some("Max Escape Iter Buffer"),
+            size: size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE
+                | BufferUsages::COPY_SRC
+                | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let max_escape_iter_bind_group =
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Max Escape Iter Bind Group"),
+                layout: &layout,
+                entries: &[BindGroupEntry {
+                    binding: 9,
+                    resource: max_escape_iter.as_entire_binding(),
+                }],
+            });
+        let readback = device.create_buffer(&BufferDescriptor {
+            label: Some("Max Escape Iter Readback Buffer"),
+            size: size_of::<u32>() as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            max_escape_iter,
+            max_escape_iter_bind_group,
+            readback,
+            pending: None,
+            measured: None,
+            uniforms: None,
+            ceiling: max_iter,
+            tuned: max_iter,
+        }
+    }
+
+    /// Whether a measurement is in flight.
+    pub const fn is_measuring(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Submits a measurement of the pixels of an image of `size`, with the
+    /// [`Uniforms`] bound by the `uniforms` bind group, and starts mapping
+    /// its result.
+    fn submit(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        uniforms: &BindGroup,
+        size: (u32, u32),
+    ) {
+        let (width, height) = (
+            size.0.div_ceil(INSIDE_STRIDE),
+            size.1.div_ceil(INSIDE_STRIDE),
+        );
+        let mut encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Max Escape Iter Encoder"),
+            });
+        encoder.clear_buffer(&self.max_escape_iter, 0, None);
+        {
+            let mut compute_pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("Max Escape Iter Compute Pass"),
+                    timestamp_writes: None,
+                });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, uniforms, &[]);
+            compute_pass.set_bind_group(
+                1,
+                &self.max_escape_iter_bind_group,
+                &[],
+            );
+            compute_pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(
+            &self.max_escape_iter,
+            0,
+            &self.readback,
+            0,
+            None,
+        );
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        self.readback.map_async(MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.pending = Some(receiver);
+    }
+
+    /// Reads back the pending measurement if it's finished, without waiting
+    /// for it. Returns whether there is no measurement in flight anymore.
+    fn receive(&mut self, device: &Device) -> bool {
+        let Some(receiver) = &self.pending else {
+            return true;
+        };
+        let _ = device.poll(PollType::Poll);
+        match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return false,
+            Ok(Ok(())) => {
+                self.measured = Some(bytemuck::pod_read_unaligned(
+                    &self.readback.get_mapped_range(..),
+                ));
+                self.readback.unmap();
+            }
+            Ok(Err(e)) => {
+                eprintln!("Failed to read the max escape iterations: {e}");
+            }
+            Err(TryRecvError::Disconnected) => {
+                eprintln!(
+                    "Failed to read the max escape iterations: mapping \
+                     cancelled"
+                );
+            }
+        }
+        self.pending = None;
+        true
+    }
+}
+
+impl AppState {
+    /// Starts or stops tuning the maximum iterations automatically. Stopping
+    /// restores the maximum iterations chosen by the user.
+    pub fn toggle_auto_max_iter(&mut self) {
+        self.auto_max_iter = match self.auto_max_iter.take() {
+            Some(auto) => {
+                self.uniforms_mut().max_iter = auto.ceiling;
+                None
+            }
+            None => Some(AutoMaxIter::new(
+                &self.device,
+                &self.renderer,
+                self.uniforms().max_iter,
+            )),
+        };
+        self.view.title.1 = None;
+    }
+
+    /// Lowers or raises the maximum iterations to the last finished
+    /// measurement of the current view, or starts measuring the current view
+    /// if it changed. Must be called after the [`Uniforms`] are written.
+    pub fn update_auto_max_iter(&mut self) {
+        let size = self.render_size();
+        let Some(auto) = &mut self.auto_max_iter else {
+            return;
+        };
+        let uniforms = &mut self.view.uniforms;
+        if uniforms.max_iter != auto.tuned {
+            // Changed by the user, e.g. with a key or by a bookmark.
+            auto.ceiling = uniforms.max_iter;
+            auto.tuned = uniforms.max_iter;
+        }
+        if !auto.receive(&self.device) {
+            return;
+        }
+        let same_view = auto
+            .uniforms
+            .is_some_and(|measured| same_orbits(&measured, uniforms));
+        if let Some(measured) = auto.measured.take()
+            && same_view
+        {
+            let max_iter = measured
+                .saturating_mul(HEADROOM)
+                .clamp(MIN_MAX_ITER.min(auto.ceiling), auto.ceiling);
+            if max_iter != uniforms.max_iter {
+                uniforms.max_iter = max_iter;
+                auto.tuned = max_iter;
+                // The new maximum is written and measured next frame.
+                self.view.redraw = true;
+                return;
+            }
+        }
+        if !same_view {
+            auto.submit(
+                &self.device,
+                &self.queue,
+                self.renderer.bind_group(),
+                size,
+            );
+            auto.uniforms = Some(*uniforms);
+        }
+    }
+}
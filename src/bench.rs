@@ -0,0 +1,224 @@
+//! Benchmark mode: renders a number of frames and prints how long drawing the
+//! fractal took, measured on the GPU with timestamp queries if it supports
+//! them, or as the wall-clock time until the frame finished otherwise.
+
+use std::{
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use wgpu::{
+    Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder,
+    Device, Features, MapMode, PollType, PresentMode, QUERY_SIZE, QuerySet,
+    QuerySetDescriptor, QueryType, Queue, RenderPassTimestampWrites,
+};
+
+use crate::{AppState, Error};
+
+/// Size of the buffers the timestamps of the beginning and the end of the
+/// render pass are resolved into.
+const TIMESTAMPS_SIZE: BufferAddress = 2 * QUERY_SIZE as BufferAddress;
+
+/// The state of a running benchmark.
+#[derive(Debug)]
+pub struct Bench {
+    /// The timestamp queries, `None` if the GPU doesn't support them.
+    timestamps: Option<Timestamps>,
+    /// Number of frames to measure.
+    frames: usize,
+    /// Whether the first frame was rendered. Its duration is discarded, since
+    /// drivers may compile the shaders when they are first used.
+    warmed_up: bool,
+    /// The durations of the frames measured so far.
+    durations: Vec<Duration>,
+}
+
+/// Resources for measuring the duration of the render pass on the GPU.
+#[derive(Debug)]
+struct Timestamps {
+    /// The timestamps of the beginning and the end of the render pass.
+    query_set: QuerySet,
+    /// Buffer the timestamps are resolved into.
+    resolve_buffer: Buffer,
+    /// Buffer the resolved timestamps are copied to, to be read on the CPU.
+    readback_buffer: Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+impl Bench {
+    /// Creates a new [`Bench`] measuring the given number of `frames`, with
+    /// timestamp queries if the `device` supports them.
+    pub fn new(device: &Device, queue: &Queue, frames: u32) -> Self {
+        let timestamps = device
+            .features()
+            .contains(Features::TIMESTAMP_QUERY)
+            .then(|| Timestamps::new(device, queue));
+        if timestamps.is_none() {
+            eprintln!(
+                "The GPU doesn't support timestamp queries, measuring the \
+                 wall-clock time of the frames instead"
+            );
+        }
+        Self {
+            timestamps,
+            frames: frames.max(1) as usize,
+            warmed_up: false,
+            durations: Vec::with_capacity(frames as usize),
+        }
+    }
+
+    /// Returns the timestamp writes of the render pass drawing the fractal,
+    /// `None` if the GPU doesn't support them.
+    #[must_use]
+    pub fn timestamp_writes(&self) -> Option<RenderPassTimestampWrites<'_>> {
+        self.timestamps
+            .as_ref()
+            .map(|timestamps| RenderPassTimestampWrites {
+                query_set: &timestamps.query_set,
+                beginning_of_pass_write_index: Some(0),
+                end_of_pass_write_index: Some(1),
+            })
+    }
+
+    /// Records the commands copying the timestamps written by the render pass
+    /// to the buffer they are read from, after the render pass.
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        let Some(timestamps) = &self.timestamps else {
+            return;
+        };
+        encoder.resolve_query_set(
+            &timestamps.query_set,
+            0..2,
+            &timestamps.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(
+            &timestamps.resolve_buffer,
+            0,
+            &timestamps.readback_buffer,
+            0,
+            TIMESTAMPS_SIZE,
+        );
+    }
+
+    /// Prints the minimum, average and maximum of the measured durations.
+    fn print_results(&self) {
+        let method = if self.timestamps.is_some() {
+            "GPU timestamps"
+        } else {
+            "wall-clock time"
+        };
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let min = self.durations.iter().min().copied().unwrap_or_default();
+        let max = self.durations.iter().max().copied().unwrap_or_default();
+        let total: Duration = self.durations.iter().sum();
+        #[allow(clippy::cast_possible_truncation, reason = "few frames")]
+        let avg = total / self.durations.len().max(1) as u32;
+        println!(
+            "{frames} frames ({method}): min {min:.3} ms, avg {avg:.3} ms, \
+             max {max:.3} ms",
+            frames = self.durations.len(),
+            min = ms(min),
+            avg = ms(avg),
+            max = ms(max),
+        );
+    }
+}
+
+impl Timestamps {
+    /// Creates the query set and the buffers for the two timestamps.
+    fn new(device: &Device, queue: &Queue) -> Self {
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("Timestamp Query Set"),
+            ty: QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Timestamp Readback Buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+        }
+    }
+
+    /// Waits for the submitted frame to finish and returns the duration of
+    /// its render pass.
+    fn read(&self, device: &Device) -> Result<Duration, Error> {
+        let (sender, receiver) = mpsc::channel();
+        self.readback_buffer
+            .map_async(MapMode::Read, .., move |result| {
+                let _ = sender.send(result);
+            });
+        device.poll(PollType::Wait)?;
+        receiver.recv().map_err(|_| Error::BufferMapCancelled)??;
+
+        let [begin, end]: [u64; 2] = bytemuck::pod_read_unaligned(
+            &self.readback_buffer.get_mapped_range(..),
+        );
+        self.readback_buffer.unmap();
+
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "frame durations are far from the limits"
+        )]
+        let nanos =
+            (end.saturating_sub(begin) as f64 * f64::from(self.period)) as u64;
+        Ok(Duration::from_nanos(nanos))
+    }
+}
+
+impl AppState {
+    /// Starts benchmarking the next `frames` frames. Vsync is disabled if
+    /// possible, so that the frames aren't limited by the refresh rate.
+    pub fn start_bench(&mut self, frames: u32) {
+        self.config.present_mode = PresentMode::AutoNoVsync;
+        self.surface.configure(&self.device, &self.config);
+        self.bench = Some(Bench::new(&self.device, &self.queue, frames));
+    }
+
+    /// Measures the frame whose rendering started at `started`, waiting for
+    /// the GPU to finish it. Returns whether the benchmark is done, after
+    /// printing its results.
+    pub fn measure_bench_frame(&mut self, started: Instant) -> bool {
+        let Some(bench) = &mut self.bench else {
+            return false;
+        };
+        let duration = match &bench.timestamps {
+            Some(timestamps) => timestamps.read(&self.device),
+            None => self
+                .device
+                .poll(PollType::Wait)
+                .map(|_| started.elapsed())
+                .map_err(Error::from),
+        };
+        match duration {
+            Ok(_) if !bench.warmed_up => bench.warmed_up = true,
+            Ok(duration) => bench.durations.push(duration),
+            Err(e) => {
+                eprintln!("Failed to measure the frame, stopping: {e}");
+                bench.print_results();
+                return true;
+            }
+        }
+        if bench.durations.len() < bench.frames {
+            return false;
+        }
+        bench.print_results();
+        true
+    }
+}
@@ -0,0 +1,98 @@
+//! Saving interesting views to a bookmarks file and restoring them later.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Uniforms};
+
+/// Path of the file the bookmarks are stored in.
+const BOOKMARKS_PATH: &str = "bookmarks.json";
+
+/// A saved view, restorable into the [`Uniforms`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// See [`Uniforms::offset`].
+    pub offset: [f64; 2],
+    /// See [`Uniforms::zooms`].
+    pub zooms: f64,
+    /// See [`Uniforms::max_iter`].
+    pub max_iter: u32,
+    /// See [`Uniforms::is_mandelbrot`].
+    pub is_mandelbrot: bool,
+    /// See [`Uniforms::rotate_colors`].
+    pub rotate_colors: bool,
+    /// See [`Uniforms::fractal_type`].
+    #[serde(default)]
+    pub fractal_type: u32,
+    /// See [`Uniforms::power`].
+    #[serde(default = "default_power")]
+    pub power: f32,
+    /// See [`Uniforms::rotation`].
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+/// The [`Bookmark::power`] of bookmarks saved before it was introduced.
+const fn default_power() -> f32 {
+    2.0
+}
+
+impl Bookmark {
+    /// Creates a [`Bookmark`] of the view described by the `uniforms`.
+    #[must_use]
+    pub fn new(uniforms: &Uniforms) -> Self {
+        Self {
+            offset: uniforms.offset,
+            zooms: uniforms.zooms,
+            max_iter: uniforms.max_iter,
+            is_mandelbrot: uniforms.is_mandelbrot == 1.0,
+            rotate_colors: uniforms.rotate_colors == 1.0,
+            fractal_type: uniforms.fractal_type,
+            power: uniforms.power,
+            rotation: uniforms.rotation,
+        }
+    }
+
+    /// Restores the bookmarked view into the `uniforms`.
+    pub fn apply(&self, uniforms: &mut Uniforms) {
+        uniforms.offset = self.offset;
+        uniforms.offset_lo = [0.0, 0.0];
+        uniforms.zooms = self.zooms;
+        uniforms.max_iter = self.max_iter;
+        uniforms.is_mandelbrot = f32::from(u8::from(self.is_mandelbrot));
+        uniforms.rotate_colors = f32::from(u8::from(self.rotate_colors));
+        uniforms.fractal_type = self.fractal_type;
+        uniforms.power = self.power;
+        uniforms.rotation = self.rotation;
+    }
+}
+
+/// Appends the view described by the `uniforms` to the bookmarks file.
+pub fn save_bookmark(uniforms: &Uniforms) -> Result<(), Error> {
+    let mut bookmarks = read_bookmarks(Path::new(BOOKMARKS_PATH))?;
+    bookmarks.push(Bookmark::new(uniforms));
+    let json = serde_json::to_string_pretty(&bookmarks)?;
+    fs::write(BOOKMARKS_PATH, json)?;
+    Ok(())
+}
+
+/// Loads all the saved bookmarks. A missing or malformed file is treated as
+/// having no bookmarks.
+#[must_use]
+pub fn load_bookmarks() -> Vec<Bookmark> {
+    read_bookmarks(Path::new(BOOKMARKS_PATH)).unwrap_or_else(|e| {
+        eprintln!("Failed to load bookmarks: {e}");
+        Vec::new()
+    })
+}
+
+/// Reads the bookmarks from the file at `path`, returning an empty list if
+/// the file doesn't exist yet.
+fn read_bookmarks(path: &Path) -> Result<Vec<Bookmark>, Error> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,350 @@
+//! Command-line arguments setting the initial view.
+
+use std::{
+    env, fmt::Display, ops::RangeInclusive, path::PathBuf, str::FromStr,
+};
+
+use clap::{Parser, ValueEnum};
+use wgpu::{Backends, PowerPreference};
+
+use crate::{
+    AdapterOptions, BURNING_SHIP, MAX_ZOOMS, MULTIBROT, NEWTON, Precision,
+    TRICORN, Uniforms, clamp_max_iter, export::MAX_EXPORT_SCALE, share,
+};
+
+/// Range the initial center coordinates are clamped to.
+const CENTER_RANGE: RangeInclusive<f64> = -4.0..=4.0;
+
+/// Range the initial window width and height are clamped to, in physical
+/// pixels. The largest textures GPUs support are usually this big.
+const WINDOW_SIZE_RANGE: RangeInclusive<u32> = 1..=16384;
+
+/// Real-time Mandelbrot and Julia set explorer.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Real part of the initial center of the view.
+    #[arg(long, allow_negative_numbers = true)]
+    pub center_x: Option<f64>,
+    /// Imaginary part of the initial center of the view.
+    #[arg(long, allow_negative_numbers = true)]
+    pub center_y: Option<f64>,
+    /// Initial magnification, as shown in the window title, which grows when
+    /// zooming in.
+    #[arg(long)]
+    pub zoom: Option<f64>,
+    /// Initial maximum number of iterations.
+    #[arg(long)]
+    pub max_iter: Option<u32>,
+    /// Initial view as a shareable string, copied with Shift+Y. The other
+    /// arguments take precedence over it.
+    #[arg(long, value_name = "STRING")]
+    pub view: Option<String>,
+    /// Start with the Julia set instead of the Mandelbrot set.
+    #[arg(long)]
+    pub julia: bool,
+    /// Fractal to start with. With `--julia` or `--julia-c`, its Julia set.
+    #[arg(long)]
+    pub fractal: Option<Fractal>,
+    /// Start with the Julia set of the given point, which stays locked
+    /// instead of following the cursor. Implies `--julia`.
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["RE", "IM"],
+        allow_negative_numbers = true
+    )]
+    pub julia_c: Option<Vec<f64>>,
+    /// Render the views listed in the given JSON file to PNG images without
+    /// opening a window, then exit.
+    #[arg(long, value_name = "VIEWS_JSON")]
+    pub headless: Option<PathBuf>,
+    /// Graphics API to render with. Any available one is used if it has no
+    /// adapter.
+    #[arg(long)]
+    pub backend: Option<Backend>,
+    /// Whether to prefer an integrated (low) or a discrete (high) GPU.
+    #[arg(long)]
+    pub power: Option<Power>,
+    /// Print the details of the GPU and the enabled features at startup.
+    #[arg(long)]
+    pub verbose: bool,
+    /// Render the given number of frames of the initial view without vsync,
+    /// print the minimum, average and maximum time the GPU took to draw them,
+    /// then exit.
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        num_args = 0..=1,
+        default_missing_value = "100"
+    )]
+    pub bench: Option<u32>,
+    /// Index of the monitor to open the window on. The available monitors
+    /// are listed when it's given.
+    #[arg(long, value_name = "INDEX")]
+    pub monitor: Option<usize>,
+    /// Start fullscreen, in the mode set in the config file.
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Render in HDR if the display supports it, with the white of the
+    /// palettes the given number of nits bright.
+    #[arg(
+        long,
+        value_name = "NITS",
+        num_args = 0..=1,
+        default_missing_value = "203"
+    )]
+    pub hdr: Option<f32>,
+    /// Reload the shaders from the source tree when they change, for
+    /// development.
+    #[arg(long)]
+    pub dev: bool,
+    /// Initial width of the window, in physical pixels.
+    #[arg(long, value_name = "PIXELS", requires = "height")]
+    pub width: Option<u32>,
+    /// Initial height of the window, in physical pixels.
+    #[arg(long, value_name = "PIXELS", requires = "width")]
+    pub height: Option<u32>,
+    /// Title of the window, shown before the details of the view.
+    #[arg(long)]
+    pub title: Option<String>,
+    /// Image of a gradient to color with instead of the built-in palettes,
+    /// e.g. a palette exported by another fractal program. Its colors are
+    /// read from left to right, or from top to bottom if it's taller than
+    /// wide.
+    #[arg(long, value_name = "IMAGE")]
+    pub palette: Option<PathBuf>,
+    /// How many times larger than the window the high-resolution exports are,
+    /// overriding the config file.
+    #[arg(long, value_name = "FACTOR")]
+    pub export_scale: Option<u32>,
+}
+
+/// Fractals that can be chosen with `--fractal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Fractal {
+    Mandelbrot,
+    /// The Julia set of the Mandelbrot set, like `--julia`.
+    Julia,
+    #[value(alias = "burningship")]
+    BurningShip,
+    Tricorn,
+    Multibrot,
+    Newton,
+}
+
+impl Fractal {
+    /// Returns the [`Uniforms::fractal_type`] of the fractal.
+    #[must_use]
+    const fn fractal_type(self) -> u32 {
+        match self {
+            Self::Mandelbrot | Self::Julia => 0,
+            Self::BurningShip => BURNING_SHIP,
+            Self::Tricorn => TRICORN,
+            Self::Multibrot => MULTIBROT,
+            Self::Newton => NEWTON,
+        }
+    }
+}
+
+/// Graphics APIs that can be chosen with `--backend`.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Backend {
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+}
+
+/// GPU power preferences that can be chosen with `--power`.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Power {
+    Low,
+    High,
+}
+
+impl Args {
+    /// Returns the initial [`Uniforms`]: the given `uniforms` with the
+    /// environment variables (see [`apply_env`]) and then the arguments
+    /// applied. Out-of-range values are clamped with a warning.
+    #[must_use]
+    pub fn initial_uniforms(&self, mut uniforms: Uniforms) -> Uniforms {
+        apply_env(&mut uniforms);
+        if let Some(view) = &self.view
+            && let Err(e) = share::decode_view(view, &mut uniforms)
+        {
+            eprintln!("Warning: ignoring --view: {e}");
+        }
+        if let Some(x) = self.center_x {
+            uniforms.offset[0] = clamp_center("--center-x", x);
+        }
+        if let Some(y) = self.center_y {
+            uniforms.offset[1] = clamp_center("--center-y", y);
+        }
+        if let Some(zoom) = self.zoom {
+            uniforms.zooms = zooms_from_zoom("--zoom", zoom);
+        }
+        if let Some(max_iter) = self.max_iter {
+            uniforms.max_iter = positive_max_iter("--max-iter", max_iter);
+        }
+        if let Some(fractal) = self.fractal {
+            uniforms.fractal_type = fractal.fractal_type();
+            uniforms.is_mandelbrot =
+                if fractal == Fractal::Julia { 0.0 } else { 1.0 };
+        }
+        if let Some(&[re, im]) = self.julia_c.as_deref() {
+            uniforms.julia_c =
+                [clamp_center("--julia-c", re), clamp_center("--julia-c", im)];
+        }
+        if self.julia || self.julia_c.is_some() {
+            uniforms.is_mandelbrot = 0.0;
+        }
+        uniforms
+    }
+
+    /// Whether the Julia constant given by `--julia-c` has to be locked, see
+    /// [`View::julia_locked`].
+    ///
+    /// [`View::julia_locked`]: crate::View::julia_locked
+    #[must_use]
+    pub const fn locks_julia_c(&self) -> bool {
+        self.julia_c.is_some()
+    }
+
+    /// Returns the initial window size given by `--width` and `--height`, in
+    /// physical pixels, clamped to [`WINDOW_SIZE_RANGE`] with a warning.
+    #[must_use]
+    pub fn window_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.width.zip(self.height)?;
+        Some((
+            clamp_window_size("--width", width),
+            clamp_window_size("--height", height),
+        ))
+    }
+
+    /// Returns the export scale given by `--export-scale`, clamped to 1 to
+    /// [`MAX_EXPORT_SCALE`] with a warning.
+    #[must_use]
+    pub fn export_scale(&self) -> Option<u32> {
+        let scale = self.export_scale?;
+        let clamped = scale.clamp(1, MAX_EXPORT_SCALE);
+        if clamped != scale {
+            eprintln!(
+                "Warning: --export-scale {scale} is out of range, using \
+                 {clamped}"
+            );
+        }
+        Some(clamped)
+    }
+
+    /// Returns the [`AdapterOptions`] chosen by the `--backend` and `--power`
+    /// arguments.
+    #[must_use]
+    pub fn adapter_options(&self) -> AdapterOptions {
+        let backends = match self.backend {
+            None => Backends::default(),
+            Some(Backend::Vulkan) => Backends::VULKAN,
+            Some(Backend::Dx12) => Backends::DX12,
+            Some(Backend::Metal) => Backends::METAL,
+            Some(Backend::Gl) => Backends::GL,
+        };
+        let power_preference = match self.power {
+            None => PowerPreference::default(),
+            Some(Power::Low) => PowerPreference::LowPower,
+            Some(Power::High) => PowerPreference::HighPerformance,
+        };
+        AdapterOptions {
+            backends,
+            power_preference,
+            verbose: self.verbose,
+        }
+    }
+}
+
+/// Applies the `MANDEL_CENTER_X`, `MANDEL_CENTER_Y`, `MANDEL_ZOOM` and
+/// `MANDEL_MAX_ITER` environment variables to the `uniforms`, like the
+/// arguments of the same names, for when arguments can't be passed, e.g. in
+/// containers. Invalid ones are ignored with a warning.
+fn apply_env(uniforms: &mut Uniforms) {
+    if let Some(x) = env_var("MANDEL_CENTER_X") {
+        uniforms.offset[0] = clamp_center("MANDEL_CENTER_X", x);
+    }
+    if let Some(y) = env_var("MANDEL_CENTER_Y") {
+        uniforms.offset[1] = clamp_center("MANDEL_CENTER_Y", y);
+    }
+    if let Some(zoom) = env_var("MANDEL_ZOOM") {
+        uniforms.zooms = zooms_from_zoom("MANDEL_ZOOM", zoom);
+    }
+    if let Some(max_iter) = env_var("MANDEL_MAX_ITER") {
+        uniforms.max_iter = positive_max_iter("MANDEL_MAX_ITER", max_iter);
+    }
+}
+
+/// Parses the environment variable `name`, if it's set, warning if it can't
+/// be parsed.
+fn env_var<T: FromStr<Err: Display>>(name: &str) -> Option<T> {
+    let value = env::var(name).ok()?;
+    value
+        .trim()
+        .parse()
+        .inspect_err(|e| eprintln!("Warning: ignoring {name}={value:?}: {e}"))
+        .ok()
+}
+
+/// Clamps the center coordinate given by the argument `name` to
+/// [`CENTER_RANGE`], warning if it's out of range.
+fn clamp_center(name: &str, value: f64) -> f64 {
+    if value.is_nan() {
+        eprintln!("Warning: {name} is not a number, using 0");
+        return 0.0;
+    }
+    let clamped = value.clamp(*CENTER_RANGE.start(), *CENTER_RANGE.end());
+    if clamped != value {
+        eprintln!("Warning: {name} {value} is out of range, using {clamped}");
+    }
+    clamped
+}
+
+/// Clamps the window dimension given by the argument `name` to
+/// [`WINDOW_SIZE_RANGE`], warning if it's out of range.
+fn clamp_window_size(name: &str, value: u32) -> u32 {
+    let clamped =
+        value.clamp(*WINDOW_SIZE_RANGE.start(), *WINDOW_SIZE_RANGE.end());
+    if clamped != value {
+        eprintln!("Warning: {name} {value} is out of range, using {clamped}");
+    }
+    clamped
+}
+
+/// Converts the magnification `zoom` given by the argument `name` to
+/// [`Uniforms::zooms`], clamping it to the range supported with
+/// [`Precision::Double`] with a warning.
+fn zooms_from_zoom(name: &str, zoom: f64) -> f64 {
+    let range = Precision::Double.min_zooms()..=MAX_ZOOMS;
+    if zoom.is_nan() || zoom <= 0.0 {
+        eprintln!("Warning: {name} must be positive, using the default");
+        return Uniforms::default().zooms;
+    }
+    let zooms = Uniforms::zooms_from_magnification(zoom);
+    let clamped = zooms.clamp(*range.start(), *range.end());
+    if clamped != zooms {
+        eprintln!(
+            "Warning: {name} {zoom} is out of range, using {}",
+            Uniforms {
+                zooms: clamped,
+                ..Uniforms::default()
+            }
+            .magnification(),
+        );
+    }
+    clamped
+}
+
+/// Clamps the `max_iter` given by the argument `name` with
+/// [`clamp_max_iter`], warning if it's zero.
+fn positive_max_iter(name: &str, max_iter: u32) -> u32 {
+    if max_iter == 0 {
+        eprintln!("Warning: {name} must be positive, using 1");
+    }
+    clamp_max_iter(max_iter)
+}
@@ -0,0 +1,111 @@
+//! Copying the current view to the clipboard as text and pasting it back.
+//!
+//! A view is encoded as `re,im,zooms,max_iter,fractal_type`, e.g.
+//! `-0.75,0.1,-20,1500,0`, see [`format_view`] and [`parse_view`], or as a
+//! shareable string, see [`share`](crate::share).
+
+use std::fmt;
+
+use crate::{AppState, Error, FRACTAL_NAMES, Uniforms, share};
+
+/// The system clipboard. On some platforms the copied text is only available
+/// while it's alive, so it's kept around after the first use.
+pub struct Clipboard(arboard::Clipboard);
+
+impl fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clipboard").finish_non_exhaustive()
+    }
+}
+
+impl AppState {
+    /// Copies the current view to the clipboard.
+    pub fn copy_view(&mut self) {
+        let text = format_view(self.uniforms());
+        match self.clipboard().and_then(|c| Ok(c.0.set_text(&text)?)) {
+            Ok(()) => eprintln!("Copied view: {text}"),
+            Err(e) => eprintln!("Failed to copy view: {e}"),
+        }
+    }
+
+    /// Copies the current view to the clipboard as a shareable string.
+    pub fn copy_share_string(&mut self) {
+        let text = share::encode_view(self.uniforms());
+        match self.clipboard().and_then(|c| Ok(c.0.set_text(&text)?)) {
+            Ok(()) => eprintln!("Copied shareable view: {text}"),
+            Err(e) => eprintln!("Failed to copy view: {e}"),
+        }
+    }
+
+    /// Applies the view from the clipboard, if it contains one.
+    pub fn paste_view(&mut self) {
+        self.save_history();
+        let result = self
+            .clipboard_text()
+            .and_then(|text| parse_view(&text, &mut self.view.uniforms));
+        match result {
+            Ok(()) => {
+                // Clamp the pasted zoom to the supported range.
+                self.zoom(0.0);
+                self.view.redraw = true;
+            }
+            Err(e) => eprintln!("Failed to paste view: {e}"),
+        }
+    }
+
+    /// Returns the text in the clipboard.
+    pub fn clipboard_text(&mut self) -> Result<String, Error> {
+        Ok(self.clipboard()?.0.get_text()?)
+    }
+
+    /// Returns the [`Clipboard`], connecting to it on the first call.
+    fn clipboard(&mut self) -> Result<&mut Clipboard, Error> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(Clipboard(arboard::Clipboard::new()?));
+        }
+        Ok(self.clipboard.as_mut().expect("just initialized"))
+    }
+}
+
+/// Encodes the view described by the `uniforms` as text.
+#[must_use]
+pub fn format_view(uniforms: &Uniforms) -> String {
+    let [re, im] = uniforms.offset;
+    format!(
+        "{re},{im},{zooms},{max_iter},{fractal_type}",
+        zooms = uniforms.zooms,
+        max_iter = uniforms.max_iter,
+        fractal_type = uniforms.fractal_type,
+    )
+}
+
+/// Decodes a view encoded by [`format_view`] or [`share::encode_view`] into
+/// the `uniforms`. Leaves the `uniforms` untouched if the `text` is invalid.
+pub fn parse_view(text: &str, uniforms: &mut Uniforms) -> Result<(), Error> {
+    // Shareable strings are base64, which has no commas.
+    if !text.contains(',') {
+        return share::decode_view(text, uniforms);
+    }
+    let invalid = || Error::InvalidView(text.to_owned());
+    let mut parts = text.trim().split(',').map(str::trim);
+    let mut next = || parts.next().ok_or_else(invalid);
+    let re: f64 = next()?.parse().map_err(|_| invalid())?;
+    let im: f64 = next()?.parse().map_err(|_| invalid())?;
+    let zooms: f64 = next()?.parse().map_err(|_| invalid())?;
+    let max_iter: u32 = next()?.parse().map_err(|_| invalid())?;
+    let fractal_type: u32 = next()?.parse().map_err(|_| invalid())?;
+    if parts.next().is_some()
+        || ![re, im, zooms].iter().all(|x| x.is_finite())
+        || max_iter == 0
+        || fractal_type as usize >= FRACTAL_NAMES.len()
+    {
+        return Err(invalid());
+    }
+
+    uniforms.offset = [re, im];
+    uniforms.offset_lo = [0.0, 0.0];
+    uniforms.zooms = zooms;
+    uniforms.max_iter = max_iter;
+    uniforms.fractal_type = fractal_type;
+    Ok(())
+}
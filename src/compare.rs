@@ -0,0 +1,148 @@
+//! Precision comparison: the right half of the window is rendered in plain
+//! `f32` over the `f64` image, showing how the view falls apart at depths
+//! `f32` can't resolve, which is why the zoom is clamped without `f64`.
+
+use wgpu::{
+    BindGroup, Buffer, CommandEncoder, Device, LoadOp, Operations,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, StoreOp,
+    TextureFormat, TextureView,
+};
+
+use crate::AppState;
+use mandelbrot_explorer_wgpu::renderer::{
+    Gradient, Precision, Uniforms, create_render_pipeline,
+    create_render_pipeline_layout, create_shader,
+    create_uniforms_bind_group_layout, create_uniforms_buffer, write_uniforms,
+};
+
+/// The resources for rendering the `f32` half of the comparison.
+#[derive(Debug)]
+pub struct PrecisionComparison {
+    /// Renders the fractal in `f32`, never in double-double precision.
+    pipeline: RenderPipeline,
+    /// The [`Uniforms`] of the `f32` half, see
+    /// [`AppState::comparison_uniforms`].
+    uniforms_buffer: Buffer,
+    uniforms_bind_group: BindGroup,
+}
+
+impl PrecisionComparison {
+    /// Creates a new [`PrecisionComparison`] rendering into targets of the
+    /// given `format`, with the palettes looping through the `gradient`.
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        gradient: &Gradient,
+    ) -> Self {
+        let shader = create_shader(device, Precision::Single);
+        let bind_group_layout = create_uniforms_bind_group_layout(device);
+        let pipeline_layout =
+            create_render_pipeline_layout(device, &bind_group_layout);
+        let pipeline = create_render_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            format,
+            1,
+        );
+        let (uniforms_buffer, uniforms_bind_group) = create_uniforms_buffer(
+            device,
+            &bind_group_layout,
+            Precision::Single,
+            gradient,
+            "Comparison Uniforms",
+        );
+
+        Self {
+            pipeline,
+            uniforms_buffer,
+            uniforms_bind_group,
+        }
+    }
+}
+
+impl AppState {
+    /// Starts or stops comparing `f64` in the left half of the window with
+    /// `f32` in the right one. Needs a GPU supporting `f64`.
+    pub fn toggle_precision_comparison(&mut self) {
+        if self.comparison.take().is_some() {
+            return;
+        }
+        if self.renderer.precision() == Precision::Single {
+            eprintln!(
+                "The GPU doesn't support f64, there's nothing to compare"
+            );
+            return;
+        }
+        self.comparison = Some(PrecisionComparison::new(
+            &self.device,
+            self.config.format,
+            self.renderer.gradient(),
+        ));
+        eprintln!("Comparing f64 (left) with f32 (right)");
+    }
+
+    /// Returns the [`Uniforms`] of the `f32` half, the current view rendered
+    /// at the size of the [`AppState::viewport`] with one sample per pixel.
+    #[must_use]
+    fn comparison_uniforms(&self) -> Uniforms {
+        let [x, y, width, height] = self.viewport();
+        Uniforms {
+            resolution: [width.into(), height.into()],
+            // Fragment positions are relative to the whole window.
+            tile_offset: [-f64::from(x), -f64::from(y)],
+            sample_count: 1,
+            ..*self.uniforms()
+        }
+    }
+
+    /// Writes the view to the buffer of the comparison, if it's enabled.
+    pub fn update_precision_comparison(&self) {
+        if let Some(comparison) = &self.comparison {
+            write_uniforms(
+                &self.queue,
+                Precision::Single,
+                &comparison.uniforms_buffer,
+                &self.comparison_uniforms(),
+            );
+        }
+    }
+
+    /// Records a render pass drawing the `f32` half of the comparison over
+    /// the right half of the [`AppState::viewport`] of the already rendered
+    /// `view`, if it's enabled.
+    pub fn draw_precision_comparison(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+    ) {
+        let Some(comparison) = &self.comparison else {
+            return;
+        };
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Comparison Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+
+        let [x, y, width, height] = self.viewport();
+        render_pass.set_scissor_rect(
+            x + width / 2,
+            y,
+            width - width / 2,
+            height,
+        );
+        render_pass.set_pipeline(&comparison.pipeline);
+        render_pass.set_bind_group(0, &comparison.uniforms_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,181 @@
+//! User settings loaded from a TOML file at startup.
+
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    Error, FullscreenMode, PALETTE_NAMES, Uniforms, clamp_max_iter,
+    export::MAX_EXPORT_SCALE, keymap::KeyMap,
+};
+use mandelbrot_explorer_wgpu::renderer::{
+    DEFAULT_PASS_ITERS, MAX_SCREENSHOT_SCALE,
+};
+
+/// Path of the file the settings are loaded from.
+const CONFIG_PATH: &str = "config.toml";
+
+/// User settings. Every field is optional in the file, missing ones keep their
+/// default values.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How far the view pans per second, relative to the zoom factor.
+    pub move_speed: f64,
+    /// Multiplier of the zoom speed of the mouse wheel, the zoom keys and the
+    /// gamepad triggers.
+    pub zoom_speed: f64,
+    /// How quickly the view slows down when gliding after a drag, as the rate
+    /// per second its speed decays at.
+    pub pan_friction: f64,
+    /// Maximum number of iterations the view starts and is reset with.
+    pub max_iter: u32,
+    /// Name of the palette the view starts and is reset with, see
+    /// [`PALETTE_NAMES`].
+    pub palette: String,
+    /// How `F11` makes the window fullscreen.
+    pub fullscreen: FullscreenMode,
+    /// Whether the view starts with dithering, see [`Uniforms::dithering`].
+    pub dithering: bool,
+    /// Color of the areas of the window the fractal isn't drawn on, as sRGB
+    /// components in [0, 1].
+    pub background: [f64; 3],
+    /// Number of iterations progressive rendering continues the orbits for
+    /// each frame, and views with more iterations are rendered in slices of.
+    /// Lower values keep slow GPUs within their time limits.
+    pub progressive_iters: u32,
+    /// How many times larger than their size the screenshots and exports are
+    /// rendered and averaged down, independently of the supersampling of the
+    /// window.
+    pub screenshot_scale: u32,
+    /// How many times larger than the window the high-resolution exports
+    /// are, up to [`MAX_EXPORT_SCALE`].
+    pub export_scale: u32,
+    /// The key bindings, see [`KeyMap`].
+    pub keys: KeyMap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let uniforms = Uniforms::default();
+        Self {
+            move_speed: 0.3,
+            zoom_speed: 1.0,
+            pan_friction: 4.0,
+            max_iter: uniforms.max_iter,
+            palette: PALETTE_NAMES[uniforms.palette as usize].to_owned(),
+            fullscreen: FullscreenMode::default(),
+            dithering: uniforms.dithering == 1.0,
+            background: [0.0; 3],
+            progressive_iters: DEFAULT_PASS_ITERS,
+            screenshot_scale: 1,
+            export_scale: 4,
+            keys: KeyMap::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the settings from the config file. A missing file is treated as
+    /// having default settings, as is a malformed one after reporting it.
+    #[must_use]
+    pub fn load() -> Self {
+        match read_config(Path::new(CONFIG_PATH)) {
+            Ok(config) => config.validated(),
+            Err(e) => {
+                eprintln!("Failed to load {CONFIG_PATH}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Returns the [`Uniforms`] the view starts and is reset with.
+    #[must_use]
+    pub fn default_uniforms(&self) -> Uniforms {
+        Uniforms {
+            max_iter: self.max_iter,
+            palette: self.palette_index(),
+            dithering: f32::from(u8::from(self.dithering)),
+            ..Uniforms::default()
+        }
+    }
+
+    /// Returns the index of the [`Config::palette`] in [`PALETTE_NAMES`].
+    #[must_use]
+    fn palette_index(&self) -> u32 {
+        PALETTE_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(&self.palette))
+            .map_or(0, |index| index as u32)
+    }
+
+    /// Replaces invalid values with the defaults, warning about each of them.
+    fn validated(mut self) -> Self {
+        let default = Self::default();
+        if !(self.move_speed.is_finite() && self.move_speed > 0.0) {
+            eprintln!("Warning: move_speed must be positive, using default");
+            self.move_speed = default.move_speed;
+        }
+        if !(self.zoom_speed.is_finite() && self.zoom_speed > 0.0) {
+            eprintln!("Warning: zoom_speed must be positive, using default");
+            self.zoom_speed = default.zoom_speed;
+        }
+        if !(self.pan_friction.is_finite() && self.pan_friction > 0.0) {
+            eprintln!("Warning: pan_friction must be positive, using default");
+            self.pan_friction = default.pan_friction;
+        }
+        if self.max_iter == 0 {
+            eprintln!("Warning: max_iter must be positive, using default");
+            self.max_iter = default.max_iter;
+        }
+        self.max_iter = clamp_max_iter(self.max_iter);
+        if self.progressive_iters == 0 {
+            eprintln!(
+                "Warning: progressive_iters must be positive, using default"
+            );
+            self.progressive_iters = default.progressive_iters;
+        }
+        if !(1..=MAX_SCREENSHOT_SCALE).contains(&self.screenshot_scale) {
+            eprintln!(
+                "Warning: screenshot_scale must be between 1 and \
+                 {MAX_SCREENSHOT_SCALE}, using default"
+            );
+            self.screenshot_scale = default.screenshot_scale;
+        }
+        if !(1..=MAX_EXPORT_SCALE).contains(&self.export_scale) {
+            eprintln!(
+                "Warning: export_scale must be between 1 and \
+                 {MAX_EXPORT_SCALE}, using default"
+            );
+            self.export_scale = default.export_scale;
+        }
+        if !PALETTE_NAMES
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&self.palette))
+        {
+            eprintln!(
+                "Warning: unknown palette {:?}, expected one of {PALETTE_NAMES:?}",
+                self.palette,
+            );
+            self.palette = default.palette;
+        }
+        if !self.background.iter().all(|c| (0.0..=1.0).contains(c)) {
+            eprintln!(
+                "Warning: background components must be in [0, 1], using \
+                 default"
+            );
+            self.background = default.background;
+        }
+        self
+    }
+}
+
+/// Reads the settings from the file at `path`, returning the defaults if the
+/// file doesn't exist.
+fn read_config(path: &Path) -> Result<Config, Error> {
+    match fs::read_to_string(path) {
+        Ok(toml) => Ok(toml::from_str(&toml)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,122 @@
+//! A crosshair at the center of the view, which zooming with the keyboard
+//! zooms into, to line features up with before zooming in. It's drawn on top
+//! of the window only, so exports never include it.
+
+use wgpu::{
+    ColorTargetState, ColorWrites, CommandEncoder, Device, FragmentState,
+    LoadOp, MultisampleState, Operations, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, StoreOp, TextureFormat, TextureView, VertexState,
+    include_wgsl,
+};
+
+use crate::AppState;
+
+/// Length of the arms of the crosshair in pixels.
+const ARM_LENGTH: u32 = 12;
+
+/// The resources for drawing the crosshair.
+#[derive(Debug)]
+pub struct Crosshair {
+    /// Draws the two lines of the crosshair.
+    pipeline: RenderPipeline,
+}
+
+impl Crosshair {
+    /// Creates a new [`Crosshair`] drawing into targets of the given
+    /// `format`.
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader =
+            device.create_shader_module(include_wgsl!("crosshair.wgsl"));
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Crosshair Pipeline Layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline =
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Crosshair Pipeline"),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    targets: &[Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                layout: Some(&pipeline_layout),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            });
+
+        Self { pipeline }
+    }
+}
+
+impl AppState {
+    /// Shows or hides the crosshair.
+    pub fn toggle_crosshair(&mut self) {
+        self.view.show_crosshair = !self.view.show_crosshair;
+    }
+
+    /// Records a render pass drawing the crosshair at the center of the
+    /// [`AppState::viewport`] on top of the already rendered `view`.
+    #[allow(clippy::cast_precision_loss, reason = "window sizes are small")]
+    pub fn draw_crosshair(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+    ) {
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Crosshair Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+
+        // The lines span the viewport, so it's shrunk to a square around the
+        // center, which must stay within the window.
+        let [x, y, width, height] = self.viewport();
+        let center = [
+            x as f32 + width as f32 / 2.0,
+            y as f32 + height as f32 / 2.0,
+        ];
+        let arm = ARM_LENGTH.min(width.min(height) / 2) as f32;
+        render_pass.set_viewport(
+            center[0] - arm,
+            center[1] - arm,
+            2.0 * arm,
+            2.0 * arm,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(&self.crosshair.pipeline);
+        render_pass.draw(0..4, 0..1);
+    }
+}
@@ -0,0 +1,19 @@
+// Draws a crosshair as two lines across the viewport, which is a small square
+// around the center of the view.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+    let ends = array(
+        vec2<f32>(-1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    return vec4<f32>(ends[vertex_index], 0.0, 1.0);
+}
+
+@fragment
+fn fg_main() -> @location(0) vec4f {
+    return vec4f(1.0);
+}
@@ -0,0 +1,84 @@
+//! Demo mode for kiosks and screensavers: the view zooms into interesting
+//! points of the Mandelbrot set endlessly, starting over at the next one when
+//! the zoom reaches the limit of `f64`.
+
+use crate::AppState;
+
+/// The points the demo zooms into, Misiurewicz points and the Seahorse
+/// Valley, which show new detail however deep the view is zoomed in.
+const DEMO_TARGETS: [[f64; 2]; 4] = [
+    [-0.743_643_887_037_151, 0.131_825_904_205_33],
+    [0.0, 1.0],
+    [-0.101_096_363_845_621_5, 0.956_286_510_809_142],
+    [-1.543_689_012_692_076_4, 0.0],
+];
+
+/// How fast the demo zooms in, in [`Uniforms::zooms`] per second.
+///
+/// [`Uniforms::zooms`]: crate::Uniforms::zooms
+const DEMO_ZOOM_RATE: f64 = 2.0;
+
+/// The state of the running demo.
+#[derive(Debug, Clone, Copy)]
+pub struct Demo {
+    /// Index of the point being zoomed into in [`DEMO_TARGETS`].
+    target: usize,
+    /// How much the view zooms in per second.
+    zoom_rate: f64,
+}
+
+impl AppState {
+    /// Starts or stops the demo.
+    pub fn toggle_demo(&mut self) {
+        if self.view.demo.is_some() {
+            self.stop_demo();
+            return;
+        }
+        self.save_history();
+        if self.uniforms().is_mandelbrot != 1.0 {
+            self.toggle_julia();
+        }
+        self.view.animation = None;
+        self.start_demo(0);
+        eprintln!("Demo started, any input stops it");
+    }
+
+    /// Stops the demo, if it's running, leaving the view where it is.
+    pub fn stop_demo(&mut self) {
+        if self.view.demo.take().is_some() {
+            eprintln!("Demo stopped");
+        }
+    }
+
+    /// Zooms the demo in for `dt` seconds, starting over at the next point
+    /// when the zoom reaches the limit of `f64`.
+    pub fn advance_demo(&mut self, dt: f64) {
+        let Some(demo) = self.view.demo else {
+            return;
+        };
+        // Past this, the orbits would be computed in slow double-double
+        // precision.
+        if self.uniforms().zooms - demo.zoom_rate * dt
+            <= self.renderer.precision().deep_zooms()
+        {
+            self.start_demo((demo.target + 1) % DEMO_TARGETS.len());
+            return;
+        }
+        self.zoom(-demo.zoom_rate * dt);
+    }
+
+    /// Resets the view to the whole Mandelbrot set centered on the point of
+    /// [`DEMO_TARGETS`] with the given index.
+    fn start_demo(&mut self, target: usize) {
+        let default = self.settings.default_uniforms();
+        let uniforms = self.uniforms_mut();
+        uniforms.fractal_type = default.fractal_type;
+        uniforms.offset = DEMO_TARGETS[target];
+        uniforms.offset_lo = [0.0, 0.0];
+        uniforms.zooms = default.zooms;
+        self.view.demo = Some(Demo {
+            target,
+            zoom_rate: DEMO_ZOOM_RATE,
+        });
+    }
+}
@@ -0,0 +1,111 @@
+//! Development mode: the shaders are reloaded from the source tree whenever
+//! they change, so that palettes and algorithms can be tried out without
+//! restarting. The files are polled for their modification time, which is
+//! cheap enough at [`POLL_INTERVAL`] and needs no file watching.
+
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::AppState;
+
+/// The shader files, concatenated in this order into the source of the
+/// shader.
+const SHADER_PATHS: [&str; 2] = [
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader.wgsl"),
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader_dd.wgsl"),
+];
+
+/// How often the shader files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches the [`SHADER_PATHS`] for changes.
+#[derive(Debug)]
+pub struct ShaderWatcher {
+    /// The latest modification time of the files when they were last read.
+    modified: Option<SystemTime>,
+    /// When the files were last checked.
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    /// Creates a new [`ShaderWatcher`], taking the current files as loaded.
+    pub fn new() -> Self {
+        eprintln!(
+            "Reloading the shaders when {} change",
+            SHADER_PATHS.join(" or ")
+        );
+        Self {
+            modified: latest_modification(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Whether the files changed since the last call, checking them at most
+    /// every [`POLL_INTERVAL`].
+    fn changed(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        let modified = latest_modification();
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+/// Returns the latest modification time of the [`SHADER_PATHS`], `None` if
+/// any of them can't be read.
+fn latest_modification() -> Option<SystemTime> {
+    SHADER_PATHS
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .try_fold(SystemTime::UNIX_EPOCH, |latest, modified| {
+            Some(latest.max(modified?))
+        })
+}
+
+impl AppState {
+    /// Reloads the shaders if they changed, recreating every pipeline
+    /// running them. If the new shaders fail to compile, the error is printed
+    /// and the old ones are kept.
+    pub fn reload_shaders(&mut self) {
+        if !self
+            .shader_watcher
+            .as_mut()
+            .is_some_and(ShaderWatcher::changed)
+        {
+            return;
+        }
+        let source = match SHADER_PATHS
+            .iter()
+            .map(fs::read_to_string)
+            .collect::<Result<String, _>>()
+        {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Failed to read the shaders: {e}");
+                return;
+            }
+        };
+        if let Err(e) = pollster::block_on(self.renderer.reload_shader(&source))
+        {
+            eprintln!("Failed to reload the shaders: {e}");
+            return;
+        }
+
+        // The other pipelines are created from the shader of the renderer.
+        if self.inside_stats.take().is_some() {
+            self.toggle_inside_stats();
+        }
+        if self.auto_max_iter.is_some() {
+            // Stopping restores the maximum iterations to tune up to.
+            self.toggle_auto_max_iter();
+            self.toggle_auto_max_iter();
+        }
+        self.view.redraw = true;
+        eprintln!("Reloaded the shaders");
+    }
+}
@@ -0,0 +1,86 @@
+//! Saving screenshots and larger exports of the view to disk.
+
+use std::path::Path;
+
+use image::RgbaImage;
+
+use crate::{AppState, Error, Uniforms};
+
+/// Largest factor the high-resolution export can be larger than the
+/// [`AppState::viewport`] by.
+pub const MAX_EXPORT_SCALE: u32 = 16;
+
+impl AppState {
+    /// Saves a screenshot of the current view to the working directory and
+    /// reports the outcome.
+    pub fn save_screenshot(&self) {
+        let [_, _, width, height] = self.viewport();
+        let file_name = screenshot_file_name(self.uniforms(), (width, height));
+        match self.screenshot(Path::new(&file_name)) {
+            Ok(()) => eprintln!("Saved screenshot to {file_name}"),
+            Err(e) => eprintln!("Failed to save screenshot: {e}"),
+        }
+    }
+
+    /// Renders the current view at the resolution of the
+    /// [`AppState::viewport`] and saves it as a PNG image to the given `path`.
+    pub fn screenshot(&self, path: &Path) -> Result<(), Error> {
+        let [_, _, width, height] = self.viewport();
+        self.export_image(width, height, path)
+    }
+
+    /// Saves an image of the current view larger than the
+    /// [`AppState::viewport`] by the `export_scale` setting to the working
+    /// directory and reports the outcome.
+    pub fn save_export(&self) {
+        let [_, _, width, height] = self.viewport();
+        let width = width * self.settings.export_scale;
+        let height = height * self.settings.export_scale;
+        let file_name = screenshot_file_name(self.uniforms(), (width, height));
+        match self.export_image(width, height, Path::new(&file_name)) {
+            Ok(()) => eprintln!("Saved {width}x{height} image to {file_name}"),
+            Err(e) => eprintln!("Failed to export image: {e}"),
+        }
+    }
+
+    /// Renders the current view at an arbitrary resolution, supersampled by
+    /// the `screenshot_scale` setting, and saves it as a PNG image to the
+    /// given `path`.
+    pub fn export_image(
+        &self,
+        width: u32,
+        height: u32,
+        path: &Path,
+    ) -> Result<(), Error> {
+        let scale = self.settings.screenshot_scale;
+        self.render_view(self.uniforms(), (width, height), scale)?
+            .save(path)?;
+
+        Ok(())
+    }
+
+    /// Renders the view described by the `uniforms` into an image of the
+    /// given `size`, supersampled `scale` times by the `render_image` of the
+    /// [`MandelbrotRenderer`](crate::MandelbrotRenderer).
+    pub fn render_view(
+        &self,
+        uniforms: &Uniforms,
+        size: (u32, u32),
+        scale: u32,
+    ) -> Result<RgbaImage, Error> {
+        Ok(self.renderer.render_image(uniforms, size, scale)?)
+    }
+}
+
+/// Returns a file name for an image of the given `size` of the view described
+/// by the `uniforms`, containing its zoom and center so that the images are
+/// self-documenting, and its size so that a screenshot and an export of the
+/// same view don't overwrite each other.
+#[must_use]
+pub fn screenshot_file_name(uniforms: &Uniforms, size: (u32, u32)) -> String {
+    let [x, y] = uniforms.offset;
+    let zoom = uniforms.magnification();
+    let sign = if y >= 0.0 { "+" } else { "" };
+    let (width, height) = size;
+    format!("mandelbrot_{x}{sign}{y}i_x{zoom}_{width}x{height}.png")
+}
@@ -0,0 +1,86 @@
+//! Switching the window to fullscreen, either as a borderless window covering
+//! the monitor or exclusively, changing the monitor's video mode.
+
+use serde::Deserialize;
+use winit::{
+    monitor::{MonitorHandle, VideoModeHandle},
+    window::Fullscreen,
+};
+
+use crate::AppState;
+
+/// How the window is made fullscreen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FullscreenMode {
+    /// A borderless window covering the monitor.
+    #[default]
+    Borderless,
+    /// Exclusive fullscreen in the best video mode of the monitor, which can
+    /// avoid tearing and the latency of the compositor.
+    Exclusive,
+}
+
+impl AppState {
+    /// Enters or leaves fullscreen in the [`View::fullscreen_mode`].
+    ///
+    /// [`View::fullscreen_mode`]: crate::View::fullscreen_mode
+    pub fn toggle_fullscreen(&mut self) {
+        self.view.fullscreen = match self.view.fullscreen {
+            Some(_) => None,
+            None => Some(self.fullscreen(self.view.fullscreen_mode)),
+        };
+        self.window.set_fullscreen(self.view.fullscreen.clone());
+    }
+
+    /// Switches between borderless and exclusive fullscreen, applying the new
+    /// mode right away if the window is fullscreen.
+    pub fn cycle_fullscreen_mode(&mut self) {
+        self.view.fullscreen_mode = match self.view.fullscreen_mode {
+            FullscreenMode::Borderless => FullscreenMode::Exclusive,
+            FullscreenMode::Exclusive => FullscreenMode::Borderless,
+        };
+        eprintln!("Fullscreen mode: {:?}", self.view.fullscreen_mode);
+        if self.view.fullscreen.is_some() {
+            self.view.fullscreen =
+                Some(self.fullscreen(self.view.fullscreen_mode));
+            self.window.set_fullscreen(self.view.fullscreen.clone());
+        }
+    }
+
+    /// Returns the [`Fullscreen`] of the given `mode` on the monitor the
+    /// window is on. Falls back to borderless if there is no monitor or it
+    /// has no video modes.
+    fn fullscreen(&self, mode: FullscreenMode) -> Fullscreen {
+        if mode == FullscreenMode::Borderless {
+            return Fullscreen::Borderless(None);
+        }
+        let monitor = self
+            .window
+            .current_monitor()
+            .or_else(|| self.window.primary_monitor());
+        match monitor.as_ref().and_then(best_video_mode) {
+            Some(video_mode) => Fullscreen::Exclusive(video_mode),
+            None => {
+                eprintln!(
+                    "No video mode available for exclusive fullscreen, \
+                     using borderless"
+                );
+                Fullscreen::Borderless(monitor)
+            }
+        }
+    }
+}
+
+/// Returns the video mode of the `monitor` with the highest resolution, then
+/// bit depth, then refresh rate, if it has any.
+fn best_video_mode(monitor: &MonitorHandle) -> Option<VideoModeHandle> {
+    monitor.video_modes().max_by_key(|mode| {
+        let size = mode.size();
+        (
+            u64::from(size.width) * u64::from(size.height),
+            mode.bit_depth(),
+            mode.refresh_rate_millihertz(),
+        )
+    })
+}
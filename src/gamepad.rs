@@ -0,0 +1,59 @@
+//! Navigating with a gamepad: the left stick pans, the triggers zoom and the
+//! face buttons toggle the set and the color rotation.
+
+use gilrs::{Axis, Button, EventType};
+
+use crate::AppState;
+
+/// How many [`Uniforms::zooms`](crate::Uniforms::zooms) a fully pressed
+/// trigger zooms per second.
+const TRIGGER_ZOOM_SPEED: f64 = 15.0;
+
+impl AppState {
+    /// Handles a gamepad event, updating the [`View`](crate::View) state that
+    /// [`AppState::apply_gamepad`] acts on every frame.
+    pub fn gamepad_input(&mut self, event: EventType) {
+        if matches!(event, EventType::ButtonPressed(..)) {
+            self.stop_demo();
+        }
+        match event {
+            EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                self.view.stick.0 = value.into();
+            }
+            EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                self.view.stick.1 = value.into();
+            }
+            EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                self.view.triggers.0 = value.into();
+            }
+            EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                self.view.triggers.1 = value.into();
+            }
+            EventType::ButtonPressed(Button::South, _) => {
+                self.toggle_julia();
+            }
+            EventType::ButtonPressed(Button::East, _) => {
+                let rotate_colors = &mut self.uniforms_mut().rotate_colors;
+                *rotate_colors = 1.0 - *rotate_colors;
+            }
+            EventType::Disconnected => {
+                self.view.stick = (0.0, 0.0);
+                self.view.triggers = (0.0, 0.0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Pans proportionally to the stick deflection and zooms proportionally to
+    /// how far the triggers are pressed for `dt` seconds: the left trigger
+    /// zooms out, the right one zooms in.
+    pub fn apply_gamepad(&mut self, dt: f64) {
+        self.pan(self.view.stick, dt);
+
+        let (zoom_out, zoom_in) = self.view.triggers;
+        if zoom_out != zoom_in {
+            let speed = TRIGGER_ZOOM_SPEED * self.settings.zoom_speed;
+            self.zoom((zoom_out - zoom_in) * speed * dt);
+        }
+    }
+}
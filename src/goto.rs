@@ -0,0 +1,124 @@
+//! Jumping to coordinates typed into a prompt shown by the overlay.
+//!
+//! The coordinates are entered as `re, im, zoom`, e.g.
+//! `-0.743643887, 0.131825904, 1e6`, see [`parse_coordinates`]. The zoom is
+//! the magnification shown in the window title and may be left out to keep
+//! the current one.
+
+use winit::{
+    event::KeyEvent,
+    keyboard::{Key, KeyCode, NamedKey, PhysicalKey},
+};
+
+use crate::{AppState, Error, Uniforms};
+
+impl AppState {
+    /// Opens the coordinate prompt. While it's open, all keyboard input goes
+    /// to [`AppState::prompt_input`].
+    pub fn open_prompt(&mut self) {
+        self.view.prompt = Some(String::new());
+        // The releases of the keys held now go to the prompt.
+        self.view.movement_delta = (0.0, 0.0);
+        self.view.zoom_delta = 0.0;
+    }
+
+    /// Handles a key `event` while the coordinate prompt is open: typing and
+    /// pasting edit the text, `Enter` jumps to the coordinates and `Escape`
+    /// closes the prompt.
+    pub fn prompt_input(&mut self, event: &KeyEvent) {
+        if !event.state.is_pressed() {
+            return;
+        }
+        match &event.logical_key {
+            Key::Named(NamedKey::Enter) => self.submit_prompt(),
+            Key::Named(NamedKey::Escape) => self.view.prompt = None,
+            Key::Named(NamedKey::Backspace) => {
+                if let Some(prompt) = &mut self.view.prompt {
+                    prompt.pop();
+                }
+            }
+            _ if self.view.ctrl_pressed
+                && event.physical_key == PhysicalKey::Code(KeyCode::KeyV) =>
+            {
+                match self.clipboard_text() {
+                    Ok(text) => self.type_text(&text),
+                    Err(e) => eprintln!("Failed to paste coordinates: {e}"),
+                }
+            }
+            _ => {
+                if let Some(text) = event.text.clone() {
+                    self.type_text(&text);
+                }
+            }
+        }
+    }
+
+    /// Centers the view on the point `center` at the magnification `zoom`, as
+    /// shown in the window title, clamped to the supported range.
+    pub fn go_to(&mut self, center: [f64; 2], zoom: f64) {
+        let uniforms = self.uniforms_mut();
+        uniforms.offset = center;
+        uniforms.offset_lo = [0.0, 0.0];
+        uniforms.zooms = Uniforms::zooms_from_magnification(zoom);
+        // Clamps the zoom to the supported range.
+        self.zoom(0.0);
+        self.view.redraw = true;
+    }
+
+    /// Appends the `text` to the prompt, dropping control characters.
+    fn type_text(&mut self, text: &str) {
+        if let Some(prompt) = &mut self.view.prompt {
+            prompt.extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    /// Jumps to the coordinates in the prompt and closes it, or keeps it open
+    /// if they are invalid.
+    fn submit_prompt(&mut self) {
+        let Some(text) = self.view.prompt.take() else {
+            return;
+        };
+        match parse_coordinates(&text) {
+            Ok((center, zoom)) => {
+                self.save_history();
+                let zoom =
+                    zoom.unwrap_or_else(|| self.uniforms().magnification());
+                self.go_to(center, zoom);
+            }
+            Err(e) => {
+                eprintln!("Failed to go to coordinates: {e}");
+                self.view.prompt = Some(text);
+            }
+        }
+    }
+}
+
+/// Parses the `re, im, zoom` coordinates typed into the prompt into the center
+/// and, if it's given, the magnification of the view. The numbers may be
+/// separated by commas or whitespace, and the zoom may be prefixed with `x` as
+/// in the window title.
+pub fn parse_coordinates(text: &str) -> Result<([f64; 2], Option<f64>), Error> {
+    let invalid = || Error::InvalidView(text.to_owned());
+    let parts: Vec<&str> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    let number = |part: &str| {
+        part.parse::<f64>()
+            .ok()
+            .filter(|x| x.is_finite())
+            .ok_or_else(invalid)
+    };
+    let (re, im, zoom) = match parts[..] {
+        [re, im] => (number(re)?, number(im)?, None),
+        [re, im, zoom] => {
+            let zoom = number(zoom.strip_prefix('x').unwrap_or(zoom))?;
+            if zoom <= 0.0 {
+                return Err(invalid());
+            }
+            (number(re)?, number(im)?, Some(zoom))
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(([re, im], zoom))
+}
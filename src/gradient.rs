@@ -0,0 +1,62 @@
+//! Palettes from images: the colors of a gradient loaded from an image file
+//! with `--palette`, e.g. one exported by another fractal program, which the
+//! palette at index [`GRADIENT_PALETTE`] loops through.
+
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+
+use crate::{AppState, Error, PALETTE_NAMES};
+
+/// Index of the palette looping through the loaded gradient, after the
+/// built-in [`PALETTE_NAMES`]. Matches `GRADIENT` in the shader.
+#[allow(clippy::cast_possible_truncation, reason = "there are few palettes")]
+pub const GRADIENT_PALETTE: u32 = PALETTE_NAMES.len() as u32;
+
+/// The most colors a gradient is resampled to, which every GPU supports as
+/// the width of a texture.
+const MAX_GRADIENT_WIDTH: u32 = 2048;
+
+/// Loads the colors of the gradient in the image at `path`, from left to
+/// right, or from top to bottom if the image is taller than wide. The colors
+/// across the gradient are averaged, and long gradients are resampled to
+/// [`MAX_GRADIENT_WIDTH`] colors.
+pub fn load_gradient(path: &Path) -> Result<Vec<[u8; 4]>, Error> {
+    let mut image = image::open(path)?.to_rgba8();
+    if image.height() > image.width() {
+        // Turns the top edge into the left one.
+        image = imageops::rotate270(&image);
+    }
+    if image.width() < 2 {
+        return Err(Error::GradientTooShort);
+    }
+    let width = image.width().min(MAX_GRADIENT_WIDTH);
+    let row = imageops::resize(&image, width, 1, FilterType::Triangle);
+    Ok(row.pixels().map(|pixel| pixel.0).collect())
+}
+
+impl AppState {
+    /// Returns the number of palettes [`Action::NextPalette`] cycles through,
+    /// including the gradient if one was loaded.
+    ///
+    /// [`Action::NextPalette`]: crate::keymap::Action::NextPalette
+    #[must_use]
+    pub const fn palette_count(&self) -> u32 {
+        if self.gradient_loaded {
+            GRADIENT_PALETTE + 1
+        } else {
+            GRADIENT_PALETTE
+        }
+    }
+
+    /// Returns the name of the current palette. Without a loaded gradient,
+    /// the shader draws the [`GRADIENT_PALETTE`] as the first palette.
+    #[must_use]
+    pub fn palette_name(&self) -> &'static str {
+        match self.uniforms().palette {
+            GRADIENT_PALETTE if self.gradient_loaded => "Gradient",
+            GRADIENT_PALETTE => PALETTE_NAMES[0],
+            palette => PALETTE_NAMES[palette as usize],
+        }
+    }
+}
@@ -0,0 +1,32 @@
+//! Rendering to an HDR surface. Its colors are linear in the extended range of
+//! scRGB, where `1.0` is a white of [`SCRGB_WHITE_NITS`] and brighter colors
+//! exceed it, so the output of the shader is scaled to the requested white.
+
+use wgpu::{SurfaceCapabilities, SurfaceConfiguration, TextureFormat};
+
+/// Brightness in nits of the color `1.0` on an scRGB surface.
+const SCRGB_WHITE_NITS: f32 = 80.0;
+
+/// Switches the surface `config` to an HDR format if the surface supports one,
+/// see its `capabilities`. Returns the [`Uniforms::output_scale`] making the
+/// white of the palettes `white_nits` bright, or `1.0` after falling back to
+/// SDR.
+///
+/// [`Uniforms::output_scale`]: crate::Uniforms::output_scale
+pub fn configure_hdr(
+    config: &mut SurfaceConfiguration,
+    capabilities: &SurfaceCapabilities,
+    white_nits: f32,
+) -> f32 {
+    if !capabilities.formats.contains(&TextureFormat::Rgba16Float) {
+        eprintln!("The display doesn't support HDR, falling back to SDR");
+        return 1.0;
+    }
+    if !(white_nits.is_finite() && white_nits > 0.0) {
+        eprintln!("Warning: --hdr must be positive, falling back to SDR");
+        return 1.0;
+    }
+    config.format = TextureFormat::Rgba16Float;
+    config.view_formats.clear();
+    white_nits / SCRGB_WHITE_NITS
+}
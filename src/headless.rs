@@ -0,0 +1,85 @@
+//! Rendering a batch of views to PNG images without opening a window.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use wgpu::TextureFormat;
+
+use crate::{
+    AdapterOptions, Error, MAX_ZOOMS, MandelbrotRenderer, bookmarks::Bookmark,
+    config::Config, create_targets, export::screenshot_file_name,
+    print_device_info, request_adapter, request_device,
+};
+
+/// Format of the textures the views are rendered into.
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+/// A view to render, in the same format as the bookmarks file, optionally with
+/// the size and path of the image.
+#[derive(Debug, Deserialize)]
+struct Job {
+    #[serde(flatten)]
+    view: Bookmark,
+    /// Path of the image, named after the view by default.
+    #[serde(default)]
+    output: Option<PathBuf>,
+    #[serde(default = "default_width")]
+    width: u32,
+    #[serde(default = "default_height")]
+    height: u32,
+}
+
+const fn default_width() -> u32 {
+    1920
+}
+
+const fn default_height() -> u32 {
+    1080
+}
+
+/// Renders every view listed in the JSON file at `path` to a PNG image.
+/// The adapter is chosen by the `options`.
+pub fn run(path: &Path, options: AdapterOptions) -> Result<(), Error> {
+    let jobs: Vec<Job> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    pollster::block_on(render_jobs(&jobs, &Config::load(), options))
+}
+
+/// Creates a device without a surface on an adapter chosen by the `options`
+/// and renders the `jobs` with it, using the defaults of the `config` for the
+/// settings the jobs don't specify.
+async fn render_jobs(
+    jobs: &[Job],
+    config: &Config,
+    options: AdapterOptions,
+) -> Result<(), Error> {
+    let targets = create_targets(options, None)?;
+    let (adapter, _) = request_adapter(options, targets).await?;
+    let (device, queue, precision) = request_device(&adapter).await?;
+    if options.verbose {
+        print_device_info(&adapter, &device, precision);
+    }
+
+    let renderer = MandelbrotRenderer::new(&device, &queue, precision, FORMAT);
+
+    for job in jobs {
+        let mut uniforms = config.default_uniforms();
+        job.view.apply(&mut uniforms);
+        uniforms.zooms = uniforms.zooms.clamp(precision.min_zooms(), MAX_ZOOMS);
+
+        let image = renderer.render_image(
+            &uniforms,
+            (job.width, job.height),
+            config.screenshot_scale,
+        )?;
+        let output = job.output.clone().unwrap_or_else(|| {
+            screenshot_file_name(&uniforms, (job.width, job.height)).into()
+        });
+        image.save(&output)?;
+        eprintln!("Saved {}", output.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Undo and redo of the navigation: the view is saved before discrete actions
+//! like zooming, starting a drag or jumping to a bookmark, so that it can be
+//! stepped back to. Continuous movement is only saved when it starts.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{AppState, Uniforms};
+
+/// Maximum number of views that can be undone.
+const HISTORY_LIMIT: usize = 100;
+
+/// Actions closer together than this are undone as one step, e.g. the
+/// ticks of a single scroll of the mouse wheel.
+const HISTORY_MERGE_TIME: Duration = Duration::from_millis(500);
+
+/// The views that can be undone and redone.
+#[derive(Debug, Default)]
+pub struct History {
+    /// Views before the latest actions, the most recent one last.
+    undo: VecDeque<Uniforms>,
+    /// Undone views, the most recently undone one last.
+    redo: Vec<Uniforms>,
+    /// When the view was last saved.
+    last_save: Option<Instant>,
+}
+
+impl AppState {
+    /// Saves the current view before a navigation action, unless another one
+    /// was saved within [`HISTORY_MERGE_TIME`] or the view hasn't changed
+    /// since the last one.
+    pub fn save_history(&mut self) {
+        // Only whole sets are saved, never a morph between them.
+        self.finish_morph();
+        let uniforms = self.view.uniforms;
+        let history = &mut self.view.history;
+        let now = Instant::now();
+        let merged = history
+            .last_save
+            .is_some_and(|last| now.duration_since(last) < HISTORY_MERGE_TIME);
+        history.last_save = Some(now);
+        if merged
+            || history.undo.back().is_some_and(|u| same_view(u, &uniforms))
+        {
+            return;
+        }
+        if history.undo.len() == HISTORY_LIMIT {
+            history.undo.pop_front();
+        }
+        history.undo.push_back(uniforms);
+        history.redo.clear();
+    }
+
+    /// Steps back to the view before the latest action.
+    pub fn undo(&mut self) {
+        self.finish_morph();
+        if let Some(uniforms) = self.view.history.undo.pop_back() {
+            self.view.history.redo.push(self.view.uniforms);
+            self.restore_view(&uniforms);
+        }
+    }
+
+    /// Steps forward to the most recently undone view.
+    pub fn redo(&mut self) {
+        self.finish_morph();
+        if let Some(uniforms) = self.view.history.redo.pop() {
+            self.view.history.undo.push_back(self.view.uniforms);
+            self.restore_view(&uniforms);
+        }
+    }
+
+    /// Restores the position and the set of the view saved in the `uniforms`,
+    /// keeping the rest of the settings.
+    fn restore_view(&mut self, uniforms: &Uniforms) {
+        // The restored view shouldn't be merged with the next action.
+        self.view.history.last_save = None;
+        self.view.animation = None;
+        #[allow(clippy::float_cmp, reason = "either 0.0 or 1.0")]
+        if uniforms.is_mandelbrot != self.uniforms().is_mandelbrot {
+            // Like toggling the set, remember the view of the other one.
+            self.view.hidden_view =
+                (self.uniforms().offset, self.uniforms().zooms);
+        }
+        let current = self.uniforms_mut();
+        current.offset = uniforms.offset;
+        current.offset_lo = uniforms.offset_lo;
+        current.zooms = uniforms.zooms;
+        current.is_mandelbrot = uniforms.is_mandelbrot;
+        current.rotation = uniforms.rotation;
+        self.view.redraw = true;
+    }
+}
+
+/// Whether the uniforms `a` and `b` describe the same view, as restored by
+/// [`AppState::restore_view`].
+#[allow(clippy::float_cmp, reason = "only exact duplicates are skipped")]
+fn same_view(a: &Uniforms, b: &Uniforms) -> bool {
+    a.offset == b.offset
+        && a.offset_lo == b.offset_lo
+        && a.zooms == b.zooms
+        && a.is_mandelbrot == b.is_mandelbrot
+        && a.rotation == b.rotation
+}
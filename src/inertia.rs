@@ -0,0 +1,86 @@
+//! Pan inertia: the velocity of a drag with the mouse or a finger is tracked,
+//! and the view keeps gliding at it after the drag is released, slowing down
+//! at the rate of the `pan_friction` setting.
+
+use std::time::{Duration, Instant};
+
+use crate::AppState;
+
+/// How long the velocity of a drag is averaged over, in seconds.
+const VELOCITY_SMOOTHING: f64 = 0.05;
+
+/// How long before its release a drag must have moved to start gliding, so
+/// that holding still before releasing stops the view.
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Speed in pixels per second below which a glide stops.
+const MIN_GLIDE_SPEED: f64 = 10.0;
+
+/// The velocity of the current drag or glide.
+#[derive(Debug, Default)]
+pub struct Inertia {
+    /// Smoothed velocity in pixels per second.
+    velocity: (f64, f64),
+    /// When the drag last moved, `None` if there is no drag.
+    last_move: Option<Instant>,
+    /// Whether the view is gliding after a drag was released.
+    gliding: bool,
+}
+
+impl Inertia {
+    /// Stops the glide and forgets the velocity of the drag, e.g. when a new
+    /// one starts.
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether the view is gliding after a drag was released.
+    pub const fn is_gliding(&self) -> bool {
+        self.gliding
+    }
+}
+
+impl AppState {
+    /// Updates the velocity of the drag that just moved by the given number of
+    /// pixels.
+    pub fn track_drag(&mut self, dx: f64, dy: f64) {
+        let inertia = &mut self.view.inertia;
+        let now = Instant::now();
+        if let Some(last) = inertia.last_move {
+            // Several events can arrive at once, which the smoothing evens out.
+            let dt = now.duration_since(last).as_secs_f64().max(1e-4);
+            let weight = 1.0 - (-dt / VELOCITY_SMOOTHING).exp();
+            let (vx, vy) = &mut inertia.velocity;
+            *vx += (dx / dt - *vx) * weight;
+            *vy += (dy / dt - *vy) * weight;
+        }
+        inertia.last_move = Some(now);
+    }
+
+    /// Starts gliding at the velocity of the drag that was just released, if
+    /// it was still moving.
+    pub fn release_drag(&mut self) {
+        let inertia = &mut self.view.inertia;
+        let moving = inertia
+            .last_move
+            .take()
+            .is_some_and(|last| last.elapsed() < RELEASE_TIMEOUT);
+        let (vx, vy) = inertia.velocity;
+        inertia.gliding = moving && vx.hypot(vy) >= MIN_GLIDE_SPEED;
+    }
+
+    /// Continues the glide for `dt` seconds, slowing it down.
+    pub fn advance_glide(&mut self, dt: f64) {
+        if !self.view.inertia.gliding {
+            return;
+        }
+        let (vx, vy) = self.view.inertia.velocity;
+        self.drag(vx * dt, vy * dt);
+        let decay = (-self.settings.pan_friction * dt).exp();
+        let velocity = (vx * decay, vy * decay);
+        self.view.inertia.velocity = velocity;
+        if velocity.0.hypot(velocity.1) < MIN_GLIDE_SPEED {
+            self.view.inertia.stop();
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Key bindings: which [`Action`] a key performs, with defaults that can be
+//! overridden in the `[keys]` table of the config file.
+
+use std::collections::HashMap;
+
+use serde::{
+    Deserialize,
+    de::{IntoDeserializer as _, value},
+};
+use winit::keyboard::KeyCode;
+
+/// Something a key does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Pans left while held.
+    MoveLeft,
+    /// Pans right while held.
+    MoveRight,
+    /// Pans up while held.
+    MoveUp,
+    /// Pans down while held.
+    MoveDown,
+    /// Zooms in while held.
+    ZoomIn,
+    /// Zooms out while held.
+    ZoomOut,
+    /// Rotates the view counterclockwise.
+    RotateLeft,
+    /// Rotates the view clockwise.
+    RotateRight,
+    /// Switches between the Mandelbrot and the Julia set.
+    ToggleJulia,
+    /// Shows or hides the preview of the Julia set.
+    ToggleJuliaInset,
+    /// Locks or unlocks the `c` of the Julia set.
+    LockJulia,
+    /// Toggles the rotation of the colors.
+    RotateColors,
+    /// Shifts the palettes back by a step.
+    ShiftColorsBack,
+    /// Shifts the palettes forward by a step.
+    ShiftColorsForward,
+    /// Pauses or resumes the time.
+    Pause,
+    /// Cycles the palettes.
+    NextPalette,
+    /// Toggles the distance estimation.
+    DistanceEstimation,
+    /// Cycles the coloring modes.
+    NextColoring,
+    /// Cycles the mappings of the iteration count to the palette.
+    NextColorScale,
+    /// Cycles the shapes of the orbit traps.
+    NextTrapShape,
+    /// Toggles the coloring of the interior.
+    InteriorColoring,
+    /// Cycles the colors of the interior.
+    NextInteriorColor,
+    /// Cycles the fractal types.
+    NextFractal,
+    /// Decreases the Multibrot power.
+    DecreasePower,
+    /// Increases the Multibrot power.
+    IncreasePower,
+    /// Makes the color bands narrower, repeating the palettes over fewer
+    /// iterations.
+    NarrowerBands,
+    /// Makes the color bands wider, repeating the palettes over more
+    /// iterations.
+    WiderBands,
+    /// Decreases the number of stripes of the stripe average coloring.
+    FewerStripes,
+    /// Increases the number of stripes of the stripe average coloring.
+    MoreStripes,
+    /// Resets the view, animating the way back.
+    Reset,
+    /// Resets the view instantly.
+    ResetInstantly,
+    /// Rebases the view on its current center, so that the rendered center is
+    /// the one shown in the title.
+    RebaseView,
+    /// Decreases the maximum number of iterations, by less with shift and by
+    /// more with control held.
+    DecreaseMaxIter,
+    /// Increases the maximum number of iterations, by less with shift and by
+    /// more with control held.
+    IncreaseMaxIter,
+    /// Starts or stops tuning the maximum number of iterations to the view
+    /// automatically, up to the one set.
+    AutoMaxIter,
+    /// Doubles the escape radius.
+    DoubleEscapeRadius,
+    /// Halves the escape radius.
+    HalveEscapeRadius,
+    /// Saves a screenshot.
+    Screenshot,
+    /// Saves an image in a higher resolution, see `Config::export_scale`.
+    Export,
+    /// Starts or stops recording a zoom video.
+    Record,
+    /// Starts or stops recording a color cycling animation of the view.
+    RecordColorCycle,
+    /// Saves the view as a bookmark.
+    SaveBookmark,
+    /// Goes to the next bookmark.
+    NextBookmark,
+    /// Copies the view to the clipboard.
+    CopyView,
+    /// Copies the view to the clipboard as a shareable string.
+    CopyShareString,
+    /// Pastes a view from the clipboard.
+    PasteView,
+    /// Opens the prompt for going to coordinates.
+    GoTo,
+    /// Undoes the last navigation.
+    Undo,
+    /// Redoes the last undone navigation.
+    Redo,
+    /// Cycles the present modes.
+    NextPresentMode,
+    /// Cycles MSAA through off and the supported sample counts.
+    Msaa,
+    /// Toggles the accumulated anti-aliasing.
+    Accumulation,
+    /// Cycles the render scales of the SSAA.
+    NextRenderScale,
+    /// Toggles the dithering.
+    Dithering,
+    /// Toggles the progressive rendering.
+    Progressive,
+    /// Toggles the histogram coloring.
+    Histogram,
+    /// Starts or stops the demo zooming into interesting points.
+    Demo,
+    /// Shows or hides the minimap.
+    Minimap,
+    /// Shows or hides the crosshair at the center of the view.
+    Crosshair,
+    /// Shows or hides the percentage of pixels inside the set.
+    InsideStats,
+    /// Starts or stops comparing `f64` with `f32` side by side.
+    PrecisionComparison,
+    /// Locks or unlocks the aspect ratio of the fractal.
+    LockAspect,
+    /// Shows or hides the FPS overlay.
+    Overlay,
+    /// Shows or hides the state of the view in the window title.
+    TitleInfo,
+    /// Enters or leaves fullscreen.
+    Fullscreen,
+    /// Switches between borderless and exclusive fullscreen.
+    NextFullscreenMode,
+}
+
+impl Action {
+    /// Whether the action lasts while the key is held, rather than happening
+    /// once when it's pressed.
+    #[must_use]
+    pub const fn is_held(self) -> bool {
+        matches!(
+            self,
+            Self::MoveLeft
+                | Self::MoveRight
+                | Self::MoveUp
+                | Self::MoveDown
+                | Self::ZoomIn
+                | Self::ZoomOut
+        )
+    }
+}
+
+/// A key together with the modifiers held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Binding {
+    /// The physical key.
+    key: KeyCode,
+    /// Whether control is held.
+    ctrl: bool,
+    /// Whether shift is held.
+    shift: bool,
+}
+
+impl Binding {
+    /// The `key` alone.
+    const fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    /// The `key` with control held.
+    const fn ctrl(key: KeyCode) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::key(key)
+        }
+    }
+
+    /// The `key` with shift held.
+    const fn shift(key: KeyCode) -> Self {
+        Self {
+            shift: true,
+            ..Self::key(key)
+        }
+    }
+
+    /// Parses a binding like `"KeyQ"` or `"Ctrl+Shift+ArrowLeft"`, with the
+    /// key named like the variants of [`KeyCode`].
+    fn parse(s: &str) -> Result<Self, String> {
+        let (modifiers, key) = s.rsplit_once('+').unwrap_or(("", s));
+        let key = KeyCode::deserialize(
+            key.into_deserializer() as value::StrDeserializer<value::Error>
+        )
+        .map_err(|_| format!("unknown key {key:?}"))?;
+        let mut binding = Self::key(key);
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier {modifier:?} in {s:?}, expected \
+                         Ctrl or Shift"
+                    ));
+                }
+            }
+        }
+        Ok(binding)
+    }
+}
+
+/// The default key bindings.
+const DEFAULT_BINDINGS: [(Binding, Action); 72] = [
+    (Binding::key(KeyCode::KeyA), Action::MoveLeft),
+    (Binding::key(KeyCode::KeyD), Action::MoveRight),
+    (Binding::key(KeyCode::KeyW), Action::MoveUp),
+    (Binding::key(KeyCode::KeyS), Action::MoveDown),
+    (Binding::key(KeyCode::ArrowLeft), Action::MoveLeft),
+    (Binding::key(KeyCode::ArrowRight), Action::MoveRight),
+    (Binding::key(KeyCode::ArrowUp), Action::MoveUp),
+    (Binding::key(KeyCode::ArrowDown), Action::MoveDown),
+    (Binding::key(KeyCode::Equal), Action::ZoomIn),
+    (Binding::key(KeyCode::NumpadAdd), Action::ZoomIn),
+    (Binding::key(KeyCode::Minus), Action::ZoomOut),
+    (Binding::key(KeyCode::NumpadSubtract), Action::ZoomOut),
+    (Binding::ctrl(KeyCode::KeyQ), Action::RotateLeft),
+    (Binding::ctrl(KeyCode::KeyE), Action::RotateRight),
+    (Binding::key(KeyCode::Space), Action::ToggleJulia),
+    (Binding::key(KeyCode::KeyI), Action::ToggleJuliaInset),
+    (Binding::key(KeyCode::KeyJ), Action::LockJulia),
+    (Binding::key(KeyCode::KeyQ), Action::RotateColors),
+    (Binding::shift(KeyCode::KeyQ), Action::ShiftColorsBack),
+    (Binding::shift(KeyCode::KeyE), Action::ShiftColorsForward),
+    (Binding::key(KeyCode::KeyX), Action::Pause),
+    (Binding::key(KeyCode::KeyC), Action::NextPalette),
+    (Binding::key(KeyCode::KeyE), Action::DistanceEstimation),
+    (Binding::key(KeyCode::KeyO), Action::NextColoring),
+    (Binding::shift(KeyCode::KeyO), Action::NextColorScale),
+    (Binding::key(KeyCode::KeyT), Action::NextTrapShape),
+    (Binding::key(KeyCode::KeyL), Action::InteriorColoring),
+    (Binding::shift(KeyCode::KeyL), Action::NextInteriorColor),
+    (Binding::key(KeyCode::KeyF), Action::NextFractal),
+    (Binding::key(KeyCode::BracketLeft), Action::DecreasePower),
+    (Binding::key(KeyCode::BracketRight), Action::IncreasePower),
+    (Binding::shift(KeyCode::BracketLeft), Action::NarrowerBands),
+    (Binding::shift(KeyCode::BracketRight), Action::WiderBands),
+    (Binding::ctrl(KeyCode::BracketLeft), Action::FewerStripes),
+    (Binding::ctrl(KeyCode::BracketRight), Action::MoreStripes),
+    (Binding::key(KeyCode::KeyR), Action::Reset),
+    (Binding::shift(KeyCode::KeyR), Action::ResetInstantly),
+    (Binding::ctrl(KeyCode::KeyR), Action::RebaseView),
+    (Binding::key(KeyCode::Comma), Action::DecreaseMaxIter),
+    (Binding::key(KeyCode::Period), Action::IncreaseMaxIter),
+    (Binding::key(KeyCode::Slash), Action::AutoMaxIter),
+    (Binding::key(KeyCode::KeyK), Action::DoubleEscapeRadius),
+    (Binding::ctrl(KeyCode::KeyK), Action::HalveEscapeRadius),
+    (Binding::key(KeyCode::KeyP), Action::Screenshot),
+    (Binding::ctrl(KeyCode::KeyP), Action::Export),
+    (Binding::key(KeyCode::F9), Action::Record),
+    (Binding::shift(KeyCode::F9), Action::RecordColorCycle),
+    (Binding::ctrl(KeyCode::KeyB), Action::SaveBookmark),
+    (Binding::key(KeyCode::KeyB), Action::NextBookmark),
+    (Binding::key(KeyCode::KeyY), Action::CopyView),
+    (Binding::shift(KeyCode::KeyY), Action::CopyShareString),
+    (Binding::ctrl(KeyCode::KeyV), Action::PasteView),
+    (Binding::key(KeyCode::KeyG), Action::GoTo),
+    (Binding::ctrl(KeyCode::KeyZ), Action::Undo),
+    (Binding::ctrl(KeyCode::KeyY), Action::Redo),
+    (Binding::key(KeyCode::KeyV), Action::NextPresentMode),
+    (Binding::key(KeyCode::KeyM), Action::Msaa),
+    (Binding::ctrl(KeyCode::KeyM), Action::Accumulation),
+    (Binding::key(KeyCode::KeyN), Action::NextRenderScale),
+    (Binding::ctrl(KeyCode::KeyN), Action::Dithering),
+    (Binding::key(KeyCode::KeyU), Action::Progressive),
+    (Binding::key(KeyCode::KeyH), Action::Histogram),
+    (Binding::key(KeyCode::Tab), Action::Minimap),
+    (Binding::shift(KeyCode::Tab), Action::Crosshair),
+    (Binding::shift(KeyCode::KeyI), Action::InsideStats),
+    (Binding::key(KeyCode::F2), Action::PrecisionComparison),
+    (Binding::key(KeyCode::F4), Action::LockAspect),
+    (Binding::key(KeyCode::F5), Action::Demo),
+    (Binding::key(KeyCode::F3), Action::Overlay),
+    (Binding::shift(KeyCode::F3), Action::TitleInfo),
+    (Binding::key(KeyCode::F11), Action::Fullscreen),
+    (Binding::shift(KeyCode::F11), Action::NextFullscreenMode),
+];
+
+/// The actions of the keys. Deserialized from a table of bindings like
+/// `"Ctrl+KeyQ"` to the snake case names of the [`Action`]s, which are added
+/// to the defaults, replacing the actions of the same bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "HashMap<String, Action>")]
+pub struct KeyMap {
+    /// The action of each binding.
+    bindings: HashMap<Binding, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_BINDINGS.into_iter().collect(),
+        }
+    }
+}
+
+impl TryFrom<HashMap<String, Action>> for KeyMap {
+    type Error = String;
+
+    fn try_from(table: HashMap<String, Action>) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        for (binding, action) in table {
+            keymap.bindings.insert(Binding::parse(&binding)?, action);
+        }
+        Ok(keymap)
+    }
+}
+
+impl KeyMap {
+    /// Returns the action of the `key` pressed with the given modifiers. Keys
+    /// without a binding for all of the modifiers fall back to the binding
+    /// with fewer of them, so that e.g. shift can be held while moving.
+    #[must_use]
+    pub fn action(
+        &self,
+        key: KeyCode,
+        ctrl: bool,
+        shift: bool,
+    ) -> Option<Action> {
+        [(ctrl, shift), (ctrl, false), (false, shift), (false, false)]
+            .into_iter()
+            .find_map(|(ctrl, shift)| {
+                self.bindings.get(&Binding { key, ctrl, shift })
+            })
+            .copied()
+    }
+}
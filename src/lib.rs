@@ -0,0 +1,8 @@
+//! Rendering the Mandelbrot set and its relatives with wgpu, independently of
+//! the window of the explorer, so that it can be embedded in other
+//! applications. See [`MandelbrotRenderer`].
+
+pub mod color;
+pub mod renderer;
+
+pub use renderer::{MandelbrotRenderer, Precision, Uniforms};
@@ -1,32 +1,90 @@
-use std::{sync::Arc, time::Instant};
+mod auto_iter;
+mod bench;
+mod bookmarks;
+mod cli;
+mod clipboard;
+mod compare;
+mod config;
+mod crosshair;
+mod demo;
+mod dev;
+mod export;
+mod fullscreen;
+mod gamepad;
+mod goto;
+mod gradient;
+mod hdr;
+mod headless;
+mod history;
+mod inertia;
+mod keymap;
+mod minimap;
+mod monitor;
+mod orbit;
+mod overlay;
+mod record;
+mod redraw;
+mod session;
+mod share;
+mod stats;
+mod touch;
 
+use std::{
+    collections::{HashMap, VecDeque},
+    f32::consts::{PI, TAU},
+    mem,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    auto_iter::AutoMaxIter, bench::Bench, cli::Args, clipboard::Clipboard,
+    compare::PrecisionComparison, config::Config, crosshair::Crosshair,
+    demo::Demo, dev::ShaderWatcher, fullscreen::FullscreenMode,
+    gradient::GRADIENT_PALETTE, history::History, inertia::Inertia,
+    keymap::Action, minimap::Minimap, overlay::Overlay, record::Recording,
+    session::Session, stats::InsideStats,
+};
+use clap::Parser as _;
+use gilrs::{Event, Gilrs};
+use mandelbrot_explorer_wgpu::{
+    color::srgb_to_linear,
+    renderer::{
+        self, ExportError, MandelbrotRenderer, Precision, Uniforms,
+        create_uniforms_buffer, write_uniforms,
+    },
+};
 use wgpu::{
-    BackendOptions, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry,
-    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
-    BufferBindingType, BufferDescriptor, BufferUsages, ColorTargetState,
-    ColorWrites, CommandEncoderDescriptor, CreateSurfaceError, Device,
-    DeviceDescriptor, Features, FragmentState, Instance, InstanceDescriptor,
-    InstanceFlags, MemoryBudgetThresholds, MultisampleState, Operations,
-    PipelineCompilationOptions, PipelineLayoutDescriptor, PrimitiveState,
-    Queue, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
-    RenderPipelineDescriptor, RequestAdapterError, RequestAdapterOptions,
-    RequestDeviceError, ShaderStages, Surface, SurfaceConfiguration,
-    SurfaceError, TextureViewDescriptor, VertexState, include_wgsl,
+    Adapter, BackendOptions, Backends, BindGroup, Buffer, BufferAsyncError,
+    Color, CommandEncoder, CommandEncoderDescriptor, CreateSurfaceError,
+    Device, Instance, InstanceDescriptor, InstanceFlags, LoadOp,
+    MemoryBudgetThresholds, Operations, PollError, PowerPreference,
+    PresentMode, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RequestAdapterError, RequestAdapterOptions, RequestDeviceError, StoreOp,
+    Surface, SurfaceConfiguration, SurfaceError, TextureFormat, TextureView,
+    TextureViewDescriptor,
 };
+
 use winit::{
     application::ApplicationHandler,
     dpi::{PhysicalPosition, PhysicalSize},
     event::{
         ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent,
     },
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
     keyboard::{KeyCode, PhysicalKey},
-    window::Window,
+    window::{Fullscreen, Window},
 };
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, thiserror::Error)]
 enum Error {
+    #[error("Failed to create window: {0}")]
+    OsError(#[from] winit::error::OsError),
+
     #[error("Failed to create surface: {0}")]
     CreateSurfaceError(#[from] CreateSurfaceError),
 
@@ -41,69 +99,621 @@ enum Error {
 
     #[error("Surface is not supported by current adapter")]
     SurfaceIsNotSupportedByAdapter,
+
+    #[error("Failed to map buffer: {0}")]
+    BufferAsyncError(#[from] BufferAsyncError),
+
+    #[error("Buffer mapping was cancelled")]
+    BufferMapCancelled,
+
+    #[error("Failed to wait for the device: {0}")]
+    PollError(#[from] PollError),
+
+    #[error(transparent)]
+    ExportError(#[from] ExportError),
+
+    #[error("Failed to save image: {0}")]
+    ImageError(#[from] image::ImageError),
+
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to (de)serialize JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Failed to access the clipboard: {0}")]
+    ClipboardError(#[from] arboard::Error),
+
+    #[error("Invalid view: {0:?}")]
+    InvalidView(String),
+
+    #[error("Failed to parse TOML: {0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("A gradient has to be at least 2 pixels long")]
+    GradientTooShort,
 }
 
-/// Represents the uniform buffer data. Matches the `struct Uniforms` in the
-/// shader.
-#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-#[repr(C)]
-struct Uniforms {
-    /// Window width and height.
-    resolution: [f64; 2],
-    /// Time in seconds since startup.
-    time: f64,
-    /// Number of zoom-ins. The lower the number, the deeper the zoom.
-    zooms: f64,
-    /// Translation of the center of the coordinate system from 0+0i.
-    offset: [f64; 2],
-    /// Current mouse position, normalized to the range [-1, 1].
-    mouse_position: [f64; 2],
-    /// Whether we are currently rendering the Mandelbrot set or the Julia set.
-    is_mandelbrot: f32,
-    /// Whether should we rotate the colors (creates a trippy rainbow effect).
-    rotate_colors: f32,
-    /// Maximum number of iterations to perform.
-    max_iter: u32,
-    _padding: u32,
+/// Names of the color palettes implemented in the shader, indexed by
+/// [`Uniforms::palette`].
+const PALETTE_NAMES: [&str; 5] =
+    ["Rainbow", "Grayscale", "Fire", "Ocean", "Ultra Fractal"];
+
+/// Names of the fractals implemented in the shader, indexed by
+/// [`Uniforms::fractal_type`].
+const FRACTAL_NAMES: [&str; 5] = [
+    "Mandelbrot",
+    "Burning Ship",
+    "Tricorn",
+    "Multibrot",
+    "Newton",
+];
+
+/// Names of the coloring modes implemented in the shader, indexed by
+/// [`Uniforms::coloring_mode`].
+const COLORING_NAMES: [&str; 3] =
+    ["Escape Time", "Orbit Trap", "Stripe Average"];
+
+/// Names of the mappings of the iteration count to the palette implemented in
+/// the shader, indexed by [`Uniforms::color_scale_mode`].
+const COLOR_SCALE_NAMES: [&str; 3] = ["Linear", "Logarithmic", "Square Root"];
+
+/// The [`Uniforms::coloring_mode`] coloring by the distance of the orbit to a
+/// trap of the [`Uniforms::trap_shape`].
+const ORBIT_TRAP: u32 = 1;
+
+/// The [`Uniforms::coloring_mode`] darkening the gradient by the average of
+/// stripes around the origin over the orbit, see [`Uniforms::stripe_density`].
+const STRIPE_AVERAGE: u32 = 2;
+
+/// Names of the orbit trap shapes implemented in the shader, indexed by
+/// [`Uniforms::trap_shape`].
+const TRAP_NAMES: [&str; 3] = ["Point", "Line", "Cross"];
+
+/// Preset colors of the points inside the set, as names and linear RGB values
+/// of [`Uniforms::interior_color`].
+const INTERIOR_COLORS: [(&str, [f32; 3]); 4] = [
+    ("Black", [0.0, 0.0, 0.0]),
+    ("White", [1.0, 1.0, 1.0]),
+    ("Navy", [0.0, 0.0, 0.13]),
+    ("Dark Red", [0.13, 0.0, 0.0]),
+];
+
+/// The [`Uniforms::fractal_type`] of the Burning Ship fractal.
+const BURNING_SHIP: u32 = 1;
+
+/// The [`Uniforms::fractal_type`] of the Tricorn.
+const TRICORN: u32 = 2;
+
+/// The [`Uniforms::fractal_type`] of the Multibrot set.
+const MULTIBROT: u32 = 3;
+
+/// The [`Uniforms::fractal_type`] of Newton's method for `z^n - 1`, see
+/// [`orbit::newton_degree`].
+const NEWTON: u32 = 4;
+
+/// Range of the Multibrot [`Uniforms::power`].
+const POWER_RANGE: std::ops::RangeInclusive<f32> = 2.0..=10.0;
+
+/// How much [`Uniforms::power`] changes with each key press.
+const POWER_STEP: f32 = 0.5;
+
+/// Range of the [`Uniforms::max_iter`] when changed with the keyboard or
+/// given on the command line or in the config file, see [`clamp_max_iter`].
+const MAX_ITER_RANGE: std::ops::RangeInclusive<u32> = 1..=u32::MAX / 10;
+
+/// Clamps the `max_iter` to [`MAX_ITER_RANGE`], warning the first time it's
+/// above it, since so many iterations can hang the GPU.
+fn clamp_max_iter(max_iter: u32) -> u32 {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    let ceiling = *MAX_ITER_RANGE.end();
+    if max_iter > ceiling && !WARNED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "Warning: max_iter {max_iter} is above {ceiling} and clamped, \
+             such counts can hang the GPU. Adaptive iterations, the \
+             `auto_max_iter` action of the [keys] in config.toml, tune the \
+             count to the view instead"
+        );
+    }
+    max_iter.clamp(*MAX_ITER_RANGE.start(), ceiling)
 }
 
-const _: () = assert!(std::mem::size_of::<Uniforms>() % 16 == 0);
+/// Normalizes the pixel `position` within the `[x, y, width, height]`
+/// `viewport` so that its width spans [-1, 1] and its height keeps the aspect
+/// ratio, with `y` pointing down, like `pixel_to_plane` in the shader does
+/// before rotating, with `y` pointing up.
+fn normalize_position([x, y]: [f64; 2], viewport: [f64; 4]) -> [f64; 2] {
+    let [vx, vy, w, h] = viewport;
+    let nx = (x - vx).mul_add(2.0, -w) / w;
+    let ny = (y - vy).mul_add(2.0, -h) / w;
+    [nx, ny]
+}
 
-impl Default for Uniforms {
-    fn default() -> Self {
-        Self {
-            resolution: Default::default(),
-            time: Default::default(),
-            zooms: 8.0,
-            offset: [(0.25 - 2.0) / 2.0, 0.0],
-            mouse_position: [0.0, 0.0],
-            // offset: [-1.999_491_453_530_413, 0.0],
-            is_mandelbrot: 1.0,
-            rotate_colors: 1.0,
-            max_iter: 1500,
-            _padding: 0,
-        }
+/// Translates the `uniforms` after their [`Uniforms::zooms`] changed from
+/// `from_zooms`, so that the point under the [`Uniforms::mouse_position`]
+/// stays where it was.
+fn keep_point_under_cursor(uniforms: &mut Uniforms, from_zooms: f64) {
+    // The point under the cursor is at the mouse position times the zoom
+    // factor from the offset, so the offset moves by the mouse position times
+    // the change of the factor. Computing that from a single read of the
+    // mouse position and a single rounded difference, instead of subtracting
+    // two separately rounded coordinates, keeps the point from drifting over
+    // many scrolls, and keeps the precision of deep zooms.
+    let [mx, my] = uniforms.mouse_position;
+    let [x, y] = uniforms.rotated([mx, -my]);
+    let change = (from_zooms / 10.0).exp() - (uniforms.zooms / 10.0).exp();
+    uniforms.translate([x * change, y * change]);
+}
+
+/// Returns how far the view of the `uniforms` pans in the complex plane in the
+/// given `direction` on the screen, each axis in the range [-1, 1], by
+/// `distance` times the zoom factor.
+fn pan_delta(
+    uniforms: &Uniforms,
+    (x, y): (f64, f64),
+    distance: f64,
+) -> [f64; 2] {
+    // Holding two keys shouldn't pan diagonally faster than one does.
+    let length = x.hypot(y).max(1.0);
+    let (x, y) = (x / length, y / length);
+    let step = distance * (uniforms.zooms / 10.0).exp();
+    uniforms.rotated([x * step, y * step])
+}
+
+/// Range of the [`Uniforms::band_count`].
+const BAND_COUNT_RANGE: std::ops::RangeInclusive<f32> = 4.0..=4096.0;
+
+/// Factor a key press changes [`Uniforms::band_count`] by.
+const BAND_COUNT_FACTOR: f32 = 1.25;
+
+/// Range of the [`Uniforms::stripe_density`].
+const STRIPE_DENSITY_RANGE: std::ops::RangeInclusive<f32> = 1.0..=16.0;
+
+/// Range of the [`Uniforms::escape_radius`]. It has to be at least 2 for the
+/// escaped orbits to diverge.
+const ESCAPE_RADIUS_RANGE: std::ops::RangeInclusive<f32> = 2.0..=65536.0;
+
+/// The highest [`Uniforms::zooms`], i.e. the most zoomed-out view.
+const MAX_ZOOMS: f64 = 42.0;
+
+/// The [`Uniforms::zooms`] the Julia set is initially shown with.
+const JULIA_ZOOMS: f64 = 9.162_907_318_741_551; // 10 * ln(2.5)
+
+/// The angle in radians a press of a rotation key rotates the view by.
+const ROTATION_STEP: f32 = PI / 36.0; // 5 degrees
+
+/// The phase in radians a press of a color shift key shifts the palettes by,
+/// see [`Uniforms::color_offset`].
+const COLOR_OFFSET_STEP: f32 = TAU / 32.0;
+
+/// How long the smooth zoom animation takes.
+const ZOOM_ANIM_DURATION: Duration = Duration::from_secs(1);
+
+/// Duration of the animation back to the default view on reset.
+const RESET_ANIM_DURATION: Duration = Duration::from_millis(1500);
+
+/// How much the smooth zoom animation zooms in, in [`Uniforms::zooms`].
+const ZOOM_ANIM_ZOOMS: f64 = 20.0;
+
+/// How long the morph between the Mandelbrot and Julia sets takes.
+const MORPH_DURATION: Duration = Duration::from_millis(500);
+
+/// Maximum time between two clicks to count as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(300);
+
+/// Number of pixels of a [`MouseScrollDelta::PixelDelta`], as sent by
+/// trackpads, that zoom as much as a single line of a mouse wheel.
+const PIXELS_PER_LINE: f64 = 40.0;
+
+/// A smooth transition of the view by a `pan` to a target `zooms`.
+#[derive(Debug, Clone, Copy)]
+struct ZoomAnim {
+    /// When the animation started.
+    start: Instant,
+    /// How long the animation takes.
+    duration: Duration,
+    /// The [`Uniforms::offset`] and [`Uniforms::offset_lo`] the animation
+    /// started at.
+    from_offset: ([f64; 2], [f64; 2]),
+    /// The [`Uniforms::zooms`] the animation started at.
+    from_zooms: f64,
+    /// How far the offset moves from `from_offset` by the end of the
+    /// animation. Relative, so that it keeps the precision of deep zooms.
+    pan: [f64; 2],
+    /// The [`Uniforms::zooms`] the animation ends at.
+    to_zooms: f64,
+}
+
+impl ZoomAnim {
+    /// Returns the animation progress in range [0, 1], eased in and out.
+    #[must_use]
+    fn progress(&self) -> f64 {
+        let t = (self.start.elapsed().as_secs_f64()
+            / self.duration.as_secs_f64())
+        .clamp(0.0, 1.0);
+        t * t * 2.0f64.mul_add(-t, 3.0)
+    }
+
+    /// Returns the interpolated pan from the `from_offset` and `zooms` at the
+    /// current moment.
+    #[must_use]
+    fn current(&self) -> ([f64; 2], f64) {
+        let t = self.progress();
+        let lerp = |a: f64, b: f64| (b - a).mul_add(t, a);
+        (
+            self.pan.map(|x| x * t),
+            lerp(self.from_zooms, self.to_zooms),
+        )
+    }
+
+    /// Whether the animation has reached its target.
+    #[must_use]
+    fn is_finished(&self) -> bool {
+        self.start.elapsed() >= self.duration
+    }
+}
+
+/// A smooth transition of [`Uniforms::is_mandelbrot`] from one set to the
+/// other, while the view pans and zooms to the one of the other set.
+#[derive(Debug, Clone, Copy)]
+struct Morph {
+    /// When the morph started.
+    start: Instant,
+    /// The [`Uniforms::is_mandelbrot`] the morph ends at, `0.0` or `1.0`.
+    to: f32,
+    /// The `offset` and `zooms` of the view the morph ends at.
+    view: ([f64; 2], f64),
+}
+
+impl Morph {
+    /// Returns the [`Uniforms::is_mandelbrot`] at the current moment, eased in
+    /// and out.
+    #[must_use]
+    fn current(&self) -> f32 {
+        let t = (self.start.elapsed().as_secs_f32()
+            / MORPH_DURATION.as_secs_f32())
+        .clamp(0.0, 1.0);
+        let t = t * t * 2.0f32.mul_add(-t, 3.0);
+        if self.to == 1.0 { t } else { 1.0 - t }
+    }
+
+    /// Whether the morph has reached the other set.
+    #[must_use]
+    fn is_finished(&self) -> bool {
+        self.start.elapsed() >= MORPH_DURATION
     }
 }
 
+/// How many [`Uniforms::zooms`] holding a zoom key zooms per second.
+const KEY_ZOOM_SPEED: f64 = 15.0;
+
+/// Longest frame time that movement and zoom are applied for, so that a stall
+/// doesn't make the view jump.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Number of frames the frame time is averaged over in the overlay.
+const FRAME_TIMES_WINDOW: usize = 60;
+
+/// How often the connected gamepads and the shader files are polled while
+/// the window isn't redrawn.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Minimum time between two updates of the window title.
+const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of decimals the coordinates in the window title show beyond those
+/// telling the pixels of the view apart.
+const TITLE_EXTRA_DECIMALS: f64 = 3.0;
+
+/// Maximum number of decimals the coordinates in the window title show, more
+/// than an `f64` holds near the origin.
+const TITLE_MAX_DECIMALS: f64 = 20.0;
+
+/// Everything the window title shows, so that it's only rebuilt when some of
+/// it changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TitleInputs {
+    /// The [`Uniforms`], without the ones changing every frame without
+    /// changing the view, e.g. the time.
+    uniforms: Uniforms,
+    show_info: bool,
+    auto_max_iter: bool,
+    present_mode: PresentMode,
+    sample_count: Option<u32>,
+    viewport_width: u32,
+    inside_percentage: Option<f64>,
+    julia_locked: bool,
+}
+
 /// The data related to the current view in the window.
 #[derive(Debug)]
 struct View {
-    /// Timer that starts when the window is created.
+    /// Timer that starts when the window is created, shifted forward by the
+    /// time spent paused.
     time: Instant,
-    /// Keyboard movement delta.
+    /// Whether [`Uniforms::time`] is frozen, stopping the color rotation.
+    paused: bool,
+    /// When the previous frame was updated.
+    last_frame: Instant,
+    /// Durations of the last [`FRAME_TIMES_WINDOW`] frames.
+    frame_times: VecDeque<Duration>,
+    /// The current window title, and when it was last rebuilt.
+    title: (String, Option<Instant>),
+    /// What the current window title shows, see [`AppState::title_inputs`].
+    title_inputs: Option<TitleInputs>,
+    /// Whether the view may have changed since the title was last rebuilt,
+    /// which it then is once [`TITLE_UPDATE_INTERVAL`] passed.
+    title_outdated: bool,
+    /// Whether to show the FPS and frame time overlay.
+    show_overlay: bool,
+    /// Whether the window title shows the state of the view, or just the
+    /// name of the window for clean captures.
+    show_title_info: bool,
+    /// Keyboard movement direction, each axis in the range [-1, 1].
     movement_delta: (f64, f64),
+    /// Keyboard zoom direction in the range [-1, 1], positive when zooming
+    /// out.
+    zoom_delta: f64,
+    /// The actions of the keys held down that last while they're held, to
+    /// stop them when the keys are released, whatever the modifiers then.
+    held_keys: HashMap<KeyCode, Action>,
+    /// Deflection of the gamepad's left stick, each axis in the range [-1, 1].
+    stick: (f64, f64),
+    /// How far the gamepad's left and right triggers are pressed, in the range
+    /// [0, 1].
+    triggers: (f64, f64),
     /// Whether the control key is pressed.
     ctrl_pressed: bool,
+    /// Whether the shift key is pressed.
+    shift_pressed: bool,
     /// Whether the mouse button is clicked.
     mouse_clicked: bool,
-    /// Whether the window should be in fullscreen mode.
-    fullscreen: bool,
+    /// Whether the middle mouse button is held, which pans like dragging with
+    /// the left one.
+    panning: bool,
+    /// The last cursor position in the window, in pixels. `None` if the cursor
+    /// is outside of the window.
+    last_cursor: Option<PhysicalPosition<f64>>,
+    /// Positions of the fingers touching the screen, by touch ID.
+    touches: HashMap<u64, PhysicalPosition<f64>>,
+    /// Velocity of the drag, with which the view glides after its release.
+    inertia: Inertia,
+    /// When the mouse button was last clicked, to detect double-clicks.
+    last_click: Option<Instant>,
+    /// The currently running smooth zoom animation.
+    animation: Option<ZoomAnim>,
+    /// The currently running morph between the Mandelbrot and Julia sets.
+    morph: Option<Morph>,
+    /// The running demo, see [`Demo`].
+    demo: Option<Demo>,
+    /// The scale factor of the [`Window`], i.e. physical pixels per logical
+    /// pixel.
+    scale_factor: f64,
+    /// The fullscreen mode of the window, `None` if it's windowed.
+    fullscreen: Option<Fullscreen>,
+    /// How the window is made fullscreen, see [`AppState::toggle_fullscreen`].
+    fullscreen_mode: FullscreenMode,
+    /// Supersampling factor: the fractal is rendered at `render_scale` times
+    /// the window resolution and downsampled to it.
+    render_scale: u32,
+    /// The aspect ratio (width / height) the fractal is letterboxed to, see
+    /// [`AppState::viewport`]. `None` if it fills the window.
+    lock_aspect: Option<f64>,
+    /// Index of the bookmark to restore next.
+    bookmark_index: usize,
+    /// The zoom animation being recorded to a video, if any.
+    recording: Option<Recording>,
+    /// The views that can be undone and redone.
+    history: History,
+    /// Text typed into the coordinate prompt, `None` if it's closed.
+    prompt: Option<String>,
+    /// Whether to show a small Julia set for the point under the cursor while
+    /// viewing the Mandelbrot set.
+    show_julia_inset: bool,
+    /// Whether to show the minimap, see [`Minimap`].
+    show_minimap: bool,
+    /// Whether to show the crosshair, see [`Crosshair`].
+    show_crosshair: bool,
+    /// Whether [`Uniforms::julia_c`] is frozen instead of following the mouse.
+    julia_locked: bool,
+    /// The `offset` and `zooms` of the set that isn't currently displayed,
+    /// restored when toggling between the Mandelbrot and Julia sets.
+    hidden_view: ([f64; 2], f64),
+    /// The last [`Uniforms::offset`], [`Uniforms::offset_lo`] and
+    /// [`Uniforms::zooms`] that were finite, restored if they stop being so,
+    /// see [`AppState::recover_view`].
+    last_valid_view: ([f64; 2], [f64; 2], f64),
+    /// Whether something changed since the last frame, e.g. because of
+    /// input, so that the window has to be redrawn, see
+    /// [`AppState::needs_redraw`].
+    redraw: bool,
+    /// Whether the window wasn't redrawn after the last frame because nothing
+    /// changed.
+    idle: bool,
+    /// Whether the window is unfocused, hidden or minimized, so that it isn't
+    /// redrawn until it's back, see [`AppState::needs_redraw`] and
+    /// [`AppState::update_paused_render`].
+    paused_render: bool,
+    /// Whether the window has the keyboard focus.
+    focused: bool,
+    /// Whether the window is hidden, e.g. by other windows or minimized.
+    occluded: bool,
+    /// Whether the window has no size, as minimized windows on some
+    /// platforms.
+    zero_size: bool,
     /// The current uniform buffer data, which is written to the GPU every
     /// [`AppState::update`].
     uniforms: Uniforms,
 }
 
+/// Which adapters to choose from, set by the command-line arguments.
+#[derive(Debug, Clone, Copy, Default)]
+struct AdapterOptions {
+    /// The graphics APIs the adapter may use.
+    backends: Backends,
+    /// Whether to prefer an integrated or a discrete GPU.
+    power_preference: PowerPreference,
+    /// Whether to print the details of the chosen adapter and the features
+    /// of the device, see [`print_device_info`].
+    verbose: bool,
+}
+
+/// A `wgpu` [`Instance`] and the surface of the window created with it, if
+/// there is a window.
+type Target = (Instance, Option<Surface<'static>>);
+
+/// Creates the [`Target`] of the backends of the `options`, and one of any
+/// backend to fall back to if they differ. Some platforms require creating
+/// the surface of the `window` on the main thread.
+fn create_targets(
+    options: AdapterOptions,
+    window: Option<&Arc<Window>>,
+) -> Result<(Target, Option<Target>), Error> {
+    let create = |backends| -> Result<Target, Error> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends,
+            flags: InstanceFlags::default(),
+            memory_budget_thresholds: MemoryBudgetThresholds::default(),
+            backend_options: BackendOptions::default(),
+        });
+        let surface = window
+            .map(|window| instance.create_surface(window.clone()))
+            .transpose()?;
+        Ok((instance, surface))
+    };
+
+    let fallback = (options.backends != Backends::default())
+        .then(|| create(Backends::default()))
+        .transpose()?;
+    Ok((create(options.backends)?, fallback))
+}
+
+/// Requests an adapter matching the `options` from the `target`, falling back
+/// to the `fallback` if it has none, see [`create_targets`]. If the targets
+/// have a surface, the adapter has to be able to present to it, and the
+/// surface is returned as well.
+async fn request_adapter(
+    options: AdapterOptions,
+    (target, fallback): (Target, Option<Target>),
+) -> Result<(Adapter, Option<Surface<'static>>), Error> {
+    let request = async |(instance, surface): Target| -> Result<_, Error> {
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: options.power_preference,
+                force_fallback_adapter: false,
+                compatible_surface: surface.as_ref(),
+            })
+            .await?;
+        Ok((adapter, surface))
+    };
+
+    let (adapter, surface) = match (request(target).await, fallback) {
+        (Err(e), Some(fallback)) => {
+            eprintln!("No adapter for the requested backend ({e}), using any");
+            request(fallback).await?
+        }
+        (result, _) => result?,
+    };
+    let info = adapter.get_info();
+    eprintln!("Using {} ({:?})", info.name, info.backend);
+    Ok((adapter, surface))
+}
+
+/// Requests a device from the `adapter`, see [`renderer::request_device`],
+/// warning if the fractal can only be computed in `f32`.
+async fn request_device(
+    adapter: &Adapter,
+) -> Result<(Device, Queue, Precision), RequestDeviceError> {
+    let (device, queue, precision) = renderer::request_device(adapter).await?;
+    if precision == Precision::Single {
+        eprintln!(
+            "The GPU doesn't support f64 in shaders, falling back to f32. \
+             The maximum zoom depth will be much lower."
+        );
+    }
+    Ok((device, queue, precision))
+}
+
+/// Prints the details of the `adapter` and the features enabled on the
+/// `device` created from it, for bug reports.
+fn print_device_info(adapter: &Adapter, device: &Device, precision: Precision) {
+    let info = adapter.get_info();
+    eprintln!("Adapter: {}", info.name);
+    eprintln!("Backend: {:?}", info.backend);
+    eprintln!("Device type: {:?}", info.device_type);
+    let driver = format!("{} {}", info.driver, info.driver_info);
+    eprintln!("Driver: {}", driver.trim());
+    eprintln!("Features: {:?}", device.features());
+    eprintln!("Precision: {precision:?}");
+}
+
+/// Returns the size of the surface of a window of `window_size`, clamped to
+/// the largest texture the `device` supports, which can't be presented
+/// otherwise. The window is then scaled by the system to fit.
+fn surface_size(window_size: PhysicalSize<u32>, device: &Device) -> (u32, u32) {
+    let max_size = device.limits().max_texture_dimension_2d;
+    (
+        window_size.width.clamp(1, max_size),
+        window_size.height.clamp(1, max_size),
+    )
+}
+
+/// Returns the `background` color, as sRGB components in [0, 1], encoded
+/// like the colors the shader outputs to a surface with the given
+/// `encode_srgb` and `output_scale`, see [`Uniforms::encode_srgb`] and
+/// [`Uniforms::output_scale`].
+fn clear_color(
+    background: [f64; 3],
+    encode_srgb: bool,
+    output_scale: f32,
+) -> Color {
+    let [r, g, b] = background.map(|c| {
+        if encode_srgb {
+            c
+        } else {
+            srgb_to_linear(c) * f64::from(output_scale)
+        }
+    });
+    Color { r, g, b, a: 1.0 }
+}
+
+/// Presents a frame of the `surface` configured with `config`, cleared to the
+/// `background` and saying that the explorer is loading with the `overlay`,
+/// so that the window isn't left blank while the pipelines are created. A
+/// frame that can't be presented is skipped, since one follows soon.
+fn present_loading_frame(
+    device: &Device,
+    queue: &Queue,
+    surface: &Surface,
+    config: &SurfaceConfiguration,
+    overlay: &mut Overlay,
+    background: Color,
+) {
+    let Ok(frame) = surface.get_current_texture() else {
+        return;
+    };
+    let view = frame.texture.create_view(&TextureViewDescriptor::default());
+    overlay.set_text(device, queue, "Loading...");
+    let mut encoder =
+        device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.begin_render_pass(&RenderPassDescriptor {
+        label: Some("Loading Render Pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(background),
+                store: StoreOp::Store,
+            },
+            depth_slice: None,
+        })],
+        ..Default::default()
+    });
+    overlay.draw(&mut encoder, &view, (config.width, config.height));
+    queue.submit([encoder.finish()]);
+    frame.present();
+}
+
 /// The state of the application with all the resources needed to render and
 /// maintain the connection to the GPU.
 #[derive(Debug)]
@@ -113,109 +723,132 @@ struct AppState {
     device: Device,
     queue: Queue,
     config: SurfaceConfiguration,
-    render_pipeline: RenderPipeline,
-    bind_group: BindGroup,
-    buffer: Buffer,
+    renderer: MandelbrotRenderer,
+    /// The sample counts above 1 supported for MSAA, in ascending order,
+    /// empty if it's unsupported.
+    sample_counts: Vec<u32>,
+    /// Counting of the pixels inside the set, if it's shown.
+    inside_stats: Option<InsideStats>,
+    /// Automatic tuning of the maximum iterations, if it's enabled.
+    auto_max_iter: Option<AutoMaxIter>,
+    /// The `f32` half of the precision comparison, if it's enabled.
+    comparison: Option<PrecisionComparison>,
+    /// Watches the shader files for changes, if `--dev` was given.
+    shader_watcher: Option<ShaderWatcher>,
+    /// The title given with `--title`, shown before the details of the view
+    /// in the window title.
+    custom_title: Option<String>,
+    /// The running benchmark, if `--bench` was given.
+    bench: Option<Bench>,
+    /// Uniforms of the Julia set inset, see [`View::show_julia_inset`].
+    inset_bind_group: BindGroup,
+    inset_buffer: Buffer,
+    minimap: Minimap,
+    crosshair: Crosshair,
+    overlay: Overlay,
+    /// Present modes supported by the [`Surface`].
+    present_modes: Vec<PresentMode>,
+    /// See [`Uniforms::output_scale`], depends on whether the [`Surface`] is
+    /// HDR.
+    output_scale: f32,
+    /// Whether the [`Surface`] has no sRGB format, see
+    /// [`Uniforms::encode_srgb`].
+    encode_srgb: bool,
+    /// The system clipboard, connected to on the first copy or paste.
+    clipboard: Option<Clipboard>,
+    /// User settings.
+    settings: Config,
+    /// Whether a gradient was loaded with `--palette`, see
+    /// [`GRADIENT_PALETTE`].
+    gradient_loaded: bool,
     view: View,
 }
 
 impl AppState {
-    /// Creates a new [`AppState`] using the given [`Window`] to initialize the
-    /// [`Instance`].
+    /// Creates a new [`AppState`] rendering to the given [`Window`] through the
+    /// `targets` created for it, starting with the view described by the
+    /// `uniforms` and the given user `settings`, on an adapter chosen by the
+    /// `options`. If `hdr_white` is given, the surface is HDR if possible,
+    /// with the white of the palettes that many nits bright. The colors of
+    /// the `gradient` are loaded for the [`GRADIENT_PALETTE`], if given.
     #[allow(clippy::too_many_lines, reason = "whatever")]
-    async fn new(window: Arc<Window>) -> Result<Self, Error> {
+    async fn new(
+        window: Arc<Window>,
+        targets: (Target, Option<Target>),
+        uniforms: Uniforms,
+        settings: Config,
+        options: AdapterOptions,
+        hdr_white: Option<f32>,
+        gradient: Option<Vec<[u8; 4]>>,
+    ) -> Result<Self, Error> {
         let window_size = window.inner_size();
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::default(),
-            flags: InstanceFlags::default(),
-            memory_budget_thresholds: MemoryBudgetThresholds::default(),
-            backend_options: BackendOptions::default(),
-        });
-
-        let surface = instance.create_surface(window.clone())?;
-
-        let adapter = instance
-            .request_adapter(&RequestAdapterOptions::default())
-            .await?;
-        let (device, queue) = adapter
-            .request_device(&DeviceDescriptor {
-                label: Some("Device"),
-                required_features: Features::SHADER_F64
-                    | Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
-                ..Default::default()
-            })
-            .await?;
+        let scale_factor = window.scale_factor();
+        let (adapter, surface) = request_adapter(options, targets).await?;
+        let surface = surface.expect("the targets have surfaces");
+        let (device, queue, precision) = request_device(&adapter).await?;
+        if options.verbose {
+            print_device_info(&adapter, &device, precision);
+        }
 
-        let config = surface
-            .get_default_config(&adapter, window_size.width, window_size.height)
+        let (width, height) = surface_size(window_size, &device);
+        let mut config = surface
+            .get_default_config(&adapter, width, height)
             .ok_or(Error::SurfaceIsNotSupportedByAdapter)?;
-
-        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
-
-        let buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Uniforms Buffer"),
-            size: std::mem::size_of::<Uniforms>() as u64,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
+        let capabilities = surface.get_capabilities(&adapter);
+        // The shader outputs linear colors, which an sRGB format encodes.
+        let srgb_format = config.format.add_srgb_suffix();
+        if capabilities.formats.contains(&srgb_format) {
+            config.format = srgb_format;
+        }
+        let output_scale = hdr_white.map_or(1.0, |nits| {
+            hdr::configure_hdr(&mut config, &capabilities, nits)
         });
+        let encode_srgb = !config.format.is_srgb()
+            && config.format != TextureFormat::Rgba16Float;
 
-        let bind_group_layout =
-            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-                label: Some("Bind Group Layout"),
-                entries: &[BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX_FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::default(),
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
+        let background =
+            clear_color(settings.background, encode_srgb, output_scale);
+        let mut overlay = Overlay::new(&device, config.format);
+        surface.configure(&device, &config);
+        present_loading_frame(
+            &device,
+            &queue,
+            &surface,
+            &config,
+            &mut overlay,
+            background,
+        );
 
-        let bind_group = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-        });
+        let mut renderer =
+            MandelbrotRenderer::new(&device, &queue, precision, config.format);
+        renderer.set_background(background);
+        renderer.set_pass_iters(settings.progressive_iters);
+        // Before the other uniforms are bound with it.
+        if let Some(colors) = &gradient {
+            renderer.set_gradient(colors);
+        }
+        let (inset_buffer, inset_bind_group) = create_uniforms_buffer(
+            &device,
+            renderer.bind_group_layout(),
+            precision,
+            renderer.gradient(),
+            "Julia Inset Uniforms",
+        );
 
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[&bind_group_layout],
-                push_constant_ranges: &[],
-            });
+        // The shader has sample positions for up to 8 samples.
+        let format_features =
+            adapter.get_texture_format_features(config.format);
+        let sample_counts = [2, 4, 8]
+            .into_iter()
+            .filter(|&count| {
+                format_features.flags.sample_count_supported(count)
+            })
+            .collect();
 
-        let render_pipeline =
-            device.create_render_pipeline(&RenderPipelineDescriptor {
-                label: Some("Render Pipeline"),
-                vertex: VertexState {
-                    module: &shader,
-                    entry_point: None,
-                    compilation_options: PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(FragmentState {
-                    module: &shader,
-                    entry_point: None,
-                    compilation_options: PipelineCompilationOptions::default(),
-                    targets: &[Some(ColorTargetState {
-                        format: config.format,
-                        blend: None,
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                layout: Some(&render_pipeline_layout),
-                primitive: PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: MultisampleState::default(),
-                multiview: None,
-                cache: None,
-            });
+        let minimap = Minimap::new(&device, &renderer, config.format);
+        let crosshair = Crosshair::new(&device, config.format);
+        let present_modes = surface.get_capabilities(&adapter).present_modes;
+        let fullscreen_mode = settings.fullscreen;
 
         let mut state = Self {
             window,
@@ -223,20 +856,88 @@ impl AppState {
             device,
             queue,
             config,
-            render_pipeline,
-            bind_group,
-            buffer,
+            renderer,
+            sample_counts,
+            inside_stats: None,
+            auto_max_iter: None,
+            comparison: None,
+            shader_watcher: None,
+            custom_title: None,
+            bench: None,
+            inset_bind_group,
+            inset_buffer,
+            minimap,
+            crosshair,
+            overlay,
+            present_modes,
+            output_scale,
+            encode_srgb,
+            clipboard: None,
+            settings,
+            gradient_loaded: gradient.is_some(),
             view: View {
                 time: Instant::now(),
-                uniforms: Uniforms::default(),
+                paused: false,
+                last_frame: Instant::now(),
+                frame_times: VecDeque::with_capacity(FRAME_TIMES_WINDOW),
+                title: (String::new(), None),
+                title_inputs: None,
+                title_outdated: false,
+                show_overlay: false,
+                show_title_info: true,
+                redraw: true,
+                idle: false,
+                paused_render: false,
+                focused: true,
+                occluded: false,
+                zero_size: false,
+                uniforms,
                 movement_delta: (0.0, 0.0),
+                zoom_delta: 0.0,
+                held_keys: HashMap::new(),
+                stick: (0.0, 0.0),
+                triggers: (0.0, 0.0),
                 ctrl_pressed: false,
+                shift_pressed: false,
                 mouse_clicked: false,
-                fullscreen: false,
+                panning: false,
+                last_cursor: None,
+                touches: HashMap::new(),
+                inertia: Inertia::default(),
+                last_click: None,
+                animation: None,
+                morph: None,
+                demo: None,
+                scale_factor,
+                fullscreen: None,
+                fullscreen_mode,
+                render_scale: 1,
+                lock_aspect: None,
+                bookmark_index: 0,
+                recording: None,
+                prompt: None,
+                history: History::default(),
+                show_julia_inset: false,
+                show_minimap: false,
+                show_crosshair: false,
+                julia_locked: false,
+                last_valid_view: (
+                    uniforms.offset,
+                    uniforms.offset_lo,
+                    uniforms.zooms,
+                ),
+                hidden_view: if uniforms.is_mandelbrot == 1.0 {
+                    ([0.0, 0.0], JULIA_ZOOMS)
+                } else {
+                    let Uniforms { offset, zooms, .. } = Uniforms::default();
+                    (offset, zooms)
+                },
             },
         };
 
         state.resize(state.window.inner_size());
+        // Clamp the initial zoom to the range supported with the precision.
+        state.zoom(0.0);
         // state.zoom(8.0);
         // state.translate((-1.999_491_453_530_413, 0.0));
         state.update();
@@ -247,23 +948,250 @@ impl AppState {
     /// Reconfigures the [`Surface`] to the new [`Window`] size.
     fn resize(&mut self, window_size: PhysicalSize<u32>) {
         if window_size.width > 0 && window_size.height > 0 {
-            self.config.width = window_size.width;
-            self.config.height = window_size.height;
+            (self.config.width, self.config.height) =
+                surface_size(window_size, &self.device);
             self.surface.configure(&self.device, &self.config);
+            self.recreate_render_targets();
+            // The same cursor position maps to another point in the new size.
+            if let Some(cursor) = self.view.last_cursor {
+                self.move_mouse(cursor);
+            }
         }
     }
 
+    /// Handles a change of the [`Window`] scale factor, e.g. when it's moved
+    /// to a monitor with another DPI. The window is resized in physical
+    /// pixels, and the last cursor position, which is in physical pixels as
+    /// well, is rescaled to stay over the same point until the cursor moves.
+    fn rescale(&mut self, scale_factor: f64) {
+        let ratio = scale_factor / self.view.scale_factor;
+        self.view.scale_factor = scale_factor;
+        if let Some(cursor) = &mut self.view.last_cursor {
+            *cursor = PhysicalPosition::new(cursor.x * ratio, cursor.y * ratio);
+        }
+        self.resize(self.window.inner_size());
+    }
+
+    /// Recreates the offscreen textures the fractal is rendered into to match
+    /// the current [`AppState::viewport`] and [`View::render_scale`].
+    fn recreate_render_targets(&mut self) {
+        // The window may have grown too large for the current scale.
+        while self.view.render_scale > self.max_render_scale().max(1) {
+            self.view.render_scale /= 2;
+        }
+        let had_histogram = self.renderer.has_histogram();
+        self.renderer.set_layout(
+            (self.config.width, self.config.height),
+            self.viewport(),
+            self.view.render_scale,
+        );
+        if had_histogram && !self.renderer.has_histogram() {
+            eprintln!("The window is too large for histogram equalization");
+        }
+    }
+
+    /// Returns the size the fractal is rendered at: the size of the
+    /// [`AppState::viewport`] multiplied by [`View::render_scale`].
+    #[must_use]
+    fn render_size(&self) -> (u32, u32) {
+        let [_, _, width, height] = self.viewport();
+        (
+            width * self.view.render_scale,
+            height * self.view.render_scale,
+        )
+    }
+
+    /// Returns the `[x, y, width, height]` of the part of the window the
+    /// fractal is drawn into: the whole window, or the largest centered
+    /// rectangle of the [`View::lock_aspect`] ratio.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "rounded and within the window size"
+    )]
+    fn viewport(&self) -> [u32; 4] {
+        let (width, height) = (self.config.width, self.config.height);
+        let Some(aspect) = self.view.lock_aspect else {
+            return [0, 0, width, height];
+        };
+        let (w, h) = (f64::from(width), f64::from(height));
+        let (w, h) = if w > h * aspect {
+            ((h * aspect).round() as u32, height)
+        } else {
+            (width, (w / aspect).round() as u32)
+        };
+        let (w, h) = (w.clamp(1, width.max(1)), h.clamp(1, height.max(1)));
+        [
+            width.saturating_sub(w) / 2,
+            height.saturating_sub(h) / 2,
+            w,
+            h,
+        ]
+    }
+
+    /// Locks the aspect ratio of the fractal to the current one of the
+    /// window, letterboxing it when the window is resized, or unlocks it.
+    fn toggle_aspect_lock(&mut self) {
+        self.view.lock_aspect = match self.view.lock_aspect {
+            Some(_) => None,
+            None => {
+                let (width, height) = (self.config.width, self.config.height);
+                Some(f64::from(width) / f64::from(height.max(1)))
+            }
+        };
+        self.recreate_render_targets();
+        if let Some(cursor) = self.view.last_cursor {
+            self.move_mouse(cursor);
+        }
+    }
+
+    /// Cycles the supersampling [`View::render_scale`] between 1x, 2x and 4x,
+    /// skipping the scales that would exceed the maximum texture size.
+    fn cycle_render_scale(&mut self) {
+        let max_scale = self.max_render_scale();
+        self.view.render_scale = match self.view.render_scale {
+            1 if max_scale >= 2 => 2,
+            2 if max_scale >= 4 => 4,
+            _ => 1,
+        };
+        self.recreate_render_targets();
+    }
+
+    /// Returns the largest [`View::render_scale`] that keeps the
+    /// [`AppState::render_size`] within the maximum texture size.
+    #[must_use]
+    fn max_render_scale(&self) -> u32 {
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        max_size / self.config.width.max(self.config.height).max(1)
+    }
+
+    /// Frees GPU memory after the surface ran out of it, by halving the
+    /// [`View::render_scale`] or else disabling the most memory-hungry of the
+    /// enabled rendering modes. Returns whether anything could be freed.
+    fn free_gpu_memory(&mut self) -> bool {
+        if self.view.render_scale > 1 {
+            self.view.render_scale /= 2;
+            self.recreate_render_targets();
+            eprintln!(
+                "Out of GPU memory, reducing the render scale to {}x",
+                self.view.render_scale,
+            );
+        } else if self.renderer.sample_count() > 1 {
+            self.renderer.set_msaa(1);
+            eprintln!("Out of GPU memory, disabling MSAA");
+        } else if self.renderer.has_histogram() {
+            self.renderer.set_histogram(false);
+            eprintln!("Out of GPU memory, disabling histogram equalization");
+        } else if self.renderer.accumulated_samples().is_some() {
+            self.renderer.set_accumulation(false);
+            eprintln!("Out of GPU memory, disabling accumulated anti-aliasing");
+        } else if self.renderer.progress().is_some() {
+            self.renderer.set_progressive(false);
+            eprintln!("Out of GPU memory, disabling progressive rendering");
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Cycles multisample anti-aliasing through off and the supported
+    /// [`AppState::sample_counts`].
+    fn cycle_msaa(&mut self) {
+        if self.sample_counts.is_empty() {
+            eprintln!("MSAA is not supported for the surface format");
+            return;
+        }
+        let current = self.renderer.sample_count();
+        let next = self
+            .sample_counts
+            .iter()
+            .copied()
+            .find(|&count| count > current)
+            .unwrap_or(1);
+        self.renderer.set_msaa(next);
+    }
+
+    /// Toggles progressive rendering, see
+    /// [`MandelbrotRenderer::set_progressive`].
+    fn toggle_progressive(&mut self) {
+        let enabled = self.renderer.progress().is_none();
+        self.renderer.set_progressive(enabled);
+    }
+
+    /// Toggles accumulated anti-aliasing, see
+    /// [`MandelbrotRenderer::set_accumulation`].
+    fn toggle_accumulation(&mut self) {
+        let enabled = self.renderer.accumulated_samples().is_none();
+        self.renderer.set_accumulation(enabled);
+    }
+
+    /// Toggles the histogram equalized coloring, see
+    /// [`MandelbrotRenderer::set_histogram`].
+    fn toggle_histogram(&mut self) {
+        if self.renderer.has_histogram() {
+            self.renderer.set_histogram(false);
+        } else if self.renderer.histogram_fits() {
+            self.renderer.set_histogram(true);
+        } else {
+            eprintln!("The window is too large for histogram equalization");
+        }
+    }
+
+    /// Switches to the next supported present mode out of [`PresentMode::Fifo`]
+    /// (vsync), [`PresentMode::Mailbox`] and [`PresentMode::Immediate`].
+    fn cycle_present_mode(&mut self) {
+        const MODES: [PresentMode; 3] = [
+            PresentMode::Fifo,
+            PresentMode::Mailbox,
+            PresentMode::Immediate,
+        ];
+        let current = MODES
+            .iter()
+            .position(|&m| m == self.config.present_mode)
+            .unwrap_or(0);
+        let Some(next) = (1..MODES.len())
+            .map(|i| MODES[(current + i) % MODES.len()])
+            .find(|m| self.present_modes.contains(m))
+        else {
+            eprintln!("No other present modes are supported");
+            return;
+        };
+        self.config.present_mode = next;
+        self.surface.configure(&self.device, &self.config);
+    }
+
     /// Handles the [`WindowEvent`]s user inputs and updates the [`View`] and
-    /// [`Uniforms`] data. Only expects [`KeyboardInput`], [`CursorMoved`],
-    /// [`MouseWheel`], [`MouseInput`] and [`ModifiersChanged`] events.
+    /// [`Uniforms`] data. Handles [`KeyboardInput`], [`CursorMoved`],
+    /// [`CursorLeft`], [`MouseWheel`], [`MouseInput`], [`Touch`],
+    /// [`PinchGesture`] and [`ModifiersChanged`] events and ignores the rest.
+    /// While the coordinate prompt is open, it gets all keyboard input.
     ///
     /// [`KeyboardInput`]: WindowEvent::KeyboardInput
     /// [`CursorMoved`]: WindowEvent::CursorMoved
+    /// [`CursorLeft`]: WindowEvent::CursorLeft
     /// [`MouseWheel`]: WindowEvent::MouseWheel
     /// [`MouseInput`]: WindowEvent::MouseInput
+    /// [`Touch`]: WindowEvent::Touch
+    /// [`PinchGesture`]: WindowEvent::PinchGesture
     /// [`ModifiersChanged`]: WindowEvent::ModifiersChanged
     #[allow(clippy::needless_pass_by_value, reason = "clippy false positive")]
     fn input(&mut self, event: WindowEvent) {
+        if self.view.prompt.is_some()
+            && let WindowEvent::KeyboardInput { event, .. } = &event
+        {
+            self.prompt_input(event);
+            return;
+        }
+        if matches!(
+            event,
+            WindowEvent::MouseWheel { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::Touch(_)
+                | WindowEvent::PinchGesture { .. }
+        ) {
+            self.stop_demo();
+        }
         match event {
             WindowEvent::KeyboardInput {
                 device_id: _,
@@ -276,51 +1204,21 @@ impl AppState {
                     },
                 is_synthetic: false,
             } => {
-                let step = (0.005 * self.compute_zoom()).max(f64::EPSILON);
-                let sign = if state == ElementState::Pressed {
-                    1.0
+                let action = if state.is_pressed() {
+                    let action = self.settings.keys.action(
+                        key,
+                        self.view.ctrl_pressed,
+                        self.view.shift_pressed,
+                    );
+                    if let Some(action) = action.filter(|a| a.is_held()) {
+                        self.view.held_keys.insert(key, action);
+                    }
+                    action
                 } else {
-                    -1.0
+                    self.view.held_keys.remove(&key)
                 };
-                // Bool cannot be used in a `Uniforms` field :(
-                let toggle_f32 = |prop: &mut f32| *prop = (*prop - 1.0).abs();
-                let (dx, dy) = &mut self.view.movement_delta;
-                match (key, state) {
-                    // Update delta by a fraction depending on current zoom.
-                    (KeyCode::KeyA, _) => *dx -= sign * step,
-                    (KeyCode::KeyD, _) => *dx += sign * step,
-                    (KeyCode::KeyW, _) => *dy += sign * step,
-                    (KeyCode::KeyS, _) => *dy -= sign * step,
-                    (KeyCode::Space, ElementState::Pressed) => {
-                        toggle_f32(&mut self.uniforms_mut().is_mandelbrot);
-                    }
-                    (KeyCode::KeyQ, ElementState::Pressed) => {
-                        toggle_f32(&mut self.uniforms_mut().rotate_colors);
-                    }
-                    (KeyCode::Comma, ElementState::Pressed)
-                        if self.uniforms().max_iter > 100 =>
-                    {
-                        self.uniforms_mut().max_iter -= 100;
-                        self.update();
-                    }
-                    (KeyCode::Period, ElementState::Pressed)
-                        if self.uniforms().max_iter < u32::MAX / 10 =>
-                    {
-                        self.uniforms_mut().max_iter += 100;
-                        self.update();
-                    }
-                    (KeyCode::KeyR, ElementState::Pressed) => {
-                        self.view.uniforms = Uniforms::default();
-                    }
-                    (KeyCode::F11, ElementState::Pressed) => {
-                        self.view.fullscreen = !self.view.fullscreen;
-                        self.window.set_fullscreen(
-                            self.view.fullscreen.then_some(
-                                winit::window::Fullscreen::Borderless(None),
-                            ),
-                        );
-                    }
-                    _ => {}
+                if let Some(action) = action {
+                    self.perform(action, state);
                 }
             }
             WindowEvent::KeyboardInput { .. } => {
@@ -330,42 +1228,431 @@ impl AppState {
                 device_id: _,
                 position,
             } => {
-                let (x0, y0) = self.mouse_coords();
-                self.move_mouse(position);
-                let (x1, y1) = self.mouse_coords();
-                let delta = (x0 - x1, y0 - y1);
-
-                if self.view.mouse_clicked {
-                    self.translate(delta);
+                if (self.view.mouse_clicked || self.view.panning)
+                    && let Some(last) = self.view.last_cursor
+                {
+                    let (dx, dy) = (position.x - last.x, position.y - last.y);
+                    self.drag(dx, dy);
+                    self.track_drag(dx, dy);
                 }
+                self.view.last_cursor = Some(position);
+                self.move_mouse(position);
             }
-            WindowEvent::MouseWheel {
-                delta: MouseScrollDelta::LineDelta(_, y),
-                ..
-            } => {
+            WindowEvent::CursorLeft { device_id: _ } => {
+                // The cursor may re-enter anywhere, don't drag to there.
+                self.view.last_cursor = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => f64::from(y),
+                    MouseScrollDelta::PixelDelta(position) => {
+                        position.y / PIXELS_PER_LINE
+                    }
+                };
+                self.save_history();
+                let delta = -lines * self.settings.zoom_speed;
                 if self.view.ctrl_pressed {
-                    self.zoom((-y).into());
+                    self.zoom(delta);
                 } else {
-                    self.mouse_zoom((-y).into());
+                    self.mouse_zoom(delta);
                 }
             }
             WindowEvent::MouseInput {
                 device_id: _,
                 state,
                 button: MouseButton::Left,
-            } => self.view.mouse_clicked = state.is_pressed(),
+            } => {
+                self.view.mouse_clicked = state.is_pressed();
+                if state.is_pressed() {
+                    self.view.inertia.stop();
+                    self.save_history();
+                    let now = Instant::now();
+                    if self.view.last_click.is_some_and(|last| {
+                        now.duration_since(last) < DOUBLE_CLICK_TIME
+                    }) {
+                        self.animate_zoom_to_mouse();
+                        self.view.last_click = None;
+                    } else {
+                        self.view.last_click = Some(now);
+                    }
+                } else if !self.view.panning {
+                    self.release_drag();
+                }
+            }
+            WindowEvent::MouseInput {
+                device_id: _,
+                state,
+                button: MouseButton::Middle,
+            } => {
+                self.view.panning = state.is_pressed();
+                if state.is_pressed() {
+                    self.view.inertia.stop();
+                    self.save_history();
+                } else if !self.view.mouse_clicked {
+                    self.release_drag();
+                }
+            }
+            WindowEvent::MouseInput {
+                device_id: _,
+                state: ElementState::Pressed,
+                button: MouseButton::Back,
+            } => self.undo(),
+            WindowEvent::MouseInput {
+                device_id: _,
+                state: ElementState::Pressed,
+                button: MouseButton::Forward,
+            } => self.redo(),
+            WindowEvent::MouseInput { .. } => {
+                // ignore all other buttons
+            }
+            WindowEvent::Touch(touch) => self.touch_input(touch),
+            WindowEvent::PinchGesture { delta, .. } => {
+                self.pinch_gesture(delta);
+            }
             WindowEvent::ModifiersChanged(modifiers) => {
                 self.view.ctrl_pressed = modifiers.state().control_key();
+                self.view.shift_pressed = modifiers.state().shift_key();
+            }
+            _ => {
+                // ignore the events that aren't user input
             }
-            _ => unreachable!("unexpected event"),
         }
     }
 
-    /// Translates the center of the coordinate system by the given delta.
+    /// Performs the [`Action`] of a key that was pressed, or released for the
+    /// actions that last while it's held.
+    fn perform(&mut self, action: Action, state: ElementState) {
+        let sign = if state.is_pressed() { 1.0 } else { -1.0 };
+        // Bool cannot be used in a `Uniforms` field :(
+        let toggle_f32 = |prop: &mut f32| *prop = (*prop - 1.0).abs();
+        if action.is_held() {
+            if state.is_pressed() {
+                self.save_history();
+            }
+        } else if !state.is_pressed() {
+            return;
+        }
+        if state.is_pressed() && action != Action::Demo {
+            self.stop_demo();
+            self.view.inertia.stop();
+        }
+        let (dx, dy) = &mut self.view.movement_delta;
+        match action {
+            Action::MoveLeft => *dx -= sign,
+            Action::MoveRight => *dx += sign,
+            Action::MoveUp => *dy += sign,
+            Action::MoveDown => *dy -= sign,
+            Action::ZoomIn => self.view.zoom_delta -= sign,
+            Action::ZoomOut => self.view.zoom_delta += sign,
+            Action::RotateLeft => self.rotate(ROTATION_STEP),
+            Action::RotateRight => self.rotate(-ROTATION_STEP),
+            Action::ToggleJulia => self.toggle_julia(),
+            Action::ToggleJuliaInset => {
+                self.view.show_julia_inset = !self.view.show_julia_inset;
+            }
+            Action::LockJulia => {
+                self.view.julia_locked = !self.view.julia_locked;
+            }
+            Action::RotateColors => {
+                toggle_f32(&mut self.uniforms_mut().rotate_colors);
+            }
+            Action::ShiftColorsBack => self.shift_colors(-COLOR_OFFSET_STEP),
+            Action::ShiftColorsForward => self.shift_colors(COLOR_OFFSET_STEP),
+            Action::Pause => self.toggle_pause(),
+            Action::NextPalette => {
+                let count = self.palette_count();
+                let palette = &mut self.uniforms_mut().palette;
+                *palette = (*palette + 1) % count;
+            }
+            Action::DistanceEstimation => {
+                toggle_f32(&mut self.uniforms_mut().distance_estimation);
+            }
+            Action::NextColoring => {
+                let mode = &mut self.uniforms_mut().coloring_mode;
+                *mode = (*mode + 1) % COLORING_NAMES.len() as u32;
+            }
+            Action::NextColorScale => {
+                let scale = &mut self.uniforms_mut().color_scale_mode;
+                *scale = (*scale + 1) % COLOR_SCALE_NAMES.len() as u32;
+            }
+            Action::NextTrapShape => {
+                let shape = &mut self.uniforms_mut().trap_shape;
+                *shape = (*shape + 1) % TRAP_NAMES.len() as u32;
+            }
+            Action::InteriorColoring => {
+                toggle_f32(&mut self.uniforms_mut().interior_coloring);
+            }
+            Action::NextInteriorColor => self.cycle_interior_color(),
+            Action::NextFractal => {
+                let fractal = &mut self.uniforms_mut().fractal_type;
+                *fractal = (*fractal + 1) % FRACTAL_NAMES.len() as u32;
+            }
+            Action::DecreasePower => self.change_power(-POWER_STEP),
+            Action::IncreasePower => self.change_power(POWER_STEP),
+            Action::Reset => self.reset(true),
+            Action::ResetInstantly => self.reset(false),
+            Action::RebaseView => self.rebase_view(),
+            Action::DecreaseMaxIter => self.change_max_iter(false),
+            Action::IncreaseMaxIter => self.change_max_iter(true),
+            Action::AutoMaxIter => self.toggle_auto_max_iter(),
+            Action::DoubleEscapeRadius => self.scale_escape_radius(2.0),
+            Action::HalveEscapeRadius => self.scale_escape_radius(0.5),
+            Action::NarrowerBands => {
+                self.scale_band_count(BAND_COUNT_FACTOR.recip());
+            }
+            Action::WiderBands => self.scale_band_count(BAND_COUNT_FACTOR),
+            Action::FewerStripes => self.change_stripe_density(-1.0),
+            Action::MoreStripes => self.change_stripe_density(1.0),
+            Action::Screenshot => self.save_screenshot(),
+            Action::Export => self.save_export(),
+            Action::Record => self.toggle_recording(),
+            Action::RecordColorCycle => self.toggle_color_cycle_recording(),
+            Action::SaveBookmark => {
+                match bookmarks::save_bookmark(self.uniforms()) {
+                    Ok(()) => eprintln!("Saved bookmark"),
+                    Err(e) => eprintln!("Failed to save bookmark: {e}"),
+                }
+            }
+            Action::NextBookmark => self.next_bookmark(),
+            Action::CopyView => self.copy_view(),
+            Action::CopyShareString => self.copy_share_string(),
+            Action::PasteView => self.paste_view(),
+            Action::GoTo => self.open_prompt(),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::NextPresentMode => self.cycle_present_mode(),
+            Action::Msaa => self.cycle_msaa(),
+            Action::Accumulation => self.toggle_accumulation(),
+            Action::NextRenderScale => self.cycle_render_scale(),
+            Action::Dithering => {
+                toggle_f32(&mut self.uniforms_mut().dithering);
+            }
+            Action::Progressive => self.toggle_progressive(),
+            Action::Histogram => self.toggle_histogram(),
+            Action::Minimap => self.toggle_minimap(),
+            Action::Crosshair => self.toggle_crosshair(),
+            Action::InsideStats => self.toggle_inside_stats(),
+            Action::LockAspect => self.toggle_aspect_lock(),
+            Action::PrecisionComparison => {
+                self.toggle_precision_comparison();
+            }
+            Action::Demo => self.toggle_demo(),
+            Action::Overlay => {
+                self.view.show_overlay = !self.view.show_overlay;
+            }
+            Action::TitleInfo => {
+                self.view.show_title_info = !self.view.show_title_info;
+                self.update_title();
+            }
+            Action::Fullscreen => self.toggle_fullscreen(),
+            Action::NextFullscreenMode => self.cycle_fullscreen_mode(),
+        }
+    }
+
+    /// Resets the view and the settings to the defaults. If `animate`, the
+    /// view zooms and pans back to the default one over
+    /// [`RESET_ANIM_DURATION`] instead of snapping to it.
+    fn reset(&mut self, animate: bool) {
+        self.save_history();
+        let from_offset = (self.uniforms().offset, self.uniforms().offset_lo);
+        let from_zooms = self.uniforms().zooms;
+        self.view.uniforms = self.settings.default_uniforms();
+        self.view.julia_locked = false;
+        self.view.hidden_view = ([0.0, 0.0], JULIA_ZOOMS);
+        let ([x, y], [lx, ly]) = from_offset;
+        let [to_x, to_y] = self.uniforms().offset;
+        self.view.animation = animate.then(|| ZoomAnim {
+            start: Instant::now(),
+            duration: RESET_ANIM_DURATION,
+            from_offset,
+            from_zooms,
+            pan: [to_x - x - lx, to_y - y - ly],
+            to_zooms: self.uniforms().zooms,
+        });
+        if animate {
+            let uniforms = self.uniforms_mut();
+            (uniforms.offset, uniforms.offset_lo) = from_offset;
+            uniforms.zooms = from_zooms;
+        }
+    }
+
+    /// Rotates the view counterclockwise by the `angle` in radians, keeping
+    /// [`Uniforms::rotation`] within [0, 2π).
+    fn rotate(&mut self, angle: f32) {
+        self.save_history();
+        let rotation = &mut self.uniforms_mut().rotation;
+        *rotation = (*rotation + angle).rem_euclid(TAU);
+    }
+
+    /// Shifts the palettes by the phase `delta` in radians, keeping
+    /// [`Uniforms::color_offset`] within [0, 2π).
+    fn shift_colors(&mut self, delta: f32) {
+        let offset = &mut self.uniforms_mut().color_offset;
+        *offset = (*offset + delta).rem_euclid(TAU);
+    }
+
+    /// Freezes or resumes [`Uniforms::time`]. It resumes from where it was
+    /// frozen, so that the colors don't jump.
+    fn toggle_pause(&mut self) {
+        self.view.paused = !self.view.paused;
+        if !self.view.paused {
+            let time = Duration::from_secs_f64(self.uniforms().time);
+            self.view.time = Instant::now() - time;
+        }
+    }
+
+    /// Switches between the Mandelbrot and Julia sets, remembering the view of
+    /// each of them. The sets morph into each other over [`MORPH_DURATION`].
+    fn toggle_julia(&mut self) {
+        self.save_history();
+        let (offset, zooms) = self.view.hidden_view;
+        self.view.hidden_view = (self.uniforms().offset, self.uniforms().zooms);
+        let from_offset = (self.uniforms().offset, self.uniforms().offset_lo);
+        let ([x, y], [lx, ly]) = from_offset;
+        let start = Instant::now();
+        self.view.animation = Some(ZoomAnim {
+            start,
+            duration: MORPH_DURATION,
+            from_offset,
+            from_zooms: self.uniforms().zooms,
+            pan: [offset[0] - x - lx, offset[1] - y - ly],
+            to_zooms: zooms,
+        });
+        self.view.morph = Some(Morph {
+            start,
+            to: (self.uniforms().is_mandelbrot - 1.0).abs(),
+            view: (offset, zooms),
+        });
+    }
+
+    /// Advances the morph between the sets, if one is running.
+    fn advance_morph(&mut self) {
+        let Some(morph) = self.view.morph else {
+            return;
+        };
+        self.uniforms_mut().is_mandelbrot = morph.current();
+        if morph.is_finished() {
+            self.view.morph = None;
+        }
+    }
+
+    /// Skips to the end of the running morph between the sets, if any, so
+    /// that the view is entirely in one of them.
+    fn finish_morph(&mut self) {
+        let Some(morph) = self.view.morph.take() else {
+            return;
+        };
+        self.view.animation = None;
+        let uniforms = self.uniforms_mut();
+        (uniforms.offset, uniforms.zooms) = morph.view;
+        uniforms.offset_lo = [0.0, 0.0];
+        uniforms.is_mandelbrot = morph.to;
+    }
+
+    /// Switches the [`Uniforms::interior_color`] to the next of the
+    /// [`INTERIOR_COLORS`], or to the first one if it isn't a preset.
+    fn cycle_interior_color(&mut self) {
+        let color = &mut self.uniforms_mut().interior_color;
+        let next = INTERIOR_COLORS
+            .iter()
+            .position(|(_, c)| c == color)
+            .map_or(0, |i| (i + 1) % INTERIOR_COLORS.len());
+        *color = INTERIOR_COLORS[next].1;
+    }
+
+    /// Restores the next saved bookmark, wrapping around after the last one.
+    fn next_bookmark(&mut self) {
+        let bookmarks = bookmarks::load_bookmarks();
+        if bookmarks.is_empty() {
+            eprintln!("No bookmarks saved yet");
+            return;
+        }
+        self.save_history();
+        let index = self.view.bookmark_index % bookmarks.len();
+        bookmarks[index].apply(self.uniforms_mut());
+        self.view.bookmark_index = index + 1;
+        self.view.redraw = true;
+    }
+
+    /// Changes the Multibrot [`Uniforms::power`], which also sets the degree of
+    /// the Newton polynomial, by the given delta, keeping it within
+    /// [`POWER_RANGE`].
+    fn change_power(&mut self, delta: f32) {
+        let power = &mut self.uniforms_mut().power;
+        *power =
+            (*power + delta).clamp(*POWER_RANGE.start(), *POWER_RANGE.end());
+    }
+
+    /// Increases or decreases the [`Uniforms::max_iter`] by 100, by 10 with
+    /// shift or by 1000 with control held, keeping it within
+    /// [`MAX_ITER_RANGE`].
+    fn change_max_iter(&mut self, increase: bool) {
+        let step = if self.view.shift_pressed {
+            10
+        } else if self.view.ctrl_pressed {
+            1000
+        } else {
+            100
+        };
+        let max_iter = self.uniforms().max_iter;
+        let max_iter = if increase {
+            max_iter.saturating_add(step)
+        } else {
+            max_iter.saturating_sub(step)
+        };
+        self.uniforms_mut().max_iter = clamp_max_iter(max_iter);
+        self.view.redraw = true;
+    }
+
+    /// Multiplies the [`Uniforms::escape_radius`] by the given `factor`,
+    /// keeping it within [`ESCAPE_RADIUS_RANGE`].
+    fn scale_escape_radius(&mut self, factor: f32) {
+        let radius = &mut self.uniforms_mut().escape_radius;
+        *radius = (*radius * factor)
+            .clamp(*ESCAPE_RADIUS_RANGE.start(), *ESCAPE_RADIUS_RANGE.end());
+    }
+
+    /// Multiplies the [`Uniforms::band_count`] by the given `factor`, keeping
+    /// it within [`BAND_COUNT_RANGE`].
+    fn scale_band_count(&mut self, factor: f32) {
+        let count = &mut self.uniforms_mut().band_count;
+        *count = (*count * factor)
+            .clamp(*BAND_COUNT_RANGE.start(), *BAND_COUNT_RANGE.end());
+    }
+
+    /// Changes the [`Uniforms::stripe_density`] by `delta`, keeping it within
+    /// [`STRIPE_DENSITY_RANGE`].
+    fn change_stripe_density(&mut self, delta: f32) {
+        let density = &mut self.uniforms_mut().stripe_density;
+        *density = (*density + delta)
+            .clamp(*STRIPE_DENSITY_RANGE.start(), *STRIPE_DENSITY_RANGE.end());
+    }
+
+    /// Pans in the given `direction` on the screen, each axis in the range
+    /// [-1, 1], for `dt` seconds at [`Config::move_speed`]. The viewport width
+    /// spans `2 * zoom` in the complex plane, so the view moves by the same
+    /// fraction of the screen per second at any zoom depth and frame rate.
+    fn pan(&mut self, direction: (f64, f64), dt: f64) {
+        let speed = self.settings.move_speed;
+        let [x, y] = pan_delta(self.uniforms(), direction, speed * dt);
+        self.translate((x, y));
+    }
+
+    /// Pans so that the point under the cursor follows it when it's dragged by
+    /// the given number of pixels.
+    fn drag(&mut self, dx: f64, dy: f64) {
+        // The viewport width spans `2 * zoom` in the complex plane.
+        let [_, _, width, _] = self.viewport();
+        let scale = 2.0 * self.compute_zoom() / f64::from(width);
+        let [x, y] = self.uniforms().rotated([-dx * scale, dy * scale]);
+        self.translate((x, y));
+    }
+
+    /// Translates the center of the coordinate system by the given delta, see
+    /// [`Uniforms::translate`].
     fn translate(&mut self, delta: (f64, f64)) {
-        let (x, y) = delta;
-        self.uniforms_mut().offset[0] += x;
-        self.uniforms_mut().offset[1] += y;
+        self.uniforms_mut().translate(delta.into());
+        self.recover_view();
     }
 
     /// Zooms in or out by the given delta. Recalculates the zoom factor and
@@ -374,48 +1661,159 @@ impl AppState {
         self.uniforms_mut().zooms += delta;
         // The bounds are chosen so that we don't zoom in too much and distort
         // the view because of floating point errors.
-        self.uniforms_mut().zooms = self.uniforms().zooms.clamp(-314.0, 42.0);
+        let min_zooms = self.renderer.precision().min_zooms();
+        self.uniforms_mut().zooms =
+            self.uniforms().zooms.clamp(min_zooms, MAX_ZOOMS);
+        self.recover_view();
+    }
 
-        // Without epsilon, we wouln't be able to move on extreme zoom-ins.
-        let step = (0.005 * self.compute_zoom()).max(f64::EPSILON);
-        let (dx, dy) = &mut self.view.movement_delta;
-        if *dx != 0.0 {
-            *dx = dx.signum() * step;
+    /// Restores the last valid view if the center or the zoom isn't finite
+    /// anymore, which would turn the whole view black with no way back.
+    /// Otherwise, remembers the view as the last valid one.
+    fn recover_view(&mut self) {
+        let Uniforms {
+            offset,
+            offset_lo,
+            zooms,
+            ..
+        } = *self.uniforms();
+        if offset
+            .iter()
+            .chain(&offset_lo)
+            .chain([&zooms])
+            .all(|x| x.is_finite())
+        {
+            self.view.last_valid_view = (offset, offset_lo, zooms);
+            return;
         }
-        if *dy != 0.0 {
-            *dy = dy.signum() * step;
+        eprintln!(
+            "Warning: the view became invalid ({offset:?} + {offset_lo:?}, \
+             zooms = {zooms}), restoring the last valid one"
+        );
+        // Animations would keep producing invalid views.
+        self.view.animation = None;
+        self.view.inertia.stop();
+        self.stop_demo();
+        let (offset, offset_lo, zooms) = self.view.last_valid_view;
+        let uniforms = self.uniforms_mut();
+        uniforms.offset = offset;
+        uniforms.offset_lo = offset_lo;
+        uniforms.zooms = zooms;
+    }
+
+    /// Rebases the view on its center, see [`Uniforms::rebase`]. The low part
+    /// of the center is only kept where the double-double pipeline renders
+    /// it. The view stops moving, so that it stays on the rebased center.
+    fn rebase_view(&mut self) {
+        self.save_history();
+        self.view.animation = None;
+        self.view.inertia.stop();
+        let deep = self.renderer.is_deep();
+        self.uniforms_mut().rebase(deep);
+        self.recover_view();
+        let [re, im] = self.uniforms().offset;
+        let sign = if im >= 0.0 { "+" } else { "" };
+        eprintln!("Rebased the view at {re}{sign}{im}i");
+    }
+
+    /// Starts a smooth zoom-in animation centering the point under the mouse.
+    fn animate_zoom_to_mouse(&mut self) {
+        // Relative to the offset, so that it keeps the precision of deep zooms.
+        let (x, y) = self.mouse_coords_in([0.0, 0.0], self.uniforms().zooms);
+        let to_zooms = (self.uniforms().zooms - ZOOM_ANIM_ZOOMS)
+            .max(self.renderer.precision().min_zooms());
+        self.view.animation = Some(ZoomAnim {
+            start: Instant::now(),
+            duration: ZOOM_ANIM_DURATION,
+            from_offset: (self.uniforms().offset, self.uniforms().offset_lo),
+            from_zooms: self.uniforms().zooms,
+            pan: [x, y],
+            to_zooms,
+        });
+    }
+
+    /// Advances the running [`ZoomAnim`], if any.
+    fn animate(&mut self) {
+        let Some(animation) = self.view.animation else {
+            return;
+        };
+        let ([x, y], zooms) = animation.current();
+        let ([fx, fy], [flx, fly]) = animation.from_offset;
+        let [ox, oy] = self.uniforms().offset;
+        let [lx, ly] = self.uniforms().offset_lo;
+        self.zoom(zooms - self.uniforms().zooms);
+        // The high and low parts are subtracted separately, so that the pan
+        // keeps the precision of deep zooms.
+        self.translate((
+            (fx - ox) + (flx - lx) + x,
+            (fy - oy) + (fly - ly) + y,
+        ));
+        if animation.is_finished() {
+            self.view.animation = None;
         }
     }
 
-    /// Zooms in on mouse position.
+    /// Zooms in on mouse position, keeping the point under the cursor where it
+    /// is.
     fn mouse_zoom(&mut self, delta: f64) {
-        let (x, y) = self.mouse_coords();
+        let from_zooms = self.uniforms().zooms;
         self.zoom(delta);
-        let (new_x, new_y) = self.mouse_coords();
-        self.translate((x - new_x, y - new_y));
+        keep_point_under_cursor(self.uniforms_mut(), from_zooms);
+        self.recover_view();
     }
 
     /// Updates the [`Uniforms::mouse_position`] to the mouse position,
-    /// normalized to the range [-1, 1] in the window space.
+    /// normalized so that the width of the [`AppState::viewport`] spans
+    /// [-1, 1] and the height keeps the aspect ratio, with `y` pointing down.
+    ///
+    /// This is the same mapping the shader applies to the pixel coordinates,
+    /// so that the point under the cursor matches the rendered one in windows
+    /// of any aspect ratio. Both the `position` and the surface size are in
+    /// physical pixels, so the mapping doesn't depend on the scale factor.
     fn move_mouse(&mut self, position: PhysicalPosition<f64>) {
-        let (x, y): (f64, f64) = position.into();
-        let (w, h): (f64, f64) = self.window.inner_size().into();
-        let aspect = w / h;
-        let nx = (x / w).mul_add(2.0, -1.0);
-        let ny = (y / h).mul_add(2.0, -1.0) / aspect;
-        self.uniforms_mut().mouse_position = [nx, ny];
+        let viewport = self.viewport().map(f64::from);
+        self.uniforms_mut().mouse_position =
+            normalize_position(position.into(), viewport);
     }
 
-    /// Returns the current mouse coordinates in the complex plane.
+    /// Returns the current mouse coordinates in the complex plane, rounded
+    /// once from the double-double [`Uniforms::offset`] and
+    /// [`Uniforms::offset_lo`].
     #[must_use]
     fn mouse_coords(&self) -> (f64, f64) {
-        let (mx, my) = self.uniforms().mouse_position.into();
-        let (ox, oy) = self.uniforms().offset.into();
-        let zoom = self.compute_zoom();
-        (mx.mul_add(zoom, ox), (-my).mul_add(zoom, oy))
+        let (x, y) = self.mouse_coords_in([0.0, 0.0], self.uniforms().zooms);
+        let [ox, oy] = self.uniforms().offset;
+        let [lx, ly] = self.uniforms().offset_lo;
+        // The small parts are added first, so that the low part isn't lost
+        // when the cursor is within a few `f64` steps of the center.
+        (ox + (x + lx), oy + (y + ly))
     }
 
-    /// Computes the exponential zoom factor.
+    /// Returns the mouse coordinates in the complex plane of the Mandelbrot
+    /// set, even if the Julia set is currently displayed.
+    #[must_use]
+    fn mandelbrot_mouse_coords(&self) -> (f64, f64) {
+        if self.uniforms().is_mandelbrot == 1.0 {
+            self.mouse_coords()
+        } else {
+            let (offset, zooms) = self.view.hidden_view;
+            self.mouse_coords_in(offset, zooms)
+        }
+    }
+
+    /// Returns the mouse coordinates in the complex plane viewed with the
+    /// given `offset` and `zooms`.
+    #[must_use]
+    fn mouse_coords_in(&self, offset: [f64; 2], zooms: f64) -> (f64, f64) {
+        let [mx, my] = self.uniforms().mouse_position;
+        let [x, y] = self.uniforms().rotated([mx, -my]);
+        let (ox, oy) = offset.into();
+        let zoom = (zooms / 10.0).exp();
+        (x.mul_add(zoom, ox), y.mul_add(zoom, oy))
+    }
+
+    /// Computes the half-width of the view in the complex plane,
+    /// `exp(zooms / 10)`, the reciprocal of [`Uniforms::magnification`].
     #[must_use]
     fn compute_zoom(&self) -> f64 {
         (self.uniforms().zooms / 10.0).exp()
@@ -434,34 +1832,195 @@ impl AppState {
     }
 
     /// Updates the [`Uniforms`] and writes them to the GPU. Also updates the
-    /// window title to show the current zoom, center and mouse position.
+    /// window title, at most every [`TITLE_UPDATE_INTERVAL`]. Called before
+    /// each frame is rendered, changes to the view in between request one with
+    /// [`View::redraw`] instead of calling it.
     fn update(&mut self) {
-        let window_size = self.window.inner_size();
+        self.view.redraw = false;
+        let now = Instant::now();
+        // The time the window was idle for is no frame, and nothing moved.
+        let frame_time = if mem::take(&mut self.view.idle) {
+            Duration::ZERO
+        } else {
+            if self.view.frame_times.len() == FRAME_TIMES_WINDOW {
+                self.view.frame_times.pop_front();
+            }
+            let frame_time = now - self.view.last_frame;
+            self.view.frame_times.push_back(frame_time);
+            frame_time
+        };
+        self.view.last_frame = now;
+        // Don't jump after a stall, e.g. while the window is being dragged.
+        let dt = frame_time.min(MAX_FRAME_TIME).as_secs_f64();
+        if self.view.show_overlay || self.view.prompt.is_some() {
+            let text = self.overlay_text();
+            self.overlay.set_text(&self.device, &self.queue, &text);
+        }
 
-        self.uniforms_mut().time = self.view.time.elapsed().as_secs_f64();
-        self.uniforms_mut().resolution = window_size.into();
-        self.translate(self.view.movement_delta);
+        if !self.view.paused {
+            self.uniforms_mut().time = self.view.time.elapsed().as_secs_f64();
+        }
+        let (width, height) = self.render_size();
+        self.uniforms_mut().resolution = [width.into(), height.into()];
+        self.uniforms_mut().output_scale = self.output_scale;
+        self.uniforms_mut().encode_srgb = f32::from(u8::from(self.encode_srgb));
+        self.pan(self.view.movement_delta, dt);
+        self.advance_glide(dt);
+        let zoom_speed = KEY_ZOOM_SPEED * self.settings.zoom_speed;
+        self.zoom(self.view.zoom_delta * zoom_speed * dt);
+        self.apply_gamepad(dt);
+        self.animate();
+        self.advance_morph();
+        self.advance_demo(dt);
+        // The view can also be changed without panning or zooming, e.g. by
+        // pasting or undoing.
+        self.recover_view();
+        if !self.view.julia_locked {
+            self.uniforms_mut().julia_c = self.mandelbrot_mouse_coords().into();
+        }
+        self.renderer.set_uniforms(&self.view.uniforms);
+        self.update_inside_stats();
+        self.update_auto_max_iter();
+        self.update_precision_comparison();
+        if self.show_julia_inset() {
+            self.write_uniforms(
+                &self.inset_buffer,
+                &self.julia_inset_uniforms(),
+            );
+        }
+        if self.view.show_minimap {
+            self.update_minimap();
+        }
 
-        self.queue.write_buffer(
-            &self.buffer,
-            0,
-            bytemuck::cast_slice(&[*self.uniforms()]),
-        );
+        if self.view.title.1.is_none_or(|last| {
+            now.duration_since(last) >= TITLE_UPDATE_INTERVAL
+        }) {
+            self.view.title.1 = Some(now);
+            self.view.title_outdated = false;
+            self.update_title();
+        } else {
+            self.view.title_outdated = true;
+        }
+    }
 
+    /// Updates the window title to show the current zoom, center, mouse
+    /// position and the iteration count under the mouse, if any of them
+    /// changed, or just the name of the window while they're hidden.
+    fn update_title(&mut self) {
+        let inputs = self.title_inputs();
+        if self.view.title_inputs == Some(inputs) {
+            return;
+        }
+        self.view.title_inputs = Some(inputs);
+        let title = if self.view.show_title_info {
+            self.info_title()
+        } else {
+            self.custom_title
+                .clone()
+                .unwrap_or_else(|| "Mandelbrot".to_owned())
+        };
+        if title != self.view.title.0 {
+            self.window.set_title(&title);
+            self.view.title.0 = title;
+        }
+    }
+
+    /// Returns everything the window title shows.
+    fn title_inputs(&self) -> TitleInputs {
+        TitleInputs {
+            uniforms: Uniforms {
+                time: 0.0,
+                tile_offset: [0.0; 2],
+                iter_start: 0,
+                iter_end: 0,
+                jitter: [0.0; 2],
+                accumulated_samples: 0,
+                ..*self.uniforms()
+            },
+            show_info: self.view.show_title_info,
+            auto_max_iter: self.auto_max_iter.is_some(),
+            present_mode: self.config.present_mode,
+            sample_count: Some(self.renderer.sample_count())
+                .filter(|&count| count > 1),
+            viewport_width: self.viewport()[2],
+            inside_percentage: self.inside_percentage(),
+            julia_locked: self.view.julia_locked,
+        }
+    }
+
+    /// Returns the window title with the state of the view.
+    fn info_title(&self) -> String {
         let max_iter = self.uniforms().max_iter;
         let [center_x, center_y] = self.uniforms().offset;
         let (mouse_x, mouse_y) = self.mouse_coords();
-        let prec = 20;
+        let pixel_size =
+            2.0 * self.compute_zoom() / f64::from(self.viewport()[2].max(1));
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "clamped to a few decimals"
+        )]
+        let prec = (TITLE_EXTRA_DECIMALS - pixel_size.log10().floor())
+            .clamp(1.0, TITLE_MAX_DECIMALS) as usize;
         let format = |x: f64, i: bool| {
             format!("{x:.prec$}{i}", i = if i { "i" } else { "" })
         };
-        self.window.set_title(&format!(
-            "Mandelbrot \
+        format!(
+            "{custom}{fractal} \
              | Zoom = x{zoom:prec$} \
-             | Max Iter = {max_iter} \
+             | Rotation = {rotation:.0}° \
+             | Max Iter = {max_iter}{auto} \
+             | Palette = {palette} \
+             | Coloring = {coloring} \
+             | Color Scale = {color_scale} \
+             | Color Offset = {color_offset:.0}° \
+             | Present Mode = {present_mode:?} \
+             | MSAA = {msaa} \
              | Center = {re1:>prec$}{sign1}{im1:<prec$} \
-             | Mouse = {re2:>prec$}{sign2}{im2:<prec$}",
-            zoom = format(self.compute_zoom().recip(), false)
+             | Mouse = {re2:>prec$}{sign2}{im2:<prec$} \
+             | Mouse Iter = {mouse_iter}\
+             {inside}\
+             {julia}",
+            custom = self
+                .custom_title
+                .as_ref()
+                .map_or_else(String::new, |title| format!("{title} | ")),
+            fractal = match self.uniforms().fractal_type {
+                MULTIBROT =>
+                    format!("Multibrot (d = {})", self.uniforms().power),
+                NEWTON => format!(
+                    "Newton (z^{} - 1)",
+                    orbit::newton_degree(self.uniforms().power)
+                ),
+                fractal => FRACTAL_NAMES[fractal as usize].to_owned(),
+            },
+            auto = if self.auto_max_iter.is_some() {
+                " (auto)"
+            } else {
+                ""
+            },
+            palette = self.palette_name(),
+            coloring = match self.uniforms().coloring_mode {
+                ORBIT_TRAP => format!(
+                    "Orbit Trap ({})",
+                    TRAP_NAMES[self.uniforms().trap_shape as usize]
+                ),
+                STRIPE_AVERAGE => format!(
+                    "Stripe Average ({})",
+                    self.uniforms().stripe_density
+                ),
+                mode => COLORING_NAMES[mode as usize].to_owned(),
+            },
+            color_scale =
+                COLOR_SCALE_NAMES[self.uniforms().color_scale_mode as usize],
+            present_mode = self.config.present_mode,
+            msaa = match self.renderer.sample_count() {
+                1 => "Off".to_owned(),
+                count => format!("{count}x"),
+            },
+            rotation = self.uniforms().rotation.to_degrees(),
+            color_offset = self.uniforms().color_offset.to_degrees(),
+            zoom = format(self.uniforms().magnification(), false)
                 .trim_end_matches('0'),
             re1 = format(center_x, false).trim_end_matches('0'),
             im1 = format(center_y, true).trim_end_matches('0'),
@@ -469,8 +2028,79 @@ impl AppState {
             re2 = format(mouse_x, false).trim_end_matches('0'),
             im2 = format(mouse_y, true).trim_end_matches('0'),
             sign2 = if mouse_y >= 0.0 { "+" } else { "" },
+            mouse_iter =
+                orbit::escape_iterations(self.uniforms(), (mouse_x, mouse_y)),
+            inside = self.inside_percentage().map_or_else(String::new, |p| {
+                format!(" | Inside = {p:.2}%")
+            }),
+            julia = if self.view.julia_locked {
+                let [re, im] = self.uniforms().julia_c;
+                format!(
+                    " | Julia C = {re}{sign}{im}i",
+                    sign = if im >= 0.0 { "+" } else { "" },
+                )
+            } else {
+                String::new()
+            },
             prec = prec + 5
-        ));
+        )
+    }
+
+    /// Returns the text of the overlay: the FPS and frame time averaged over
+    /// the last [`FRAME_TIMES_WINDOW`] frames, the current max iterations and
+    /// escape radius, whether the view is computed in double-double precision
+    /// and the progress of the progressive rendering and of the accumulated
+    /// anti-aliasing, if they're enabled, followed by the coordinate prompt,
+    /// if it's open.
+    #[must_use]
+    fn overlay_text(&self) -> String {
+        let prompt = self
+            .view
+            .prompt
+            .as_ref()
+            .map(|prompt| format!("Go to (re, im, zoom): {prompt}_"));
+        if !self.view.show_overlay {
+            return prompt.unwrap_or_default();
+        }
+        let frame_times = &self.view.frame_times;
+        #[allow(clippy::cast_precision_loss, reason = "at most 60 frames")]
+        let frame_time = frame_times.iter().sum::<Duration>().as_secs_f64()
+            / frame_times.len().max(1) as f64;
+        let mut text = format!(
+            "FPS: {fps:.1}\nFrame: {ms:.2} ms\nMax Iter: {max_iter}\n\
+             Escape Radius: {radius}",
+            fps = frame_time.recip(),
+            ms = frame_time * 1000.0,
+            max_iter = self.uniforms().max_iter,
+            radius = self.uniforms().escape_radius,
+        );
+        if self.renderer.is_deep() {
+            text += "\nDeep Zoom (double-double)";
+        }
+        if let Some(iters) = self.renderer.progress() {
+            text += &format!(
+                "\nProgress: {iters}/{max_iter}",
+                max_iter = self.uniforms().max_iter,
+            );
+        }
+        if let Some(samples) = self.renderer.accumulated_samples() {
+            text += &format!("\nSamples: {samples}");
+        }
+        if let Some(prompt) = prompt {
+            text += "\n";
+            text += &prompt;
+        }
+        text
+    }
+
+    /// Writes the given `uniforms` to the GPU `buffer`.
+    fn write_uniforms(&self, buffer: &Buffer, uniforms: &Uniforms) {
+        write_uniforms(
+            &self.queue,
+            self.renderer.precision(),
+            buffer,
+            uniforms,
+        );
     }
 
     /// Renders the current frame to the window.
@@ -480,51 +2110,301 @@ impl AppState {
         let mut encoder = self
             .device
             .create_command_encoder(&CommandEncoderDescriptor::default());
+        self.draw_frame(&mut encoder, &view);
+        if let Some(bench) = &self.bench {
+            bench.resolve(&mut encoder);
+        }
+
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+
+        Ok(())
+    }
+
+    /// Records the render passes drawing a whole frame, the fractal and
+    /// everything on top of it, into the texture `view` of the size of the
+    /// window.
+    fn draw_frame(&self, encoder: &mut CommandEncoder, view: &TextureView) {
+        if let Some(bench) = &self.bench {
+            // Benchmarks time the single render pass of plain rendering.
+            self.renderer
+                .draw_timed(encoder, view, bench.timestamp_writes());
+        } else {
+            self.renderer.draw(encoder, view);
+        }
+        self.draw_precision_comparison(encoder, view);
+        if self.show_julia_inset() {
+            self.draw_julia_inset(encoder, view);
+        }
+        if self.view.show_minimap {
+            self.draw_minimap(encoder, view);
+        }
+        if self.view.show_crosshair {
+            self.draw_crosshair(encoder, view);
+        }
+        if self.view.show_overlay || self.view.prompt.is_some() {
+            let size = (self.config.width, self.config.height);
+            self.overlay.draw(encoder, view, size);
+        }
+    }
+
+    /// Whether the Julia set inset should be drawn on top of the view.
+    #[must_use]
+    fn show_julia_inset(&self) -> bool {
+        self.view.show_julia_inset && self.uniforms().is_mandelbrot == 1.0
+    }
+
+    /// Returns the `[x, y, width, height]` of the Julia set inset in the
+    /// bottom-right corner of the window.
+    #[must_use]
+    fn julia_inset_rect(&self) -> [u32; 4] {
+        let (width, height) = (self.config.width, self.config.height);
+        let size = width.min(height) / 3;
+        let margin = size / 16;
+        let x = width.saturating_sub(size + margin);
+        let y = height.saturating_sub(size + margin);
+        [x, y, size, size]
+    }
 
+    /// Returns the [`Uniforms`] of the Julia set inset, showing the whole Julia
+    /// set of the point under the cursor.
+    #[must_use]
+    fn julia_inset_uniforms(&self) -> Uniforms {
+        let [x, y, width, height] = self.julia_inset_rect();
+        Uniforms {
+            resolution: [width.into(), height.into()],
+            // Fragment positions are relative to the whole window, not the
+            // viewport, so shift them back to the inset's own coordinates.
+            tile_offset: [-f64::from(x), -f64::from(y)],
+            offset: [0.0, 0.0],
+            zooms: JULIA_ZOOMS,
+            julia_c: self.mouse_coords().into(),
+            is_mandelbrot: 0.0,
+            sample_count: 1,
+            rotation: 0.0,
+            ..*self.uniforms()
+        }
+    }
+
+    /// Records a render pass drawing the Julia set inset on top of the
+    /// already rendered `view`.
+    #[allow(clippy::cast_precision_loss, reason = "window sizes are small")]
+    fn draw_julia_inset(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+    ) {
         let mut render_pass =
             encoder.begin_render_pass(&RenderPassDescriptor {
-                label: Some("Render Pass"),
+                label: Some("Julia Inset Render Pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
-                    ops: Operations::default(),
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
                     depth_slice: None,
                 })],
                 ..Default::default()
             });
 
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        let [x, y, width, height] = self.julia_inset_rect();
+        render_pass.set_viewport(
+            x as f32,
+            y as f32,
+            width as f32,
+            height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(self.renderer.pipeline());
+        render_pass.set_bind_group(0, &self.inset_bind_group, &[]);
         render_pass.draw(0..3, 0..1);
+    }
+}
 
-        drop(render_pass);
+/// Events sent to the event loop from other threads.
+#[derive(Debug)]
+enum UserEvent {
+    /// The [`AppState`] created in the background, see
+    /// [`App::start_loading`].
+    Loaded(Box<Result<AppState, Error>>),
+}
 
-        self.queue.submit([encoder.finish()]);
-        frame.present();
-        self.window.request_redraw();
+#[derive(Debug)]
+pub struct App {
+    state: Option<AppState>,
+    /// Sends the [`UserEvent`]s to the event loop.
+    proxy: EventLoopProxy<UserEvent>,
+    /// Gamepad input, if it could be initialized.
+    gilrs: Option<Gilrs>,
+    /// The view to start with.
+    initial_uniforms: Uniforms,
+    /// The session saved on the last exit, if any.
+    session: Option<Session>,
+    /// User settings.
+    settings: Config,
+    /// Which adapters to choose from.
+    adapter_options: AdapterOptions,
+    /// Number of frames to benchmark, if `--bench` was given.
+    bench_frames: Option<u32>,
+    /// Index of the monitor to open the window on, the system's choice if
+    /// `None`.
+    monitor: Option<usize>,
+    /// Whether to start fullscreen.
+    fullscreen: bool,
+    /// Brightness in nits of the white of an HDR surface, `None` for SDR.
+    hdr_white: Option<f32>,
+    /// Whether to reload the shaders when they change.
+    dev: bool,
+    /// Initial size of the window in physical pixels, the system's choice if
+    /// `None`.
+    window_size: Option<(u32, u32)>,
+    /// Title of the window given with `--title`.
+    title: Option<String>,
+    /// Colors of the gradient loaded with `--palette`.
+    gradient: Option<Vec<[u8; 4]>>,
+    /// Whether to lock the Julia constant given with `--julia-c`.
+    lock_julia_c: bool,
+}
 
+impl App {
+    /// Creates a new [`App`] starting with the view given by the command-line
+    /// `args` and the user settings, initializing the gamepad input.
+    fn new(args: &Args, proxy: EventLoopProxy<UserEvent>) -> Self {
+        let mut settings = Config::load();
+        if let Some(scale) = args.export_scale() {
+            settings.export_scale = scale;
+        }
+        // Benchmarks start from the view given by the arguments only, so that
+        // they are reproducible.
+        let session =
+            args.bench.is_none().then(session::load_session).flatten();
+        let mut uniforms = settings.default_uniforms();
+        if let Some(session) = &session {
+            session.apply(&mut uniforms);
+        }
+        let gilrs = Gilrs::new()
+            .inspect_err(|e| eprintln!("Failed to initialize gamepads: {e}"))
+            .ok();
+        let gradient = args.palette.as_deref().and_then(|path| {
+            gradient::load_gradient(path)
+                .inspect_err(|e| eprintln!("Warning: ignoring --palette: {e}"))
+                .ok()
+        });
+        let mut initial_uniforms = args.initial_uniforms(uniforms);
+        if gradient.is_some() {
+            initial_uniforms.palette = GRADIENT_PALETTE;
+        }
+        Self {
+            state: None,
+            proxy,
+            gilrs,
+            initial_uniforms,
+            session,
+            settings,
+            adapter_options: args.adapter_options(),
+            bench_frames: args.bench,
+            monitor: args.monitor,
+            fullscreen: args.fullscreen,
+            hdr_white: args.hdr,
+            dev: args.dev,
+            window_size: args.window_size(),
+            title: args.title.clone(),
+            gradient,
+            lock_julia_c: args.locks_julia_c(),
+        }
+    }
+
+    /// Creates the [`Window`] and starts creating the [`AppState`] rendering
+    /// into it on another thread, so that the window stays responsive while
+    /// the device and the pipelines are created, which is slow on some
+    /// backends. The state arrives as a [`UserEvent::Loaded`].
+    fn start_loading(&self, event_loop: &ActiveEventLoop) -> Result<(), Error> {
+        let monitor = self
+            .monitor
+            .and_then(|index| monitor::choose_monitor(event_loop, index));
+        let title = self.title.as_deref().unwrap_or("Mandelbrot");
+        let mut attributes = Window::default_attributes().with_title(title);
+        if let Some(monitor) = &monitor {
+            attributes = attributes.with_position(monitor.position());
+        }
+        if let Some((width, height)) = self.window_size {
+            attributes =
+                attributes.with_inner_size(PhysicalSize::new(width, height));
+        }
+        let window = Arc::new(event_loop.create_window(attributes)?);
+        if let Some(monitor) = &monitor {
+            monitor::center_on(&window, monitor);
+        }
+
+        // Nothing can be drawn before there is a device.
+        window.set_title(&format!("{title} (loading…)"));
+
+        let targets = create_targets(self.adapter_options, Some(&window))?;
+        let proxy = self.proxy.clone();
+        let (uniforms, settings) =
+            (self.initial_uniforms, self.settings.clone());
+        let (options, hdr_white) = (self.adapter_options, self.hdr_white);
+        let gradient = self.gradient.clone();
+        std::thread::spawn(move || {
+            let state = pollster::block_on(AppState::new(
+                window, targets, uniforms, settings, options, hdr_white,
+                gradient,
+            ));
+            // Fails only if the event loop has already exited.
+            let _ = proxy.send_event(UserEvent::Loaded(Box::new(state)));
+        });
         Ok(())
     }
-}
 
-#[derive(Debug, Default)]
-pub struct App {
-    state: Option<AppState>,
+    /// Finishes setting up the `state` created by [`App::start_loading`].
+    fn finish_loading(&self, state: &mut AppState) {
+        // The window may have been resized or moved to another monitor while
+        // the state was loading.
+        state.rescale(state.window.scale_factor());
+        if let Some(session) = &self.session {
+            state.restore_session(session);
+        }
+        if self.lock_julia_c {
+            state.view.julia_locked = true;
+        }
+        if self.fullscreen {
+            state.toggle_fullscreen();
+        }
+        if let Some(frames) = self.bench_frames {
+            state.start_bench(frames);
+        }
+        if self.dev {
+            state.shader_watcher = Some(ShaderWatcher::new());
+        }
+        state.custom_title.clone_from(&self.title);
+        state.window.request_redraw();
+    }
 }
 
-impl ApplicationHandler for App {
+impl ApplicationHandler<UserEvent> for App {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window = Arc::new(
-            event_loop
-                .create_window(
-                    Window::default_attributes().with_title("Mandelbrot"),
-                )
-                .expect("Failed to create window"),
-        );
+        if let Err(e) = self.start_loading(event_loop) {
+            eprintln!("Error: {e}");
+            event_loop.exit();
+        }
+    }
 
-        let state = pollster::block_on(AppState::new(window))
-            .expect("Failed to create state");
-        self.state = Some(state);
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::Loaded(state) => match *state {
+                Ok(mut state) => {
+                    self.finish_loading(&mut state);
+                    self.state = Some(state);
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    event_loop.exit();
+                }
+            },
+        }
     }
 
     fn window_event(
@@ -533,12 +2413,35 @@ impl ApplicationHandler for App {
         _window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
-        let Some(state) = &mut self.state else { return };
+        let Some(state) = &mut self.state else {
+            // The window can be closed while the state is loading.
+            if event == WindowEvent::CloseRequested {
+                event_loop.exit();
+            }
+            return;
+        };
 
+        if event != WindowEvent::RedrawRequested {
+            state.view.redraw = true;
+        }
         match event {
             WindowEvent::Resized(physical_size) => {
+                state.view.zero_size =
+                    physical_size.width == 0 || physical_size.height == 0;
+                state.update_paused_render();
                 state.resize(physical_size);
             }
+            WindowEvent::Focused(focused) => {
+                state.view.focused = focused;
+                state.update_paused_render();
+            }
+            WindowEvent::Occluded(occluded) => {
+                state.view.occluded = occluded;
+                state.update_paused_render();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                state.rescale(scale_factor);
+            }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -547,8 +2450,14 @@ impl ApplicationHandler for App {
                         ..
                     },
                 ..
+            } if state.view.prompt.is_none() => {
+                state.save_session();
+                event_loop.exit();
+            }
+            WindowEvent::CloseRequested => {
+                state.save_session();
+                event_loop.exit();
             }
-            | WindowEvent::CloseRequested => event_loop.exit(),
             WindowEvent::KeyboardInput {
                 device_id: _,
                 event: _,
@@ -558,6 +2467,7 @@ impl ApplicationHandler for App {
                 device_id: _,
                 position: _,
             }
+            | WindowEvent::CursorLeft { device_id: _ }
             | WindowEvent::MouseWheel {
                 device_id: _,
                 delta: _,
@@ -568,26 +2478,230 @@ impl ApplicationHandler for App {
                 state: _,
                 button: _,
             }
+            | WindowEvent::Touch(_)
+            | WindowEvent::PinchGesture {
+                device_id: _,
+                delta: _,
+                phase: _,
+            }
             | WindowEvent::ModifiersChanged(_) => {
                 state.input(event);
             }
+            // The system can still ask for a frame, e.g. when a minimized
+            // window is previewed, but the surface may have no texture then.
+            WindowEvent::RedrawRequested if state.view.paused_render => {}
             WindowEvent::RedrawRequested => {
                 state.update();
+                let started = Instant::now();
                 match state.render() {
                     Ok(()) => {}
                     Err(SurfaceError::Outdated | SurfaceError::Lost) => {
                         let window_size = state.window.inner_size();
                         state.resize(window_size);
                     }
+                    // The frame wasn't ready in time, skip it and retry.
+                    Err(SurfaceError::Timeout) => state.window.request_redraw(),
+                    Err(SurfaceError::OutOfMemory) => {
+                        if state.free_gpu_memory() {
+                            state.window.request_redraw();
+                        } else {
+                            eprintln!("Out of GPU memory, exiting");
+                            state.save_session();
+                            event_loop.exit();
+                        }
+                    }
                     Err(e) => eprintln!("Surface error: {e:?}"),
                 }
+                state.record_frame();
+                if state.measure_bench_frame(started) {
+                    event_loop.exit();
+                }
             }
             _ => {}
         }
     }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(state) = &mut self.state else {
+            return;
+        };
+        if let Some(gilrs) = &mut self.gilrs {
+            while let Some(Event { event, .. }) = gilrs.next_event() {
+                state.gamepad_input(event);
+                state.view.redraw = true;
+            }
+        }
+        state.reload_shaders();
+        if state.needs_redraw() {
+            state.window.request_redraw();
+        } else {
+            state.view.idle = true;
+        }
+        // Nothing wakes the event loop up when the gamepads or the shader
+        // files change, so they're polled, but only while there is a gamepad
+        // to listen to. One plugged in while idle is noticed on the next
+        // window event.
+        let gamepad = self
+            .gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.gamepads().next().is_some())
+            || state.view.stick != (0.0, 0.0)
+            || state.view.triggers != (0.0, 0.0);
+        event_loop.set_control_flow(
+            if gamepad || state.shader_watcher.is_some() {
+                ControlFlow::WaitUntil(Instant::now() + IDLE_POLL_INTERVAL)
+            } else {
+                ControlFlow::Wait
+            },
+        );
+    }
 }
 
 fn main() {
-    let event_loop = EventLoop::new().expect("Failed to create event loop");
-    let _ = event_loop.run_app(&mut App::default());
+    let args = Args::parse();
+    if let Some(path) = &args.headless {
+        if let Err(e) = headless::run(path, args.adapter_options()) {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let event_loop = EventLoop::with_user_event()
+        .build()
+        .expect("Failed to create event loop");
+    let mut app = App::new(&args, event_loop.create_proxy());
+    if let Err(e) = event_loop.run_app(&mut app) {
+        eprintln!("Error: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The normalization of `pixel_to_plane` in the shader, before rotating,
+    /// for a pixel `position` of a surface of the given `resolution`.
+    fn shader_pixel_to_plane(
+        position: [f64; 2],
+        resolution: [f64; 2],
+    ) -> [f64; 2] {
+        let [px, py] = position;
+        let [w, h] = resolution;
+        let uv = [px, h - py];
+        [(uv[0] * 2.0 - w) / w, (uv[1] * 2.0 - h) / w]
+    }
+
+    /// Asserts that the cursor at `position` in a viewport of the given
+    /// `resolution` is normalized to `expected`, with `y` pointing down, and
+    /// matches the point the shader renders there.
+    fn assert_normalized(
+        position: [f64; 2],
+        resolution: [f64; 2],
+        expected: [f64; 2],
+    ) {
+        let [w, h] = resolution;
+        let [nx, ny] = normalize_position(position, [0.0, 0.0, w, h]);
+        assert!((nx - expected[0]).abs() < 1e-12, "{nx} != {}", expected[0]);
+        assert!((ny - expected[1]).abs() < 1e-12, "{ny} != {}", expected[1]);
+        let [sx, sy] = shader_pixel_to_plane(position, resolution);
+        assert!((nx - sx).abs() < 1e-12 && (ny + sy).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cursor_matches_the_shader_in_a_16_9_window() {
+        let resolution = [1920.0, 1080.0];
+        assert_normalized([960.0, 540.0], resolution, [0.0, 0.0]);
+        assert_normalized([1920.0, 540.0], resolution, [1.0, 0.0]);
+        assert_normalized([0.0, 540.0], resolution, [-1.0, 0.0]);
+        // The height keeps the aspect ratio, 9/16 of the width.
+        assert_normalized([960.0, 0.0], resolution, [0.0, -0.5625]);
+        assert_normalized([1920.0, 1080.0], resolution, [1.0, 0.5625]);
+    }
+
+    #[test]
+    fn cursor_maps_to_the_same_point_at_scale_factor_2() {
+        let logical_viewport = [0.0, 0.0, 960.0, 540.0];
+        let scale_factor = 2.0;
+        let physical_viewport = logical_viewport.map(|x| x * scale_factor);
+        for logical in [[480.0, 270.0], [960.0, 0.0], [123.0, 456.0]] {
+            // Positions are physical, as after `AppState::rescale`.
+            let physical = logical.map(|x| x * scale_factor);
+            let [nx, ny] = normalize_position(physical, physical_viewport);
+            let [ex, ey] = normalize_position(logical, logical_viewport);
+            assert!((nx - ex).abs() < 1e-12 && (ny - ey).abs() < 1e-12);
+            let [sx, sy] = shader_pixel_to_plane(
+                physical,
+                [physical_viewport[2], physical_viewport[3]],
+            );
+            assert!((nx - sx).abs() < 1e-12 && (ny + sy).abs() < 1e-12);
+        }
+        let center = normalize_position([960.0, 540.0], physical_viewport);
+        assert_eq!(center, [0.0, 0.0]);
+    }
+
+    /// Returns the point under the [`Uniforms::mouse_position`], like
+    /// [`AppState::mouse_coords`].
+    fn point_under_cursor(uniforms: &Uniforms) -> [f64; 2] {
+        let [mx, my] = uniforms.mouse_position;
+        let [x, y] = uniforms.rotated([mx, -my]);
+        let zoom = (uniforms.zooms / 10.0).exp();
+        let [ox, oy] = uniforms.offset;
+        let [lx, ly] = uniforms.offset_lo;
+        [ox + x.mul_add(zoom, lx), oy + y.mul_add(zoom, ly)]
+    }
+
+    #[test]
+    fn mouse_zoom_keeps_the_point_under_the_cursor() {
+        let mut uniforms = Uniforms {
+            offset: [-0.743_643_887_037_151, 0.131_825_904_205_33],
+            mouse_position: [0.37, -0.21],
+            rotation: 0.3,
+            ..Uniforms::default()
+        };
+        let point = point_under_cursor(&uniforms);
+        // Zooming out with `mouse_zoom(1.0)`, then back in further.
+        for delta in [1.0; 10].into_iter().chain([-1.0; 30]) {
+            let from_zooms = uniforms.zooms;
+            uniforms.zooms += delta;
+            keep_point_under_cursor(&mut uniforms, from_zooms);
+            let [x, y] = point_under_cursor(&uniforms);
+            // Within a billionth of the width of the view.
+            let tolerance = 2e-9 * (uniforms.zooms / 10.0).exp();
+            assert!(
+                (x - point[0]).abs() < tolerance
+                    && (y - point[1]).abs() < tolerance,
+                "moved from {point:?} to {:?} at zooms {}",
+                [x, y],
+                uniforms.zooms,
+            );
+        }
+    }
+
+    #[test]
+    fn pan_speed_on_the_screen_is_independent_of_the_zoom() {
+        let move_speed = Config::default().move_speed;
+        for zooms in [8.0, -200.0] {
+            let uniforms = Uniforms {
+                zooms,
+                rotation: 0.5,
+                ..Uniforms::default()
+            };
+            // The viewport width spans `2 * zoom` in the complex plane.
+            let width = 2.0 * (zooms / 10.0).exp();
+            for direction in [(1.0, 0.0), (0.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            {
+                // Ten frames make up a second.
+                let [x, y] = (0..10)
+                    .map(|_| pan_delta(&uniforms, direction, move_speed * 0.1))
+                    .fold([0.0, 0.0], |[x, y], [dx, dy]| [x + dx, y + dy]);
+                let fraction = x.hypot(y) / width;
+                assert!(
+                    (fraction - move_speed / 2.0).abs() < 1e-12,
+                    "panned {fraction} of the width at zooms {zooms} in the \
+                     direction {direction:?}",
+                );
+            }
+        }
+    }
 }
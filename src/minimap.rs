@@ -0,0 +1,265 @@
+//! A minimap in the bottom-left corner of the window: an overview of the
+//! whole fractal with the outline of the current view, to keep track of where
+//! a deep zoom is.
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, ColorTargetState, ColorWrites,
+    CommandEncoder, Device, FragmentState, LoadOp, MultisampleState,
+    Operations, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, PrimitiveTopology, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    ShaderStages, StoreOp, TextureFormat, TextureView, VertexState,
+    include_wgsl,
+};
+
+use crate::{
+    AppState, JULIA_ZOOMS, MandelbrotRenderer, Uniforms, create_uniforms_buffer,
+};
+
+/// Minimum size of the outline of the view in pixels, so that it stays
+/// visible however deep the view is zoomed in.
+const MIN_MARKER_SIZE: f64 = 6.0;
+
+/// The resources for drawing the minimap.
+#[derive(Debug)]
+pub struct Minimap {
+    /// Draws the outline of the view.
+    pipeline: RenderPipeline,
+    /// The corners of the outline of the view, see
+    /// [`AppState::minimap_marker`].
+    marker_buffer: Buffer,
+    marker_bind_group: BindGroup,
+    /// The [`Uniforms`] of the overview, see [`AppState::minimap_uniforms`].
+    uniforms_buffer: Buffer,
+    uniforms_bind_group: BindGroup,
+}
+
+impl Minimap {
+    /// Creates a new [`Minimap`] rendering the overview with the `renderer`
+    /// into targets of the given `format`.
+    pub fn new(
+        device: &Device,
+        renderer: &MandelbrotRenderer,
+        format: TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("minimap.wgsl"));
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Minimap Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Minimap Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline =
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Minimap Pipeline"),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    targets: &[Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                layout: Some(&pipeline_layout),
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::LineStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            });
+
+        let marker_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Minimap Marker Buffer"),
+            size: size_of::<[[f32; 4]; 4]>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let marker_bind_group =
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Minimap Marker Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: marker_buffer.as_entire_binding(),
+                }],
+            });
+        let (uniforms_buffer, uniforms_bind_group) = create_uniforms_buffer(
+            device,
+            renderer.bind_group_layout(),
+            renderer.precision(),
+            renderer.gradient(),
+            "Minimap Uniforms",
+        );
+
+        Self {
+            pipeline,
+            marker_buffer,
+            marker_bind_group,
+            uniforms_buffer,
+            uniforms_bind_group,
+        }
+    }
+}
+
+impl AppState {
+    /// Shows or hides the minimap.
+    pub fn toggle_minimap(&mut self) {
+        self.view.show_minimap = !self.view.show_minimap;
+    }
+
+    /// Returns the `[x, y, width, height]` of the minimap in the bottom-left
+    /// corner of the window, a quarter of its size.
+    #[must_use]
+    fn minimap_rect(&self) -> [u32; 4] {
+        let (width, height) = (self.config.width / 4, self.config.height / 4);
+        let margin = width.min(height) / 16;
+        let y = self.config.height.saturating_sub(height + margin);
+        [margin, y, width, height]
+    }
+
+    /// Returns the [`Uniforms`] of the minimap, showing the whole fractal as
+    /// the view is reset to, or the whole Julia set.
+    #[must_use]
+    fn minimap_uniforms(&self) -> Uniforms {
+        let [x, y, width, height] = self.minimap_rect();
+        let default = self.settings.default_uniforms();
+        let (offset, zooms) = if self.uniforms().is_mandelbrot == 1.0 {
+            (default.offset, default.zooms)
+        } else {
+            ([0.0, 0.0], JULIA_ZOOMS)
+        };
+        Uniforms {
+            resolution: [width.into(), height.into()],
+            // Fragment positions are relative to the whole window, not the
+            // viewport, so shift them back to the minimap's own coordinates.
+            tile_offset: [-f64::from(x), -f64::from(y)],
+            offset,
+            offset_lo: [0.0, 0.0],
+            zooms,
+            max_iter: default.max_iter,
+            sample_count: 1,
+            rotation: 0.0,
+            ..*self.uniforms()
+        }
+    }
+
+    /// Returns the corners of the current view in the clip space of the
+    /// minimap, enlarged to [`MIN_MARKER_SIZE`] if it's smaller.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "the minimap is drawn in f32"
+    )]
+    fn minimap_marker(&self) -> [[f32; 4]; 4] {
+        let overview = self.minimap_uniforms();
+        let [width, height] = overview.resolution;
+        let overview_zoom = (overview.zooms / 10.0).exp();
+        let ([x, y], [ox, oy]) = (self.uniforms().offset, overview.offset);
+        let center = [(x - ox) / overview_zoom, (y - oy) / overview_zoom];
+        let scale =
+            (self.compute_zoom() / overview_zoom).max(MIN_MARKER_SIZE / width);
+        // The view spans [-1, 1] horizontally, keeping the aspect ratio.
+        let [_, _, view_width, view_height] = self.viewport();
+        let aspect = f64::from(view_height) / f64::from(view_width);
+        [
+            [-1.0, -aspect],
+            [1.0, -aspect],
+            [1.0, aspect],
+            [-1.0, aspect],
+        ]
+        .map(|corner| {
+            let [cx, cy] = self.uniforms().rotated(corner);
+            [
+                cx.mul_add(scale, center[0]) as f32,
+                (cy.mul_add(scale, center[1]) * width / height) as f32,
+                0.0,
+                1.0,
+            ]
+        })
+    }
+
+    /// Writes the overview and the outline of the view to the buffers of the
+    /// minimap.
+    pub fn update_minimap(&self) {
+        self.write_uniforms(
+            &self.minimap.uniforms_buffer,
+            &self.minimap_uniforms(),
+        );
+        self.queue.write_buffer(
+            &self.minimap.marker_buffer,
+            0,
+            bytemuck::cast_slice(&self.minimap_marker()),
+        );
+    }
+
+    /// Records a render pass drawing the minimap on top of the already
+    /// rendered `view`.
+    #[allow(clippy::cast_precision_loss, reason = "window sizes are small")]
+    pub fn draw_minimap(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+    ) {
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Minimap Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+
+        let [x, y, width, height] = self.minimap_rect();
+        render_pass.set_viewport(
+            x as f32,
+            y as f32,
+            width as f32,
+            height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(self.renderer.pipeline());
+        render_pass.set_bind_group(0, &self.minimap.uniforms_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        render_pass.set_pipeline(&self.minimap.pipeline);
+        render_pass.set_bind_group(0, &self.minimap.marker_bind_group, &[]);
+        render_pass.draw(0..5, 0..1);
+    }
+}
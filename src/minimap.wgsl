@@ -0,0 +1,17 @@
+// The corners of the current view in the minimap, in clip space.
+@group(0) @binding(0)
+var<uniform> corners: array<vec4<f32>, 4>;
+
+// Draws the outline of the view as a line strip through the corners, back to
+// the first one.
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+    return corners[vertex_index % 4u];
+}
+
+@fragment
+fn fg_main() -> @location(0) vec4f {
+    return vec4f(1.0);
+}
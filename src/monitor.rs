@@ -0,0 +1,48 @@
+//! Opening the window on a monitor chosen with `--monitor`.
+
+use winit::{
+    dpi::PhysicalPosition, event_loop::ActiveEventLoop, monitor::MonitorHandle,
+    window::Window,
+};
+
+/// Returns the monitor with the given `index` out of the available ones,
+/// listing them. An out-of-range index is clamped to the last monitor with a
+/// warning. Returns `None` if no monitors are available.
+pub fn choose_monitor(
+    event_loop: &ActiveEventLoop,
+    index: usize,
+) -> Option<MonitorHandle> {
+    let monitors: Vec<_> = event_loop.available_monitors().collect();
+    if monitors.is_empty() {
+        eprintln!("No monitors available, opening the window on any");
+        return None;
+    }
+    eprintln!("Available monitors:");
+    for (i, monitor) in monitors.iter().enumerate() {
+        let size = monitor.size();
+        eprintln!(
+            "  {i}: {name} ({width}x{height})",
+            name = monitor.name().as_deref().unwrap_or("unnamed"),
+            width = size.width,
+            height = size.height,
+        );
+    }
+    let last = monitors.len() - 1;
+    if index > last {
+        eprintln!("Warning: --monitor {index} is out of range, using {last}");
+    }
+    monitors.into_iter().nth(index.min(last))
+}
+
+/// Moves the `window` to the center of the `monitor`.
+pub fn center_on(window: &Window, monitor: &MonitorHandle) {
+    let position = monitor.position();
+    let (size, window_size) = (monitor.size(), window.outer_size());
+    let center = |start: i32, length: u32, window_length: u32| {
+        start.saturating_add_unsigned(length.saturating_sub(window_length) / 2)
+    };
+    window.set_outer_position(PhysicalPosition::new(
+        center(position.x, size.width, window_size.width),
+        center(position.y, size.height, window_size.height),
+    ));
+}
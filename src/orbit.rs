@@ -0,0 +1,195 @@
+//! Computing the orbit of a single point on the CPU, to show the iteration
+//! count under the cursor without reading it back from the GPU. Mirrors
+//! `continue_orbit` in the shader, always in `f64`.
+
+use std::fmt;
+
+use crate::{BURNING_SHIP, MULTIBROT, NEWTON, TRICORN, Uniforms};
+
+/// Most iterations computed for a point, however high [`Uniforms::max_iter`]
+/// is, since they run on the thread handling the events.
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+
+/// Squared distance below which two points of an orbit are considered equal by
+/// the period detection, matches `PERIOD_EPSILON` in the shader.
+const PERIOD_EPSILON: f64 = 1e-20;
+
+/// Squared length of a Newton step below which the orbit is considered to have
+/// converged, matches `NEWTON_EPSILON` in the shader.
+const NEWTON_EPSILON: f64 = 1e-10;
+
+/// A complex number as its real and imaginary parts.
+type Complex = (f64, f64);
+
+/// How the orbit of a point ends, see [`escape_iterations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// The orbit escapes after that many iterations.
+    After(u32),
+    /// The orbit doesn't escape within [`Uniforms::max_iter`], i.e. the point
+    /// is in the set.
+    Never,
+    /// The orbit doesn't escape within [`MAX_ITERATIONS`], fewer than
+    /// [`Uniforms::max_iter`], so it's unknown whether it does.
+    Beyond,
+}
+
+impl fmt::Display for Escape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::After(iter) => write!(f, "{iter}"),
+            Self::Never => write!(f, "∞"),
+            Self::Beyond => write!(f, "≥{MAX_ITERATIONS}"),
+        }
+    }
+}
+
+/// Returns after how many iterations the orbit of the `point` of the fractal
+/// described by the `uniforms` escapes, computing at most [`MAX_ITERATIONS`].
+#[must_use]
+pub fn escape_iterations(uniforms: &Uniforms, point: Complex) -> Escape {
+    if uniforms.fractal_type == NEWTON {
+        return newton_iterations(uniforms, point);
+    }
+    // Morphs between the sets like the shader.
+    let t = f64::from(uniforms.is_mandelbrot);
+    let [jx, jy] = uniforms.julia_c;
+    let c = (
+        point.0.mul_add(t, jx * (1.0 - t)),
+        point.1.mul_add(t, jy * (1.0 - t)),
+    );
+    let mut z = (point.0 * (1.0 - t), point.1 * (1.0 - t));
+    let radius = f64::from(uniforms.escape_radius);
+
+    // Brent's cycle detection, so that the points in the set don't take all
+    // the iterations.
+    let mut reference = z;
+    let mut since = 0;
+    let mut window = 1;
+    for iter in 0..uniforms.max_iter.min(MAX_ITERATIONS) {
+        z = iterate(z, c, uniforms.fractal_type, uniforms.power);
+        if z.0.mul_add(z.0, z.1 * z.1) > radius * radius {
+            return Escape::After(iter);
+        }
+        since += 1;
+        let d = (z.0 - reference.0, z.1 - reference.1);
+        if d.0.mul_add(d.0, d.1 * d.1) < PERIOD_EPSILON {
+            return Escape::Never;
+        }
+        if since == window {
+            reference = z;
+            since = 0;
+            window *= 2;
+        }
+    }
+    unescaped(uniforms)
+}
+
+/// Returns how an orbit that didn't escape within the iterations computed for
+/// it ends: never, unless they were cut short at [`MAX_ITERATIONS`].
+const fn unescaped(uniforms: &Uniforms) -> Escape {
+    if uniforms.max_iter > MAX_ITERATIONS {
+        Escape::Beyond
+    } else {
+        Escape::Never
+    }
+}
+
+/// Returns the number of iterations after which Newton's method for `z^n - 1`
+/// starting from `z` converges to a root, like `continue_newton` in the
+/// shader.
+fn newton_iterations(uniforms: &Uniforms, mut z: Complex) -> Escape {
+    let n = newton_degree(uniforms.power);
+    for iter in 0..uniforms.max_iter.min(MAX_ITERATIONS) {
+        // The derivative vanishes at 0.
+        if z == (0.0, 0.0) {
+            return Escape::Never;
+        }
+        let w = complex_pow(z, (n - 1) as f32);
+        let p = (
+            w.0.mul_add(z.0, -w.1 * z.1) - 1.0,
+            w.0.mul_add(z.1, w.1 * z.0),
+        );
+        let dp = (f64::from(n) * w.0, f64::from(n) * w.1);
+        let d = dp.0.mul_add(dp.0, dp.1 * dp.1);
+        let step = (
+            p.0.mul_add(dp.0, p.1 * dp.1) / d,
+            p.1.mul_add(dp.0, -p.0 * dp.1) / d,
+        );
+        z = (z.0 - step.0, z.1 - step.1);
+        if step.0.mul_add(step.0, step.1 * step.1) < NEWTON_EPSILON {
+            return Escape::After(iter);
+        }
+    }
+    unescaped(uniforms)
+}
+
+/// Returns the degree `n` of the polynomial `z^n - 1` of Newton's method for
+/// the given [`Uniforms::power`]: its integer part, at least 3.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "the power is clamped to a small positive range"
+)]
+pub fn newton_degree(power: f32) -> u32 {
+    power.floor().max(3.0) as u32
+}
+
+/// Performs a single iteration of the formula of the given `fractal_type`.
+fn iterate(z: Complex, c: Complex, fractal_type: u32, power: f32) -> Complex {
+    let (x, y) = match fractal_type {
+        // z = (|re| + |im|i)^2 + c
+        BURNING_SHIP => {
+            let (x, y) = (z.0.abs(), z.1.abs());
+            (x.mul_add(x, -y * y), 2.0 * x * y)
+        }
+        // z = conj(z)^2 + c
+        TRICORN => (z.0.mul_add(z.0, -z.1 * z.1), -2.0 * z.0 * z.1),
+        // z = z^d + c
+        MULTIBROT => complex_pow(z, power),
+        // z = z^2 + c
+        _ => (z.0.mul_add(z.0, -z.1 * z.1), 2.0 * z.0 * z.1),
+    };
+    (x + c.0, y + c.1)
+}
+
+/// Raises `z` to the power `d`. Integer powers are computed by repeated
+/// multiplication, like in the shader, others in polar form.
+fn complex_pow(z: Complex, d: f32) -> Complex {
+    if d.fract() == 0.0 {
+        let mut w = z;
+        for _ in 1..d as u32 {
+            w = (w.0.mul_add(z.0, -w.1 * z.1), w.0.mul_add(z.1, w.1 * z.0));
+        }
+        return w;
+    }
+    let d = f64::from(d);
+    let r = z.0.hypot(z.1).powf(d);
+    let theta = z.1.atan2(z.0) * d;
+    (r * theta.cos(), r * theta.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_iterations_stop_at_the_bound() {
+        let uniforms = Uniforms {
+            max_iter: u32::MAX / 10,
+            ..Uniforms::default()
+        };
+        assert_eq!(escape_iterations(&uniforms, (1.0, 0.0)), Escape::After(4));
+        // Found to be periodic long before the bound.
+        assert_eq!(escape_iterations(&uniforms, (-1.0, 0.0)), Escape::Never);
+        // Where the period 2 bulb touches the cardioid, the orbit converges
+        // too slowly to be found periodic.
+        assert_eq!(escape_iterations(&uniforms, (-0.75, 0.0)), Escape::Beyond);
+        let uniforms = Uniforms {
+            max_iter: 1000,
+            ..uniforms
+        };
+        assert_eq!(escape_iterations(&uniforms, (-0.75, 0.0)), Escape::Never);
+    }
+}
@@ -0,0 +1,304 @@
+//! A minimal text overlay drawn on top of the fractal, using a built-in bitmap
+//! font rasterized on the CPU into a texture.
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource,
+    BindingType, BlendState, ColorTargetState, ColorWrites, CommandEncoder,
+    Device, Extent3d, FragmentState, LoadOp, MultisampleState, Operations,
+    Origin3d, PipelineCompilationOptions, PipelineLayoutDescriptor,
+    PrimitiveState, Queue, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, RenderPipelineDescriptor, ShaderStages, StoreOp,
+    TexelCopyBufferLayout, TexelCopyTextureInfo, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState, include_wgsl,
+};
+
+/// Width of a glyph in pixels.
+const GLYPH_WIDTH: usize = 5;
+/// Height of a glyph in pixels.
+const GLYPH_HEIGHT: usize = 7;
+/// Size of a character cell, the glyph plus spacing.
+const CELL: (usize, usize) = (GLYPH_WIDTH + 1, GLYPH_HEIGHT + 1);
+/// How many screen pixels a single font pixel takes.
+const SCALE: u32 = 2;
+/// Distance of the overlay from the top-left corner of the window.
+const MARGIN: u32 = 8;
+
+/// Color of the glyphs.
+const FOREGROUND: [u8; 4] = [255, 255, 255, 255];
+/// Color of the box behind the text.
+const BACKGROUND: [u8; 4] = [0, 0, 0, 160];
+
+/// A 5x7 bitmap font. Each row of a glyph is stored in the lowest 5 bits,
+/// the most significant of them being the leftmost pixel.
+const FONT: &[(char, [u8; GLYPH_HEIGHT])] = &[
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('=', [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+    ('|', [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+];
+
+/// Returns the bitmap of the glyph for `c`, falling back to `?` for
+/// characters missing from the [`FONT`]. Lowercase letters are drawn as
+/// uppercase.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    let find = |c| FONT.iter().find(|(g, _)| *g == c);
+    find(c.to_ascii_uppercase())
+        .or_else(|| find('?'))
+        .map(|(_, bitmap)| *bitmap)
+        .unwrap_or_default()
+}
+
+/// Rasterizes the (possibly multiline) `text` into RGBA8 pixels, returning
+/// them along with the width and height of the image.
+fn rasterize(text: &str) -> (Vec<u8>, usize, usize) {
+    let lines: Vec<_> = text.lines().collect();
+    let columns = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    // One pixel of padding around the text.
+    let width = columns * CELL.0 + 1;
+    let height = lines.len() * CELL.1 + 1;
+
+    let mut pixels = BACKGROUND.repeat(width * height);
+    for (row, line) in lines.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            for (y, bits) in glyph(c).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        let px = column * CELL.0 + 1 + x;
+                        let py = row * CELL.1 + 1 + y;
+                        let i = (py * width + px) * 4;
+                        pixels[i..i + 4].copy_from_slice(&FOREGROUND);
+                    }
+                }
+            }
+        }
+    }
+
+    (pixels, width, height)
+}
+
+/// Text drawn in the top-left corner of the window.
+#[derive(Debug)]
+pub struct Overlay {
+    pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    /// The rasterized text and its bind group, if there is any text.
+    text: Option<(Texture, BindGroup)>,
+}
+
+impl Overlay {
+    /// Creates a new [`Overlay`] that draws to targets of the given `format`.
+    pub fn new(device: &Device, format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("overlay.wgsl"));
+
+        let bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Overlay Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float {
+                            filterable: false,
+                        },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Overlay Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline =
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Overlay Pipeline"),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: None,
+                    compilation_options: PipelineCompilationOptions::default(),
+                    targets: &[Some(ColorTargetState {
+                        format,
+                        blend: Some(BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                layout: Some(&pipeline_layout),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            text: None,
+        }
+    }
+
+    /// Replaces the displayed text.
+    #[allow(clippy::cast_possible_truncation, reason = "the text is short")]
+    pub fn set_text(&mut self, device: &Device, queue: &Queue, text: &str) {
+        let (pixels, width, height) = rasterize(text);
+        let size = Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        };
+
+        // Only recreate the texture when the size of the text changes.
+        if self.text.as_ref().is_none_or(|(t, _)| t.size() != size) {
+            let texture = device.create_texture(&TextureDescriptor {
+                label: Some("Overlay Texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&TextureViewDescriptor::default());
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Overlay Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                }],
+            });
+            self.text = Some((texture, bind_group));
+        }
+
+        let Some((texture, _)) = &self.text else {
+            return;
+        };
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &pixels,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.width * 4),
+                rows_per_image: None,
+            },
+            size,
+        );
+    }
+
+    /// Records a render pass drawing the text on top of the already rendered
+    /// `view` of size `target_size`.
+    #[allow(clippy::cast_precision_loss, reason = "sizes are small")]
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        target_size: (u32, u32),
+    ) {
+        let Some((texture, bind_group)) = &self.text else {
+            return;
+        };
+        let (target_width, target_height) = target_size;
+        if target_width <= MARGIN || target_height <= MARGIN {
+            return;
+        }
+        // The viewport must stay within the target, so squeeze the text if it
+        // doesn't fit.
+        let width = (texture.width() * SCALE).min(target_width - MARGIN);
+        let height = (texture.height() * SCALE).min(target_height - MARGIN);
+
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Overlay Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+
+        render_pass.set_viewport(
+            MARGIN as f32,
+            MARGIN as f32,
+            width as f32,
+            height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,30 @@
+@group(0) @binding(0)
+var glyphs: texture_2d<f32>;
+
+struct Interpolators {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> Interpolators {
+    let vert = array(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 2.0),
+        vec2<f32>(2.0, 0.0),
+    );
+    let uv = vert[vertex_index];
+    return Interpolators(
+        vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0),
+        uv,
+    );
+}
+
+@fragment
+fn fg_main(i: Interpolators) -> @location(0) vec4f {
+    let size = textureDimensions(glyphs);
+    let texel = min(vec2<u32>(i.uv * vec2<f32>(size)), size - 1u);
+    return textureLoad(glyphs, texel, 0);
+}
@@ -0,0 +1,281 @@
+//! Recording a zoom flythrough video: frames are rendered offscreen at a fixed
+//! resolution while zooming from the initial view into the current one, and
+//! piped to `ffmpeg`, or saved as a numbered PNG sequence if it isn't
+//! installed.
+//!
+//! The same machinery records color cycling animations, holding the view still
+//! and shifting the palettes once around, which loop seamlessly.
+
+use std::{
+    f32::consts::TAU,
+    fs,
+    io::{self, Write as _},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+};
+
+use crate::{AppState, Error, Uniforms};
+
+/// Size of the recorded frames.
+const RECORD_SIZE: (u32, u32) = (1280, 720);
+
+/// Frame rate of the recorded video.
+const RECORD_FPS: u32 = 30;
+
+/// How many [`Uniforms::zooms`] the recording zooms per second of video.
+const RECORD_ZOOM_SPEED: f64 = 4.0;
+
+/// Number of frames of a color cycling animation, 4 seconds at
+/// [`RECORD_FPS`].
+const COLOR_CYCLE_FRAMES: u32 = 120;
+
+/// An animation being recorded, one frame per rendered frame.
+#[derive(Debug)]
+pub struct Recording {
+    /// What changes from frame to frame.
+    animation: Animation,
+    /// Frame rate of the video.
+    fps: u32,
+    /// Index of the next frame to record.
+    frame: u32,
+    /// Total number of frames.
+    frame_count: u32,
+    /// Where the frames go.
+    output: Output,
+}
+
+/// What a [`Recording`] animates.
+#[derive(Debug, Clone, Copy)]
+enum Animation {
+    /// Zooming from the initial view into the current one.
+    Zoom {
+        /// The [`Uniforms::zooms`] of the first frame.
+        start_zooms: f64,
+        /// The [`Uniforms::zooms`] of the last frame.
+        target_zooms: f64,
+    },
+    /// Shifting the palettes of a still view once around.
+    ColorCycle {
+        /// The [`Uniforms::color_offset`] of the first frame.
+        start_offset: f32,
+        /// The [`Uniforms::time`] all frames are rendered at, so that the
+        /// rotation of the colors doesn't break the loop.
+        time: f64,
+    },
+}
+
+/// Destination of the recorded frames.
+#[derive(Debug)]
+enum Output {
+    /// `ffmpeg` encoding the raw RGBA frames written to its stdin.
+    Ffmpeg(Child),
+    /// Directory the frames are saved to as numbered PNG images.
+    Frames(PathBuf),
+}
+
+impl AppState {
+    /// Starts recording a zoom from the initial view into the current one, or
+    /// stops the running recording.
+    pub fn toggle_recording(&mut self) {
+        match self.view.recording.take() {
+            Some(recording) => finish_recording(recording),
+            None => self.start_recording(),
+        }
+    }
+
+    /// Starts recording the palettes of the current view cycling once around
+    /// as a PNG sequence, or stops the running recording.
+    pub fn toggle_color_cycle_recording(&mut self) {
+        if let Some(recording) = self.view.recording.take() {
+            finish_recording(recording);
+            return;
+        }
+
+        let name = color_cycle_name(self.uniforms());
+        let dir = PathBuf::from(&name);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            eprintln!("Failed to create {}: {e}", dir.display());
+            return;
+        }
+        eprintln!("Recording {COLOR_CYCLE_FRAMES} frames to {name}/");
+
+        self.view.recording = Some(Recording {
+            animation: Animation::ColorCycle {
+                start_offset: self.uniforms().color_offset,
+                time: self.uniforms().time,
+            },
+            fps: RECORD_FPS,
+            frame: 0,
+            frame_count: COLOR_CYCLE_FRAMES,
+            output: Output::Frames(dir),
+        });
+    }
+
+    /// Starts recording, spawning `ffmpeg` if it's available.
+    fn start_recording(&mut self) {
+        let start_zooms = self.settings.default_uniforms().zooms;
+        let target_zooms = self.uniforms().zooms;
+        let seconds = (start_zooms - target_zooms).abs() / RECORD_ZOOM_SPEED;
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "zooms are bounded"
+        )]
+        let frame_count = (seconds * f64::from(RECORD_FPS)).ceil() as u32;
+
+        let name = recording_name(self.uniforms());
+        let output = match spawn_ffmpeg(&format!("{name}.mp4"), RECORD_FPS) {
+            Ok(child) => {
+                eprintln!("Recording {frame_count} frames to {name}.mp4");
+                Output::Ffmpeg(child)
+            }
+            Err(e) => {
+                eprintln!("Failed to start ffmpeg ({e}), saving PNG frames");
+                let dir = PathBuf::from(&name);
+                if let Err(e) = fs::create_dir_all(&dir) {
+                    eprintln!("Failed to create {}: {e}", dir.display());
+                    return;
+                }
+                eprintln!("Recording {frame_count} frames to {name}/");
+                Output::Frames(dir)
+            }
+        };
+
+        self.view.recording = Some(Recording {
+            animation: Animation::Zoom {
+                start_zooms,
+                target_zooms,
+            },
+            fps: RECORD_FPS,
+            frame: 0,
+            frame_count: frame_count.max(1),
+            output,
+        });
+    }
+
+    /// Renders and writes the next frame of the recording, if there is one,
+    /// finishing the recording after the last frame or on failure.
+    pub fn record_frame(&mut self) {
+        let Some(recording) = &mut self.view.recording else {
+            return;
+        };
+        let uniforms = match recording.animation {
+            Animation::Zoom {
+                start_zooms,
+                target_zooms,
+            } => {
+                let progress = f64::from(recording.frame)
+                    / f64::from(recording.frame_count.saturating_sub(1).max(1));
+                Uniforms {
+                    zooms: start_zooms
+                        + (target_zooms - start_zooms) * progress,
+                    ..self.view.uniforms
+                }
+            }
+            Animation::ColorCycle { start_offset, time } => {
+                // The last frame stops short of a full turn, which would
+                // repeat the first one in the loop.
+                #[allow(
+                    clippy::cast_precision_loss,
+                    reason = "frame counts are small"
+                )]
+                let progress =
+                    recording.frame as f32 / recording.frame_count as f32;
+                Uniforms {
+                    color_offset: (start_offset + TAU * progress)
+                        .rem_euclid(TAU),
+                    time,
+                    ..self.view.uniforms
+                }
+            }
+        };
+        recording.frame += 1;
+        let done = recording.frame == recording.frame_count;
+
+        let result =
+            self.render_view(&uniforms, RECORD_SIZE, 1)
+                .and_then(|image| {
+                    let recording =
+                        self.view.recording.as_mut().expect("checked above");
+                    match &mut recording.output {
+                        Output::Ffmpeg(child) => {
+                            let stdin =
+                                child.stdin.as_mut().ok_or_else(|| {
+                                    io::Error::from(io::ErrorKind::BrokenPipe)
+                                })?;
+                            stdin.write_all(&image)?;
+                        }
+                        Output::Frames(dir) => {
+                            let file_name =
+                                format!("frame_{:05}.png", recording.frame);
+                            image.save(dir.join(file_name))?;
+                        }
+                    }
+                    Ok(())
+                });
+
+        let failed = result
+            .inspect_err(|e| eprintln!("Failed to record frame: {e}"))
+            .is_err();
+        if failed || done {
+            let recording = self.view.recording.take().expect("checked above");
+            finish_recording(recording);
+        }
+    }
+}
+
+/// Spawns `ffmpeg` encoding raw RGBA frames of [`RECORD_SIZE`] from its stdin
+/// at the given `fps` into the video at `path`.
+fn spawn_ffmpeg(path: &str, fps: u32) -> Result<Child, Error> {
+    let (width, height) = RECORD_SIZE;
+    let child = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+        .args(["-s", &format!("{width}x{height}")])
+        .args(["-r", &fps.to_string()])
+        .args(["-i", "-", "-pix_fmt", "yuv420p", path])
+        .stdin(Stdio::piped())
+        .spawn()?;
+    Ok(child)
+}
+
+/// Finishes the `recording`, waiting for `ffmpeg` to encode the remaining
+/// frames, and reports the outcome.
+fn finish_recording(recording: Recording) {
+    let frames =
+        format!("{}/{} frames", recording.frame, recording.frame_count);
+    match recording.output {
+        Output::Ffmpeg(mut child) => {
+            // Closing stdin tells ffmpeg that there are no more frames.
+            drop(child.stdin.take());
+            match child.wait() {
+                Ok(status) if status.success() => {
+                    eprintln!("Recorded {frames} at {} FPS", recording.fps);
+                }
+                Ok(status) => eprintln!("ffmpeg failed: {status}"),
+                Err(e) => eprintln!("Failed to wait for ffmpeg: {e}"),
+            }
+        }
+        Output::Frames(dir) => {
+            eprintln!("Recorded {frames} to {}", dir.display());
+        }
+    }
+}
+
+/// Returns the name of the video file, without the extension, or frame
+/// directory of a recording into the view described by the `uniforms`.
+fn recording_name(uniforms: &Uniforms) -> String {
+    let [x, y] = uniforms.offset;
+    let zoom = uniforms.magnification();
+    let sign = if y >= 0.0 { "+" } else { "" };
+    format!("zoom_{x}{sign}{y}i_x{zoom}")
+}
+
+/// Returns the name of the frame directory of a color cycling animation of
+/// the view described by the `uniforms`.
+fn color_cycle_name(uniforms: &Uniforms) -> String {
+    let [x, y] = uniforms.offset;
+    let zoom = uniforms.magnification();
+    let sign = if y >= 0.0 { "+" } else { "" };
+    format!("colors_{x}{sign}{y}i_x{zoom}")
+}
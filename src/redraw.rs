@@ -0,0 +1,53 @@
+//! Redrawing on demand: the window is only redrawn when something changed
+//! since the last frame, e.g. after input, or while the view changes by
+//! itself, so that a still view doesn't keep the GPU busy. Nothing is redrawn
+//! while the window is unfocused, hidden or minimized.
+
+use crate::AppState;
+
+impl AppState {
+    /// Whether the window has to be redrawn, because something changed since
+    /// the last frame or the view is animating, and rendering isn't paused.
+    /// Once it resumes, the time it was paused for is skipped like the time
+    /// the window was idle for.
+    #[must_use]
+    pub fn needs_redraw(&self) -> bool {
+        !self.view.paused_render && (self.view.redraw || self.is_animating())
+    }
+
+    /// Pauses rendering while the window is unfocused, hidden or has no size,
+    /// and resumes it once none of them is the case anymore.
+    pub const fn update_paused_render(&mut self) {
+        let view = &mut self.view;
+        view.paused_render = !view.focused || view.occluded || view.zero_size;
+    }
+
+    /// Whether the view changes by itself, so that every frame differs from
+    /// the previous one. The dithering noise shifts only while this is the
+    /// case, since it changes too little to be worth redrawing for.
+    #[must_use]
+    fn is_animating(&self) -> bool {
+        let view = &self.view;
+        let (zoom_out, zoom_in) = view.triggers;
+        (self.uniforms().rotate_colors == 1.0 && !view.paused)
+            || view.movement_delta != (0.0, 0.0)
+            || view.zoom_delta != 0.0
+            || view.stick != (0.0, 0.0)
+            || zoom_out != zoom_in
+            || view.inertia.is_gliding()
+            || view.animation.is_some()
+            || view.morph.is_some()
+            || view.demo.is_some()
+            || view.recording.is_some()
+            // The overlay measures the frame times of continuous rendering.
+            || view.show_overlay
+            || view.title_outdated
+            || self.bench.is_some()
+            || self.renderer.is_refining()
+            || self.inside_stats.as_ref().is_some_and(|s| s.is_counting())
+            || self
+                .auto_max_iter
+                .as_ref()
+                .is_some_and(|a| a.is_measuring())
+    }
+}
@@ -0,0 +1,1328 @@
+//! The GPU side of the explorer: the [`Uniforms`] describing the view, the
+//! shader computing the fractal and the pipelines running it. Nothing here
+//! depends on the windowing, so the [`MandelbrotRenderer`] can draw into any
+//! texture, e.g. of another application.
+
+mod accumulate;
+mod blit;
+mod export;
+mod frame;
+mod histogram;
+mod progressive;
+
+use std::f32::consts::TAU;
+
+use wgpu::{
+    Adapter, AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+    BindingResource, BindingType, Buffer, BufferBindingType, BufferDescriptor,
+    BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder,
+    CommandEncoderDescriptor, Device, DeviceDescriptor, Error, ErrorFilter,
+    Extent3d, Features, FilterMode, FragmentState, LoadOp, MultisampleState,
+    Operations, PipelineCompilationOptions, PipelineLayout,
+    PipelineLayoutDescriptor, PrimitiveState, Queue, RenderPass,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPassTimestampWrites,
+    RenderPipeline, RenderPipelineDescriptor, RequestDeviceError, Sampler,
+    SamplerBindingType, SamplerDescriptor, ShaderModule,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState,
+    util::{DeviceExt as _, TextureDataOrder},
+};
+
+pub use export::{ExportError, MAX_SCREENSHOT_SCALE};
+pub use progressive::{DEFAULT_PASS_ITERS, same_orbits};
+
+use crate::renderer::{
+    accumulate::Accumulation,
+    blit::Blit,
+    histogram::Histogram,
+    progressive::{OrbitPipelines, Orbits, Progressive},
+};
+
+/// Represents the uniform buffer data. Matches the `struct Uniforms` in the
+/// shader.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+pub struct Uniforms {
+    /// Window width and height.
+    pub resolution: [f64; 2],
+    /// Time in seconds since startup.
+    pub time: f64,
+    /// Number of zoom-ins. The lower the number, the deeper the zoom. The
+    /// view spans `2 * exp(zooms / 10)` horizontally in the complex plane, see
+    /// [`Uniforms::magnification`] for the human-facing zoom factor.
+    pub zooms: f64,
+    /// Translation of the center of the coordinate system from 0+0i.
+    pub offset: [f64; 2],
+    /// The rounding error of [`Uniforms::offset`], which together with it
+    /// forms a double-double number. Only used past
+    /// [`Precision::deep_zooms`].
+    pub offset_lo: [f64; 2],
+    /// Current mouse position, normalized to the range [-1, 1].
+    pub mouse_position: [f64; 2],
+    /// Position of the rendered target's top-left pixel within the full image
+    /// of size [`Uniforms::resolution`]. Only non-zero when exporting in tiles.
+    pub tile_offset: [f64; 2],
+    /// The constant `c` of the Julia set.
+    pub julia_c: [f64; 2],
+    /// Whether we are currently rendering the Mandelbrot set (`1.0`) or the
+    /// Julia set (`0.0`). Values in between morph between the two.
+    pub is_mandelbrot: f32,
+    /// Whether should we rotate the colors (creates a trippy rainbow effect).
+    pub rotate_colors: f32,
+    /// Maximum number of iterations to perform.
+    pub max_iter: u32,
+    /// Index of the color palette, see `palette` in the shader.
+    pub palette: u32,
+    /// Number of samples per pixel of the render target, each sample computes
+    /// the fractal at its own position within the pixel.
+    pub sample_count: u32,
+    /// Index of the iterated formula, see `iterate` in the shader.
+    pub fractal_type: u32,
+    /// Exponent `d` of the Multibrot formula `z = z^d + c`. Its integer part,
+    /// at least 3, is the degree `n` of Newton's method for `z^n - 1`.
+    pub power: f32,
+    /// How the fractal is colored, by the escape time (`0`), by an orbit trap
+    /// (`1`) or by the stripe average (`2`).
+    pub coloring_mode: u32,
+    /// Shape of the orbit trap, see `trap_distance` in the shader.
+    pub trap_shape: u32,
+    /// Whether to anti-alias the boundary of the set using the estimated
+    /// distance to it.
+    pub distance_estimation: f32,
+    /// Iteration the orbits continue from in a step of the progressive
+    /// rendering, `0` starts them over. See `fg_progressive` in the shader.
+    pub iter_start: u32,
+    /// Iteration a step of the progressive rendering stops at.
+    pub iter_end: u32,
+    /// Whether to color the inside of the set by the period of the cycle the
+    /// orbits converge to.
+    pub interior_coloring: f32,
+    /// Radius of the circle outside of which an orbit counts as escaped.
+    /// Larger radii make the smooth coloring more accurate.
+    pub escape_radius: f32,
+    /// Position within each pixel the fractal is computed at by the current
+    /// sample of the accumulated anti-aliasing. See `fg_accumulate` in the
+    /// shader.
+    pub jitter: [f32; 2],
+    /// Number of samples accumulated before the current one, `0` starts the
+    /// accumulation over.
+    pub accumulated_samples: u32,
+    /// The paddings are public only so that the uniforms can be built with
+    /// the struct update syntax, e.g.
+    /// `Uniforms { zooms: 0.0, ..Uniforms::default() }`.
+    #[doc(hidden)]
+    pub _padding: [u32; 3],
+    /// Linear RGB color of the points inside the set, unless they are colored
+    /// by their period.
+    pub interior_color: [f32; 3],
+    /// Angle in radians the view is rotated by counterclockwise around its
+    /// center.
+    pub rotation: f32,
+    /// Whether to add noise to the output colors to hide the banding of
+    /// smooth gradients.
+    pub dithering: f32,
+    /// Factor the output colors are scaled by, `1.0` except on HDR targets,
+    /// where it makes the palettes brighter than the SDR white.
+    pub output_scale: f32,
+    /// Whether the shader encodes the output colors in sRGB itself, because
+    /// the target format doesn't.
+    pub encode_srgb: f32,
+    /// How the smooth iteration count is mapped to the position in the
+    /// palette, see `color_position` in the shader.
+    pub color_scale_mode: u32,
+    /// Phase in radians the palettes are shifted by, on top of the rotation
+    /// of the colors.
+    pub color_offset: f32,
+    /// Number of iterations the palettes repeat over with the linear color
+    /// scale, the nonlinear ones are stretched alike. Smaller counts show
+    /// more, narrower color bands.
+    pub band_count: f32,
+    /// Number of stripes around the origin the stripe average coloring
+    /// (`coloring_mode` `2`) averages over the orbits, see `stripe_term` in
+    /// the shader. Whole numbers keep the stripes continuous.
+    pub stripe_density: f32,
+    #[doc(hidden)]
+    pub _padding_end: [u32; 1],
+}
+
+const _: () = assert!(std::mem::size_of::<Uniforms>().is_multiple_of(16));
+
+/// Single-precision version of [`Uniforms`], used when the GPU doesn't
+/// support `f64` in shaders. Matches the `struct Uniforms` in the shader with
+/// every `f64` replaced by `f32`.
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct UniformsF32 {
+    resolution: [f32; 2],
+    time: f32,
+    zooms: f32,
+    offset: [f32; 2],
+    offset_lo: [f32; 2],
+    mouse_position: [f32; 2],
+    tile_offset: [f32; 2],
+    julia_c: [f32; 2],
+    is_mandelbrot: f32,
+    rotate_colors: f32,
+    max_iter: u32,
+    palette: u32,
+    sample_count: u32,
+    fractal_type: u32,
+    power: f32,
+    coloring_mode: u32,
+    trap_shape: u32,
+    distance_estimation: f32,
+    iter_start: u32,
+    iter_end: u32,
+    interior_coloring: f32,
+    escape_radius: f32,
+    jitter: [f32; 2],
+    accumulated_samples: u32,
+    _padding: [u32; 1],
+    interior_color: [f32; 3],
+    rotation: f32,
+    dithering: f32,
+    output_scale: f32,
+    encode_srgb: f32,
+    color_scale_mode: u32,
+    color_offset: f32,
+    band_count: f32,
+    stripe_density: f32,
+    _padding_end: [u32; 1],
+}
+
+const _: () = assert!(std::mem::size_of::<UniformsF32>().is_multiple_of(16));
+
+#[allow(clippy::cast_possible_truncation, reason = "intended")]
+impl From<&Uniforms> for UniformsF32 {
+    fn from(u: &Uniforms) -> Self {
+        let vec2 = |[x, y]: [f64; 2]| [x as f32, y as f32];
+        // The error of rounding the offset to `f32` goes into the low part.
+        let offset = vec2(u.offset);
+        let offset_lo = [0, 1].map(|i| {
+            ((u.offset[i] - f64::from(offset[i])) + u.offset_lo[i]) as f32
+        });
+        Self {
+            resolution: vec2(u.resolution),
+            time: u.time as f32,
+            zooms: u.zooms as f32,
+            offset,
+            offset_lo,
+            mouse_position: vec2(u.mouse_position),
+            tile_offset: vec2(u.tile_offset),
+            julia_c: vec2(u.julia_c),
+            is_mandelbrot: u.is_mandelbrot,
+            rotate_colors: u.rotate_colors,
+            max_iter: u.max_iter,
+            palette: u.palette,
+            sample_count: u.sample_count,
+            fractal_type: u.fractal_type,
+            power: u.power,
+            coloring_mode: u.coloring_mode,
+            trap_shape: u.trap_shape,
+            distance_estimation: u.distance_estimation,
+            iter_start: u.iter_start,
+            iter_end: u.iter_end,
+            interior_coloring: u.interior_coloring,
+            escape_radius: u.escape_radius,
+            jitter: u.jitter,
+            accumulated_samples: u.accumulated_samples,
+            _padding: [0; 1],
+            interior_color: u.interior_color,
+            rotation: u.rotation,
+            dithering: u.dithering,
+            output_scale: u.output_scale,
+            encode_srgb: u.encode_srgb,
+            color_scale_mode: u.color_scale_mode,
+            color_offset: u.color_offset,
+            band_count: u.band_count,
+            stripe_density: u.stripe_density,
+            _padding_end: [0; 1],
+        }
+    }
+}
+
+/// Floating point precision the fractal is computed with on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// `f64`, requires [`Features::SHADER_F64`].
+    Double,
+    /// `f32` fallback, allowing only much shallower zooms.
+    Single,
+}
+
+impl Precision {
+    /// Returns the lowest [`Uniforms::zooms`] value at which the view isn't yet
+    /// distorted by floating point errors, when computing in double-double
+    /// precision.
+    #[must_use]
+    pub const fn min_zooms(self) -> f64 {
+        2.0 * self.deep_zooms()
+    }
+
+    /// Returns the [`Uniforms::zooms`] below which the view would be distorted
+    /// by floating point errors, so the orbits are computed in double-double
+    /// precision, which is much slower.
+    #[must_use]
+    pub const fn deep_zooms(self) -> f64 {
+        match self {
+            Self::Double => -314.0,
+            Self::Single => -100.0,
+        }
+    }
+
+    /// Returns the source of the shader computing with this precision.
+    fn shader_source(self) -> String {
+        self.adapt_shader_source(concat!(
+            include_str!("shader.wgsl"),
+            include_str!("shader_dd.wgsl")
+        ))
+    }
+
+    /// Adapts the `source` of the shader, `shader.wgsl` followed by
+    /// `shader_dd.wgsl`, to compute with this precision, declaring the `real`
+    /// type it computes with and `DD_SPLIT`, the factor splitting the
+    /// mantissa of a `real` in half for the double-double arithmetic.
+    #[must_use]
+    pub fn adapt_shader_source(self, source: &str) -> String {
+        let (real, mantissa_digits) = match self {
+            Self::Double => ("f64", f64::MANTISSA_DIGITS),
+            Self::Single => ("f32", f32::MANTISSA_DIGITS),
+        };
+        // 2^27 + 1 for `f64`, 2^12 + 1 for `f32`.
+        let split = (1_u64 << mantissa_digits.div_ceil(2)) + 1;
+        format!(
+            "alias real = {real};\nconst DD_SPLIT: real = {split}.0;\n\n{source}"
+        )
+    }
+
+    /// Returns the size of the uniform buffer the shader expects.
+    const fn uniforms_size(self) -> usize {
+        match self {
+            Self::Double => std::mem::size_of::<Uniforms>(),
+            Self::Single => std::mem::size_of::<UniformsF32>(),
+        }
+    }
+}
+
+impl Uniforms {
+    /// Returns the magnification of the view, as shown in the window title
+    /// and given with `--zoom`: how many times larger the fractal appears than
+    /// in a view spanning [-1, 1] horizontally. It grows when zooming in, as
+    /// [`Uniforms::zooms`] decreases.
+    #[must_use]
+    pub fn magnification(&self) -> f64 {
+        (-self.zooms / 10.0).exp()
+    }
+
+    /// Returns the [`Uniforms::zooms`] of a view with the given
+    /// `magnification`, the inverse of [`Uniforms::magnification`].
+    #[must_use]
+    pub fn zooms_from_magnification(magnification: f64) -> f64 {
+        -10.0 * magnification.ln()
+    }
+
+    /// Rotates the vector `v` of the unrotated view by [`Uniforms::rotation`],
+    /// like `pixel_to_plane` in the shader.
+    #[must_use]
+    pub fn rotated(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let (sin, cos) = f64::from(self.rotation).sin_cos();
+        [x.mul_add(cos, -y * sin), x.mul_add(sin, y * cos)]
+    }
+
+    /// Translates the center of the coordinate system by the given `delta`,
+    /// keeping the rounding error in [`Uniforms::offset_lo`], so that the
+    /// offset doesn't get stuck when zoomed in deeper than `f64` allows.
+    pub fn translate(&mut self, delta: [f64; 2]) {
+        let offsets = self.offset.iter_mut().zip(&mut self.offset_lo);
+        for ((hi, lo), delta) in offsets.zip(delta) {
+            let (sum, error) = two_sum(*hi, delta);
+            (*hi, *lo) = two_sum(sum, error + *lo);
+        }
+    }
+
+    /// Rebases the view on its center: [`Uniforms::offset_lo`] is folded into
+    /// [`Uniforms::offset`], which becomes the `f64` nearest to the center.
+    /// What remains of the low part is kept if `keep_low`, otherwise dropped,
+    /// so that the pipeline reading only the offset renders the view centered
+    /// exactly on it.
+    pub fn rebase(&mut self, keep_low: bool) {
+        let offsets = self.offset.iter_mut().zip(&mut self.offset_lo);
+        for (hi, lo) in offsets {
+            (*hi, *lo) = two_sum(*hi, *lo);
+            if !keep_low {
+                *lo = 0.0;
+            }
+        }
+    }
+}
+
+/// Returns `a + b` and its rounding error, exactly.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let v = sum - a;
+    (sum, (a - (sum - v)) + (b - v))
+}
+
+impl Default for Uniforms {
+    fn default() -> Self {
+        Self {
+            resolution: Default::default(),
+            time: Default::default(),
+            zooms: 8.0,
+            offset: [(0.25 - 2.0) / 2.0, 0.0],
+            offset_lo: [0.0, 0.0],
+            mouse_position: [0.0, 0.0],
+            tile_offset: [0.0, 0.0],
+            julia_c: [0.0, 0.0],
+            // offset: [-1.999_491_453_530_413, 0.0],
+            is_mandelbrot: 1.0,
+            rotate_colors: 1.0,
+            max_iter: 1500,
+            palette: 0,
+            sample_count: 1,
+            fractal_type: 0,
+            power: 2.0,
+            coloring_mode: 0,
+            trap_shape: 0,
+            distance_estimation: 0.0,
+            iter_start: 0,
+            iter_end: 0,
+            interior_coloring: 0.0,
+            escape_radius: 32.0,
+            jitter: [0.5, 0.5],
+            accumulated_samples: 0,
+            _padding: [0; 3],
+            interior_color: [0.0; 3],
+            rotation: 0.0,
+            dithering: 0.0,
+            output_scale: 1.0,
+            encode_srgb: 0.0,
+            color_scale_mode: 0,
+            color_offset: 0.0,
+            // The palettes used to be scaled by 0.075 per iteration.
+            band_count: TAU / 0.075,
+            stripe_density: 5.0,
+            _padding_end: [0; 1],
+        }
+    }
+}
+
+/// Requests a device from the `adapter`, with `f64` support in shaders and
+/// timestamp queries if the adapter has them. Returns the [`Precision`] the
+/// fractal can be computed with, [`Precision::Single`] if the adapter doesn't
+/// support `f64`.
+pub async fn request_device(
+    adapter: &Adapter,
+) -> Result<(Device, Queue, Precision), RequestDeviceError> {
+    let precision = if adapter.features().contains(Features::SHADER_F64) {
+        Precision::Double
+    } else {
+        Precision::Single
+    };
+
+    let mut required_features = adapter.features()
+        & (Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | Features::TIMESTAMP_QUERY);
+    if precision == Precision::Double {
+        required_features |= Features::SHADER_F64;
+    }
+    let (device, queue) = adapter
+        .request_device(&DeviceDescriptor {
+            label: Some("Device"),
+            required_features,
+            ..Default::default()
+        })
+        .await?;
+
+    Ok((device, queue, precision))
+}
+
+/// Creates the shader computing the fractal with the given `precision`.
+pub fn create_shader(device: &Device, precision: Precision) -> ShaderModule {
+    device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("shader.wgsl"),
+        source: ShaderSource::Wgsl(precision.shader_source().into()),
+    })
+}
+
+/// Creates the layout of the bind group holding the [`Uniforms`] buffer and
+/// the [`Gradient`].
+pub fn create_uniforms_bind_group_layout(device: &Device) -> BindGroupLayout {
+    let visibility = ShaderStages::VERTEX_FRAGMENT | ShaderStages::COMPUTE;
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("Bind Group Layout"),
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::default(),
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility,
+                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Creates a [`Uniforms`] buffer of the size expected with the given
+/// `precision` and a bind group binding it along with the `gradient`.
+pub fn create_uniforms_buffer(
+    device: &Device,
+    layout: &BindGroupLayout,
+    precision: Precision,
+    gradient: &Gradient,
+    label: &str,
+) -> (Buffer, BindGroup) {
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some(label),
+        size: precision.uniforms_size() as u64,
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group =
+        create_uniforms_bind_group(device, layout, &buffer, gradient, label);
+    (buffer, bind_group)
+}
+
+/// Creates a bind group binding the [`Uniforms`] `buffer` and the `gradient`.
+fn create_uniforms_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    buffer: &Buffer,
+    gradient: &Gradient,
+    label: &str,
+) -> BindGroup {
+    device.create_bind_group(&BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(&gradient.view),
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::Sampler(&gradient.sampler),
+            },
+        ],
+    })
+}
+
+/// A gradient of colors loaded from an image, which the palette at index
+/// `GRADIENT` in the shader loops through.
+#[derive(Debug)]
+pub struct Gradient {
+    view: TextureView,
+    sampler: Sampler,
+}
+
+impl Gradient {
+    /// Creates a [`Gradient`] of the given sRGB `colors`, interpolated
+    /// between neighbors and from the last one back to the first. There has
+    /// to be at least one color, and at most as many as the `device` allows
+    /// in a texture row.
+    #[must_use]
+    pub fn new(device: &Device, queue: &Queue, colors: &[[u8; 4]]) -> Self {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "gradients are narrower than textures can be"
+        )]
+        let texture = device.create_texture_with_data(
+            queue,
+            &TextureDescriptor {
+                label: Some("Gradient Texture"),
+                size: Extent3d {
+                    width: colors.len() as u32,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                // Not an sRGB format, since the palettes are decoded after
+                // interpolating, see `palette` in the shader.
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(colors),
+        );
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Gradient Sampler"),
+            address_mode_u: AddressMode::Repeat,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        Self {
+            view: texture.create_view(&TextureViewDescriptor::default()),
+            sampler,
+        }
+    }
+}
+
+/// Creates the layout of the pipelines rendering the fractal.
+pub fn create_render_pipeline_layout(
+    device: &Device,
+    bind_group_layout: &BindGroupLayout,
+) -> PipelineLayout {
+    device.create_pipeline_layout(&PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    })
+}
+
+/// Writes the given `uniforms` to the GPU `buffer` in the layout expected with
+/// the given `precision`.
+pub fn write_uniforms(
+    queue: &Queue,
+    precision: Precision,
+    buffer: &Buffer,
+    uniforms: &Uniforms,
+) {
+    match precision {
+        Precision::Double => {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[*uniforms]));
+        }
+        Precision::Single => queue.write_buffer(
+            buffer,
+            0,
+            bytemuck::cast_slice(&[UniformsF32::from(uniforms)]),
+        ),
+    }
+}
+
+/// Creates the pipeline rendering the fractal into targets of the given
+/// `format` with `sample_count` samples per pixel.
+pub fn create_render_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    format: TextureFormat,
+    sample_count: u32,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some("fg_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            targets: &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        layout: Some(layout),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Creates a pipeline drawing a fullscreen triangle with the given fragment
+/// `entry_point` of the `shader` into the `targets`.
+pub fn create_fragment_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    entry_point: &str,
+    targets: &[Option<ColorTargetState>],
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some(entry_point),
+        vertex: VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: shader,
+            entry_point: Some(entry_point),
+            compilation_options: PipelineCompilationOptions::default(),
+            targets,
+        }),
+        layout: Some(layout),
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Creates the pipelines rendering the fractal with the `shader` into targets
+/// of the given `format`: with one sample per pixel, and in double-double
+/// precision.
+fn create_pipelines(
+    device: &Device,
+    layout: &PipelineLayout,
+    shader: &ShaderModule,
+    format: TextureFormat,
+) -> (RenderPipeline, RenderPipeline) {
+    let pipeline = create_render_pipeline(device, layout, shader, format, 1);
+    let deep_pipeline = create_fragment_pipeline(
+        device,
+        layout,
+        shader,
+        "fg_deep",
+        &[Some(ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        })],
+    );
+    (pipeline, deep_pipeline)
+}
+
+/// Resources for rendering with multisample anti-aliasing.
+#[derive(Debug)]
+struct Msaa {
+    /// Number of samples per pixel.
+    sample_count: u32,
+    /// Pipeline rendering with [`Msaa::sample_count`] samples per pixel.
+    pipeline: RenderPipeline,
+    /// The multisampled texture that is resolved to the target.
+    texture: TextureView,
+}
+
+/// Resources for rendering with supersample anti-aliasing, or letterboxed.
+#[derive(Debug)]
+struct Supersample {
+    /// The texture of [`MandelbrotRenderer::render_size`] the fractal is
+    /// rendered into.
+    texture: TextureView,
+    /// Bind group downsampling [`Supersample::texture`] into the viewport of
+    /// the target.
+    bind_group: BindGroup,
+}
+
+/// Renders the fractal described by its [`Uniforms`] into textures of a
+/// single format, with the rendering modes enabled on it.
+#[derive(Debug)]
+pub struct MandelbrotRenderer {
+    device: Device,
+    queue: Queue,
+    precision: Precision,
+    /// Format of the targets the fractal is rendered into.
+    format: TextureFormat,
+    shader: ShaderModule,
+    /// Layout of the bind groups holding the [`Uniforms`] buffers.
+    bind_group_layout: BindGroupLayout,
+    gradient: Gradient,
+    pipeline_layout: PipelineLayout,
+    pipeline: RenderPipeline,
+    /// Pipeline rendering the fractal in double-double precision, past
+    /// [`Precision::deep_zooms`].
+    deep_pipeline: RenderPipeline,
+    buffer: Buffer,
+    bind_group: BindGroup,
+    /// The uniforms last written to the `buffer`.
+    uniforms: Uniforms,
+    /// Size of the targets the frames are drawn into.
+    target_size: (u32, u32),
+    /// The `[x, y, width, height]` part of the targets the fractal is drawn
+    /// into, see [`MandelbrotRenderer::set_layout`].
+    viewport: [u32; 4],
+    /// Factor the fractal is supersampled by.
+    render_scale: u32,
+    /// Color the targets are cleared to around the viewport.
+    background: Color,
+    /// Multisample anti-aliasing resources, if it's enabled.
+    msaa: Option<Msaa>,
+    /// Supersample anti-aliasing resources, if the render scale is greater
+    /// than `1` or the fractal is letterboxed.
+    supersample: Option<Supersample>,
+    blit: Blit,
+    orbit_pipelines: OrbitPipelines,
+    /// Orbits of [`MandelbrotRenderer::render_size`] the frames are rendered
+    /// with in slices of iterations, allocated once they're needed, see
+    /// [`MandelbrotRenderer::is_sliced`].
+    slices: Option<[Orbits; 2]>,
+    /// Progressive rendering resources, if it's enabled.
+    progressive: Option<Progressive>,
+    /// Accumulated anti-aliasing resources, if it's enabled.
+    accumulation: Option<Accumulation>,
+    /// Histogram equalized coloring resources, if it's enabled.
+    histogram: Option<Histogram>,
+    /// Number of iterations each step of the progressive rendering continues
+    /// the orbits for, and each render pass of the views rendered in slices.
+    pass_iters: u32,
+}
+
+impl MandelbrotRenderer {
+    /// Creates a new [`MandelbrotRenderer`] computing the fractal with the
+    /// given `precision`, which the `device` has to support, and rendering it
+    /// into targets of the given `format`, without any of the rendering modes
+    /// until they're enabled.
+    #[must_use]
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        precision: Precision,
+        format: TextureFormat,
+    ) -> Self {
+        let shader = create_shader(device, precision);
+        let bind_group_layout = create_uniforms_bind_group_layout(device);
+        // A placeholder until one is loaded, see
+        // [`MandelbrotRenderer::set_gradient`].
+        let gradient = Gradient::new(device, queue, &[[0, 0, 0, 255]]);
+        let (buffer, bind_group) = create_uniforms_buffer(
+            device,
+            &bind_group_layout,
+            precision,
+            &gradient,
+            "Uniforms",
+        );
+        let pipeline_layout =
+            create_render_pipeline_layout(device, &bind_group_layout);
+        let (pipeline, deep_pipeline) =
+            create_pipelines(device, &pipeline_layout, &shader, format);
+        let orbit_pipelines =
+            OrbitPipelines::new(device, &shader, &bind_group_layout, format);
+
+        let renderer = Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            precision,
+            format,
+            shader,
+            bind_group_layout,
+            gradient,
+            pipeline_layout,
+            pipeline,
+            deep_pipeline,
+            buffer,
+            bind_group,
+            uniforms: Uniforms::default(),
+            target_size: (1, 1),
+            viewport: [0, 0, 1, 1],
+            render_scale: 1,
+            background: Color::BLACK,
+            msaa: None,
+            supersample: None,
+            blit: Blit::new(device, format),
+            orbit_pipelines,
+            slices: None,
+            progressive: None,
+            accumulation: None,
+            histogram: None,
+            pass_iters: DEFAULT_PASS_ITERS,
+        };
+        renderer.write_uniforms();
+        renderer
+    }
+
+    /// Returns the precision the fractal is computed with.
+    #[must_use]
+    pub const fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Replaces the shader with one compiled from its `source`, see
+    /// [`Precision::adapt_shader_source`], and recreates the pipelines with
+    /// it, including those of the rendering modes. Keeps the old shader if the
+    /// new one fails to compile, returning the error.
+    ///
+    /// Pipelines created from the [`MandelbrotRenderer::shader`] elsewhere
+    /// have to be recreated as well.
+    pub async fn reload_shader(&mut self, source: &str) -> Result<(), Error> {
+        self.device.push_error_scope(ErrorFilter::Validation);
+        let shader = self.device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            source: ShaderSource::Wgsl(
+                self.precision.adapt_shader_source(source).into(),
+            ),
+        });
+        let (pipeline, deep_pipeline) = create_pipelines(
+            &self.device,
+            &self.pipeline_layout,
+            &shader,
+            self.format,
+        );
+        let orbit_pipelines = OrbitPipelines::new(
+            &self.device,
+            &shader,
+            &self.bind_group_layout,
+            self.format,
+        );
+        if let Some(error) = self.device.pop_error_scope().await {
+            return Err(error);
+        }
+
+        self.shader = shader;
+        self.pipeline = pipeline;
+        self.deep_pipeline = deep_pipeline;
+        self.orbit_pipelines = orbit_pipelines;
+        // Reallocated for the new pipelines by the next uniforms.
+        self.slices = None;
+        self.set_msaa(self.sample_count());
+        if self.progressive.take().is_some() {
+            self.set_progressive(true);
+        }
+        if self.accumulation.take().is_some() {
+            self.set_accumulation(true);
+        }
+        if self.histogram.take().is_some() {
+            self.set_histogram(true);
+        }
+        Ok(())
+    }
+
+    /// Returns the shader computing the fractal, for creating other pipelines
+    /// with its entry points.
+    #[must_use]
+    pub const fn shader(&self) -> &ShaderModule {
+        &self.shader
+    }
+
+    /// Returns the layout of the bind groups holding the [`Uniforms`].
+    #[must_use]
+    pub const fn bind_group_layout(&self) -> &BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    /// Returns the gradient the palette at index `GRADIENT` in the shader
+    /// loops through, for binding it along with other [`Uniforms`] buffers.
+    #[must_use]
+    pub const fn gradient(&self) -> &Gradient {
+        &self.gradient
+    }
+
+    /// Replaces the gradient with one of the given sRGB `colors`, see
+    /// [`Gradient::new`].
+    ///
+    /// Bind groups created with the [`MandelbrotRenderer::gradient`]
+    /// elsewhere keep binding the old one.
+    pub fn set_gradient(&mut self, colors: &[[u8; 4]]) {
+        self.gradient = Gradient::new(&self.device, &self.queue, colors);
+        self.bind_group = create_uniforms_bind_group(
+            &self.device,
+            &self.bind_group_layout,
+            &self.buffer,
+            &self.gradient,
+            "Uniforms",
+        );
+    }
+
+    /// Returns the layout of the pipelines rendering the fractal.
+    #[must_use]
+    pub const fn pipeline_layout(&self) -> &PipelineLayout {
+        &self.pipeline_layout
+    }
+
+    /// Returns the pipeline rendering the fractal with one sample per pixel,
+    /// in double-double precision if the view [`MandelbrotRenderer::is_deep`].
+    #[must_use]
+    pub fn pipeline(&self) -> &RenderPipeline {
+        if self.is_deep() {
+            &self.deep_pipeline
+        } else {
+            &self.pipeline
+        }
+    }
+
+    /// Whether the view is zoomed in past [`Precision::deep_zooms`], so that
+    /// the fractal is computed in double-double precision.
+    #[must_use]
+    pub fn is_deep(&self) -> bool {
+        self.uniforms.zooms < self.precision.deep_zooms()
+    }
+
+    /// Whether the view described by the `uniforms` is rendered in slices of
+    /// [`MandelbrotRenderer::pass_iters`] iterations, see
+    /// [`MandelbrotRenderer::draw_sliced`]. Views computed in double-double
+    /// precision are rendered in a single pass, since the orbits can't be
+    /// stored at that precision.
+    fn is_sliced(&self, uniforms: &Uniforms) -> bool {
+        uniforms.max_iter > self.pass_iters
+            && uniforms.zooms >= self.precision.deep_zooms()
+    }
+
+    /// Returns the buffer holding the [`Uniforms`].
+    #[must_use]
+    pub const fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the bind group binding the [`Uniforms`] buffer.
+    #[must_use]
+    pub const fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// Returns the uniforms the fractal is rendered with.
+    #[must_use]
+    pub const fn uniforms(&self) -> &Uniforms {
+        &self.uniforms
+    }
+
+    /// Sets the uniforms the next frame is rendered with, advancing the
+    /// progressive rendering and the accumulated anti-aliasing, so it's called
+    /// once per frame. The [`Uniforms::sample_count`] is set to that of the
+    /// MSAA and the fields of the rendering modes are set by them.
+    pub fn set_uniforms(&mut self, uniforms: &Uniforms) {
+        self.uniforms = Uniforms {
+            sample_count: self.sample_count(),
+            ..*uniforms
+        };
+        if let Some(progressive) = &mut self.progressive {
+            progressive.advance(&mut self.uniforms, self.pass_iters);
+        }
+        if let Some(accumulation) = &mut self.accumulation {
+            accumulation.advance(&mut self.uniforms);
+        }
+        // Only the frames without the other rendering modes are sliced.
+        let plain = self.msaa.is_none()
+            && self.histogram.is_none()
+            && self.progressive.is_none()
+            && self.accumulation.is_none();
+        if plain && self.slices.is_none() && self.is_sliced(&self.uniforms) {
+            self.slices = Some(
+                self.orbit_pipelines
+                    .create_orbits(&self.device, self.render_size()),
+            );
+        }
+        self.write_uniforms();
+    }
+
+    /// Sets the size of the targets the fractal is rendered into, filling
+    /// them entirely, see [`MandelbrotRenderer::set_layout`].
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let render_scale = self.render_scale;
+        self.set_layout((width, height), [0, 0, width, height], render_scale);
+    }
+
+    /// Renders a frame into the given texture `view` and submits the work to
+    /// the queue, see [`MandelbrotRenderer::draw`].
+    pub fn render_to(&self, view: &TextureView) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        self.draw(&mut encoder, view);
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Renders the fractal into the `[x, y, width, height]` rectangle
+    /// `viewport` of the given texture `view`, e.g. one sampled by another
+    /// scene, keeping the rest of it, and submits the work to the queue. The
+    /// resolution and [`Uniforms::tile_offset`] are set to match the
+    /// `viewport`.
+    pub fn render_to_viewport(
+        &mut self,
+        view: &TextureView,
+        viewport: [u32; 4],
+    ) {
+        let [x, y, width, height] = viewport;
+        self.uniforms.resolution = [width.into(), height.into()];
+        // Fragment positions are relative to the whole texture, not the
+        // viewport.
+        self.uniforms.tile_offset = [-f64::from(x), -f64::from(y)];
+        self.write_uniforms();
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor::default());
+        self.draw_viewport(&mut encoder, view, viewport);
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Records a render pass drawing the fractal into the `[x, y, width,
+    /// height]` rectangle `viewport` of the given texture `view`, keeping the
+    /// rest of it. The [`Uniforms`] have to match the `viewport`, see
+    /// [`MandelbrotRenderer::render_to_viewport`].
+    #[allow(clippy::cast_precision_loss, reason = "texture sizes are small")]
+    pub fn draw_viewport(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        viewport: [u32; 4],
+    ) {
+        let [x, y, width, height] = viewport;
+        let mut render_pass = self.begin_render_pass(
+            encoder,
+            view,
+            Operations {
+                load: LoadOp::Load,
+                store: StoreOp::Store,
+            },
+        );
+        render_pass.set_viewport(
+            x as f32,
+            y as f32,
+            width as f32,
+            height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(self.pipeline());
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Records a render pass drawing the fractal into the texture `view`, in
+    /// double-double precision if `deep`, or resolving it from the
+    /// multisampled texture of `msaa` if it's given. The render pass is timed
+    /// by the `timestamp_writes`, if any.
+    fn draw_single_pass(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        deep: bool,
+        msaa: Option<&Msaa>,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        let (pipeline, attachment) = match msaa {
+            Some(msaa) => (
+                &msaa.pipeline,
+                RenderPassColorAttachment {
+                    view: &msaa.texture,
+                    resolve_target: Some(view),
+                    ops: Operations {
+                        load: LoadOp::Clear(self.background),
+                        store: StoreOp::Discard,
+                    },
+                    depth_slice: None,
+                },
+            ),
+            None => (
+                if deep {
+                    &self.deep_pipeline
+                } else {
+                    &self.pipeline
+                },
+                RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(self.background),
+                        store: StoreOp::Store,
+                    },
+                    depth_slice: None,
+                },
+            ),
+        };
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(attachment)],
+                timestamp_writes,
+                ..Default::default()
+            });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Records the render passes drawing the fractal described by the
+    /// `uniforms` into the texture `view`, computing the `orbits` in slices of
+    /// [`MandelbrotRenderer::pass_iters`] iterations, so that no render pass
+    /// runs into the time limits some GPU drivers reset the device after.
+    ///
+    /// Each slice needs its own uniforms, so every slice but the last is
+    /// submitted right away. The last one and the pass coloring the pixels by
+    /// the orbits are recorded into the `encoder`, with the uniforms of the
+    /// last slice left in the buffer.
+    fn draw_sliced(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        orbits: &[Orbits; 2],
+        uniforms: &Uniforms,
+    ) {
+        let mut slice = Uniforms {
+            iter_start: 0,
+            ..*uniforms
+        };
+        let mut current = 0;
+        loop {
+            slice.iter_end = slice
+                .iter_start
+                .saturating_add(self.pass_iters)
+                .min(uniforms.max_iter);
+            write_uniforms(&self.queue, self.precision, &self.buffer, &slice);
+            if slice.iter_end == uniforms.max_iter {
+                break;
+            }
+            let mut slice_encoder =
+                self.device
+                    .create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("Slice Encoder"),
+                    });
+            self.orbit_pipelines.step(
+                &mut slice_encoder,
+                &self.bind_group,
+                orbits,
+                current,
+            );
+            self.queue.submit([slice_encoder.finish()]);
+            slice.iter_start = slice.iter_end;
+            current = 1 - current;
+        }
+        self.orbit_pipelines
+            .step(encoder, &self.bind_group, orbits, current);
+        self.orbit_pipelines.resolve(
+            encoder,
+            &self.bind_group,
+            &orbits[current],
+            view,
+        );
+    }
+
+    /// Begins a render pass into the given texture `view` with the given
+    /// `ops`.
+    fn begin_render_pass<'a>(
+        &self,
+        encoder: &'a mut CommandEncoder,
+        view: &TextureView,
+        ops: Operations<Color>,
+    ) -> RenderPass<'a> {
+        encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops,
+                depth_slice: None,
+            })],
+            ..Default::default()
+        })
+    }
+
+    /// Writes the [`MandelbrotRenderer::uniforms`] to the GPU buffer.
+    fn write_uniforms(&self) {
+        write_uniforms(
+            &self.queue,
+            self.precision,
+            &self.buffer,
+            &self.uniforms,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magnification_round_trips_through_zooms() {
+        // From the deepest zoom with `f64` to far out.
+        for zooms in (-628..=42).step_by(10).map(f64::from) {
+            let magnification = Uniforms {
+                zooms,
+                ..Uniforms::default()
+            }
+            .magnification();
+            let round_trip = Uniforms {
+                zooms: Uniforms::zooms_from_magnification(magnification),
+                ..Uniforms::default()
+            }
+            .magnification();
+            let error = (round_trip - magnification).abs() / magnification;
+            assert!(error < 1e-12, "{magnification} became {round_trip}");
+        }
+    }
+
+    #[test]
+    fn sub_pixel_translations_accumulate_at_deep_zooms() {
+        let mut uniforms = Uniforms {
+            zooms: Precision::Double.deep_zooms(),
+            offset: [-0.75, 0.1],
+            ..Uniforms::default()
+        };
+        let start = uniforms.offset;
+        // A tenth of a pixel of a 1000 pixel wide view, less than half the
+        // spacing of `f64`s around the offset.
+        let pixel = 2.0 * (uniforms.zooms / 10.0).exp() / 1000.0;
+        let delta = [0.1 * pixel, -0.1 * pixel];
+        for (delta, start) in delta.iter().zip(start) {
+            assert!(delta.abs() < start.abs() * f64::EPSILON / 2.0);
+        }
+        let steps = 1000;
+        for _ in 0..steps {
+            uniforms.translate(delta);
+        }
+        for axis in 0..2 {
+            // Exact, since the offset moved by much less than itself.
+            let moved = (uniforms.offset[axis] - start[axis])
+                + uniforms.offset_lo[axis];
+            let expected = f64::from(steps) * delta[axis];
+            let error = (moved - expected).abs() / expected.abs();
+            assert!(error < 1e-9, "moved {moved} instead of {expected}");
+        }
+    }
+
+    #[test]
+    fn rebasing_moves_the_offset_to_the_nearest_f64() {
+        // The spacing of `f64`s in [0.5, 1).
+        let ulp = f64::EPSILON / 2.0;
+        let mut uniforms = Uniforms {
+            offset: [-0.75, 0.6],
+            offset_lo: [2.25 * ulp, -0.75 * ulp],
+            ..Uniforms::default()
+        };
+        uniforms.rebase(true);
+        assert_eq!(uniforms.offset, [-0.75 + 2.0 * ulp, 0.6 - ulp]);
+        assert_eq!(uniforms.offset_lo, [0.25 * ulp, 0.25 * ulp]);
+        uniforms.rebase(false);
+        assert_eq!(uniforms.offset, [-0.75 + 2.0 * ulp, 0.6 - ulp]);
+        assert_eq!(uniforms.offset_lo, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn shader_splits_the_mantissa_of_each_precision_in_half() {
+        for (precision, declarations) in [
+            (
+                Precision::Double,
+                "alias real = f64;\nconst DD_SPLIT: real = 134217729.0;",
+            ),
+            (
+                Precision::Single,
+                "alias real = f32;\nconst DD_SPLIT: real = 4097.0;",
+            ),
+        ] {
+            let source = precision.adapt_shader_source("");
+            assert!(source.starts_with(declarations), "{source}");
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Accumulated anti-aliasing: each frame computes the fractal at a different
+//! position within the pixels, following a low-discrepancy sequence, and
+//! averages it with the previous frames, so that a still view gets smooth
+//! edges over a few frames without rendering at a higher resolution.
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource,
+    BindingType, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, Operations, PipelineLayoutDescriptor, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, ShaderModule, ShaderStages,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::renderer::{
+    MandelbrotRenderer, Uniforms, create_fragment_pipeline, progressive,
+};
+
+/// Number of samples per pixel after which the accumulation stops.
+const MAX_SAMPLES: u32 = 64;
+
+/// Format of the textures the sums of the samples are stored in, as the bits
+/// of `f32`s, since float formats aren't renderable on every GPU.
+const SUM_FORMAT: TextureFormat = TextureFormat::Rgba32Uint;
+
+/// Resources for accumulating the samples.
+#[derive(Debug)]
+pub struct Accumulation {
+    /// Pipeline adding a sample to the sums read from one [`Sums`] and
+    /// writing them to the other.
+    accumulate_pipeline: RenderPipeline,
+    /// Pipeline coloring the pixels by the average of their samples.
+    average_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    /// The sums of the previous and the current frame.
+    sums: [Sums; 2],
+    /// Index of the [`Sums`] the current frame writes to.
+    current: usize,
+    /// Whether a sample is added this frame, `false` once there are
+    /// [`MAX_SAMPLES`].
+    stepping: bool,
+    /// Number of samples accumulated, including the current frame's.
+    samples: u32,
+    /// The uniforms the samples are computed with.
+    uniforms: Uniforms,
+}
+
+/// Texture of [`MandelbrotRenderer::render_size`] holding the sum of the
+/// samples of every pixel.
+#[derive(Debug)]
+struct Sums {
+    view: TextureView,
+    /// Bind group for reading the sums.
+    bind_group: BindGroup,
+}
+
+impl Accumulation {
+    /// Creates a new [`Accumulation`] rendering the fractal of `size` into
+    /// targets of the given `format`. The pipelines use the `shader` with the
+    /// [`Uniforms`] bound by a bind group of the `uniforms_layout`.
+    pub fn new(
+        device: &Device,
+        shader: &ShaderModule,
+        uniforms_layout: &BindGroupLayout,
+        format: TextureFormat,
+        size: (u32, u32),
+    ) -> Self {
+        let bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Sums Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Uint,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            });
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Accumulation Pipeline Layout"),
+            bind_group_layouts: &[uniforms_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let accumulate_pipeline = create_fragment_pipeline(
+            device,
+            &layout,
+            shader,
+            "fg_accumulate",
+            &[Some(ColorTargetState {
+                format: SUM_FORMAT,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        );
+        let average_pipeline = create_fragment_pipeline(
+            device,
+            &layout,
+            shader,
+            "fg_average",
+            &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        );
+
+        let sums = [0, 1].map(|_| Sums::new(device, &bind_group_layout, size));
+
+        Self {
+            accumulate_pipeline,
+            average_pipeline,
+            bind_group_layout,
+            sums,
+            current: 0,
+            stepping: false,
+            samples: 0,
+            uniforms: Uniforms::default(),
+        }
+    }
+
+    /// Whether a sample is added this frame.
+    pub const fn is_stepping(&self) -> bool {
+        self.stepping
+    }
+
+    /// Recreates the sum textures with the new `size` and starts the
+    /// accumulation over.
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        self.sums =
+            [0, 1].map(|_| Sums::new(device, &self.bind_group_layout, size));
+        self.samples = 0;
+    }
+
+    /// Sets the position of this frame's sample in the [`Uniforms`], starting
+    /// the accumulation over if the image changed since the previous frame.
+    pub fn advance(&mut self, uniforms: &mut Uniforms) {
+        if !same_image(&self.uniforms, uniforms) {
+            self.samples = 0;
+            self.uniforms = *uniforms;
+        }
+
+        self.stepping = self.samples < MAX_SAMPLES;
+        if self.stepping {
+            self.current = 1 - self.current;
+            // The first sample is at the center of the pixel.
+            let index = self.samples;
+            uniforms.jitter =
+                [halton(index, 2), halton(index, 3)].map(|x| (x + 0.5).fract());
+            self.samples += 1;
+        }
+        uniforms.accumulated_samples = self.samples - 1;
+    }
+
+    /// Records the render passes adding this frame's sample, if the
+    /// accumulation isn't finished yet, and drawing the averaged fractal into
+    /// the `target`. The `uniforms` bind group binds the [`Uniforms`] prepared
+    /// by [`Accumulation::advance`].
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        uniforms: &BindGroup,
+        target: &TextureView,
+    ) {
+        let current = &self.sums[self.current];
+        if self.stepping {
+            let previous = &self.sums[1 - self.current];
+            let mut render_pass =
+                encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Accumulate Render Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: &current.view,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                        depth_slice: None,
+                    })],
+                    ..Default::default()
+                });
+            render_pass.set_pipeline(&self.accumulate_pipeline);
+            render_pass.set_bind_group(0, uniforms, &[]);
+            render_pass.set_bind_group(1, &previous.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Average Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+        render_pass.set_pipeline(&self.average_pipeline);
+        render_pass.set_bind_group(0, uniforms, &[]);
+        render_pass.set_bind_group(1, &current.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Sums {
+    /// Creates the sum texture of the given `size` and a bind group of the
+    /// `layout` for reading it.
+    fn new(
+        device: &Device,
+        layout: &BindGroupLayout,
+        size: (u32, u32),
+    ) -> Self {
+        let view = device
+            .create_texture(&TextureDescriptor {
+                label: Some("Sums Texture"),
+                size: Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: SUM_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT
+                    | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Sums Bind Group"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 7,
+                resource: BindingResource::TextureView(&view),
+            }],
+        });
+        Self { view, bind_group }
+    }
+}
+
+impl MandelbrotRenderer {
+    /// Enables or disables accumulated anti-aliasing. While it's enabled, MSAA
+    /// is not used.
+    pub fn set_accumulation(&mut self, enabled: bool) {
+        if !enabled {
+            self.accumulation = None;
+        } else if self.accumulation.is_none() {
+            self.accumulation = Some(Accumulation::new(
+                &self.device,
+                &self.shader,
+                &self.bind_group_layout,
+                self.format,
+                self.render_size(),
+            ));
+        }
+    }
+
+    /// Returns the number of samples accumulated per pixel, `None` if
+    /// accumulated anti-aliasing is disabled.
+    #[must_use]
+    pub fn accumulated_samples(&self) -> Option<u32> {
+        self.accumulation
+            .as_ref()
+            .map(|accumulation| accumulation.samples)
+    }
+}
+
+/// Whether the images rendered with the uniforms `a` and `b` are the same, so
+/// that their samples can be averaged.
+#[allow(clippy::float_cmp, reason = "any change restarts the accumulation")]
+fn same_image(a: &Uniforms, b: &Uniforms) -> bool {
+    // The time only matters while the colors rotate.
+    let time = |u: &Uniforms| (u.rotate_colors == 1.0).then_some(u.time);
+    progressive::same_orbits(a, b)
+        && a.rotate_colors == b.rotate_colors
+        && time(a) == time(b)
+        && a.palette == b.palette
+        && a.color_offset == b.color_offset
+        && a.color_scale_mode == b.color_scale_mode
+        && a.band_count == b.band_count
+        && a.distance_estimation == b.distance_estimation
+        && a.interior_color == b.interior_color
+}
+
+/// Returns the `index`th element of the Halton sequence of the given `base`,
+/// which fills `[0, 1)` evenly.
+#[allow(clippy::cast_precision_loss, reason = "small numbers")]
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
@@ -0,0 +1,47 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+struct Params {
+    // How many times larger the source texture is than the target.
+    scale: u32,
+    // Position of the top-left pixel of the viewport drawn into.
+    origin: vec2u,
+}
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+    let vert = array(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+    return vec4<f32>(vert[vertex_index] * 4 - 1, 0.0, 1.0);
+}
+
+@fragment
+fn fg_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4f {
+    let size = vec2f(textureDimensions(source));
+    let scale = params.scale;
+    let corner = (floor(pos.xy) - vec2f(params.origin)) * f32(scale);
+
+    if scale == 1u {
+        return textureSampleLevel(source, source_sampler, (corner + 0.5) / size, 0.0);
+    }
+
+    // A bilinear tap in the middle of 2x2 texels averages them, so
+    // (scale / 2)^2 taps average the whole scale x scale block.
+    let taps = scale / 2u;
+    var color = vec4f(0.0);
+    for (var y = 0u; y < taps; y++) {
+        for (var x = 0u; x < taps; x++) {
+            let p = corner + vec2f(f32(2u * x + 1u), f32(2u * y + 1u));
+            color += textureSampleLevel(source, source_sampler, p / size, 0.0);
+        }
+    }
+    return color / f32(taps * taps);
+}
@@ -0,0 +1,332 @@
+//! Rendering the fractal into offscreen textures and reading them back as
+//! images.
+
+use std::sync::mpsc;
+
+use image::{GenericImage as _, RgbaImage};
+use wgpu::{
+    BufferAsyncError, BufferDescriptor, BufferUsages,
+    COPY_BYTES_PER_ROW_ALIGNMENT, CommandEncoder, CommandEncoderDescriptor,
+    Device, Extent3d, MapMode, Origin3d, PollError, PollType, Queue,
+    TexelCopyBufferInfo, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture,
+    TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor,
+};
+
+use crate::{
+    color::{f16_to_f32, linear_to_srgb8, srgb_to_linear},
+    renderer::{MandelbrotRenderer, Uniforms, write_uniforms},
+};
+
+/// Largest supersampling factor of the images, see
+/// [`MandelbrotRenderer::render_image`].
+pub const MAX_SCREENSHOT_SCALE: u32 = 8;
+
+/// Upper bound for the side of a single tile, to keep the readback buffers at
+/// a reasonable size even on devices with huge texture limits.
+const MAX_TILE_SIZE: u32 = 4096;
+
+/// Upper bound for the side of the tiles of images rendered in slices of
+/// iterations, whose orbit textures take 64 bytes per pixel.
+const MAX_SLICED_TILE_SIZE: u32 = 1024;
+
+/// Errors of rendering the fractal into an image.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("Failed to map buffer: {0}")]
+    BufferAsyncError(#[from] BufferAsyncError),
+
+    #[error("Buffer mapping was cancelled")]
+    BufferMapCancelled,
+
+    #[error("Failed to wait for the device: {0}")]
+    PollError(#[from] PollError),
+
+    #[error("Exporting texture format {0:?} is not supported")]
+    UnsupportedExportFormat(TextureFormat),
+
+    #[error("Failed to stitch the tiles together: {0}")]
+    ImageError(#[from] image::ImageError),
+}
+
+impl MandelbrotRenderer {
+    /// Renders the view described by the `uniforms` into an image of the
+    /// given `size`, without the anti-aliasing and the other modes of the
+    /// frames. The images are SDR, so the colors aren't scaled for HDR, see
+    /// [`Uniforms::output_scale`].
+    ///
+    /// Each tile is rendered in a single pass, or in slices of
+    /// [`MandelbrotRenderer::set_pass_iters`] iterations if the view has more,
+    /// like the frames.
+    ///
+    /// Images larger than a single texture can hold are rendered in tiles,
+    /// each drawing its own sub-region of the full image, and stitched
+    /// together on the CPU. With a `scale` above 1, up to
+    /// [`MAX_SCREENSHOT_SCALE`], the tiles are rendered that many times larger
+    /// and averaged down, like the supersampling of the frames, which keeps
+    /// the interactive rendering fast.
+    pub fn render_image(
+        &self,
+        uniforms: &Uniforms,
+        size: (u32, u32),
+        scale: u32,
+    ) -> Result<RgbaImage, ExportError> {
+        let deep = uniforms.zooms < self.precision.deep_zooms();
+        let scale = scale.clamp(1, MAX_SCREENSHOT_SCALE);
+        // Every tile fits into the orbits of the first one.
+        let slices = self.is_sliced(uniforms).then(|| {
+            let side = |length: u32| (length * scale).min(MAX_SLICED_TILE_SIZE);
+            self.orbit_pipelines
+                .create_orbits(&self.device, (side(size.0), side(size.1)))
+        });
+        let max_tile_size = if slices.is_some() {
+            MAX_SLICED_TILE_SIZE
+        } else {
+            MAX_TILE_SIZE
+        };
+        let image = self.render_tiles(
+            uniforms,
+            size,
+            scale,
+            max_tile_size,
+            |encoder, view, uniforms| {
+                let uniforms = &Uniforms {
+                    output_scale: 1.0,
+                    ..*uniforms
+                };
+                if let Some(orbits) = &slices {
+                    self.draw_sliced(encoder, view, orbits, uniforms);
+                    return;
+                }
+                write_uniforms(
+                    &self.queue,
+                    self.precision,
+                    &self.buffer,
+                    uniforms,
+                );
+                self.draw_single_pass(encoder, view, deep, None, None);
+            },
+        );
+
+        // Restore the uniforms of the frames.
+        self.write_uniforms();
+
+        image
+    }
+
+    /// Renders the view described by the `uniforms` into an image of the
+    /// given `size`, supersampled `scale` times, in tiles at most
+    /// `max_tile_size` wide, see [`MandelbrotRenderer::render_image`]. For
+    /// each tile, `draw` is called to write its uniforms and record the render
+    /// passes into the texture view.
+    fn render_tiles(
+        &self,
+        uniforms: &Uniforms,
+        (width, height): (u32, u32),
+        scale: u32,
+        max_tile_size: u32,
+        mut draw: impl FnMut(&mut CommandEncoder, &TextureView, &Uniforms),
+    ) -> Result<RgbaImage, ExportError> {
+        let (device, queue) = (&self.device, &self.queue);
+        let tile_size =
+            device.limits().max_texture_dimension_2d.min(max_tile_size) / scale;
+        let mut image = RgbaImage::new(width, height);
+
+        for tile_y in (0..height).step_by(tile_size as usize) {
+            for tile_x in (0..width).step_by(tile_size as usize) {
+                let tile_width = tile_size.min(width - tile_x);
+                let tile_height = tile_size.min(height - tile_y);
+
+                let uniforms = Uniforms {
+                    resolution: [
+                        (width * scale).into(),
+                        (height * scale).into(),
+                    ],
+                    tile_offset: [
+                        (tile_x * scale).into(),
+                        (tile_y * scale).into(),
+                    ],
+                    sample_count: 1,
+                    ..*uniforms
+                };
+
+                let texture = create_export_texture(
+                    device,
+                    self.format,
+                    tile_width * scale,
+                    tile_height * scale,
+                );
+                let view =
+                    texture.create_view(&TextureViewDescriptor::default());
+                let mut encoder = device.create_command_encoder(
+                    &CommandEncoderDescriptor::default(),
+                );
+                draw(&mut encoder, &view, &uniforms);
+                queue.submit([encoder.finish()]);
+
+                let mut pixels = read_texture(device, queue, &texture)?;
+                if scale > 1 {
+                    pixels =
+                        downsample(&pixels, tile_width, tile_height, scale);
+                }
+                let tile = RgbaImage::from_raw(tile_width, tile_height, pixels)
+                    .expect("tile size matches the texture size");
+                image.copy_from(&tile, tile_x, tile_y)?;
+            }
+        }
+
+        Ok(image)
+    }
+}
+
+/// Averages each `scale` x `scale` block of the sRGB `pixels` of an image
+/// `scale` times larger than `width` x `height` into one pixel. The colors are
+/// averaged in linear space, like the samples of the other anti-aliasing.
+fn downsample(pixels: &[u8], width: u32, height: u32, scale: u32) -> Vec<u8> {
+    #[allow(clippy::cast_possible_truncation, reason = "colors fit in f32")]
+    let to_linear: Vec<f32> = (0..=u8::MAX)
+        .map(|c| srgb_to_linear(f64::from(c) / 255.0) as f32)
+        .collect();
+    let (width, height, scale) =
+        (width as usize, height as usize, scale as usize);
+    let row = width * scale * 4;
+    #[allow(clippy::cast_precision_loss, reason = "the scale is small")]
+    let samples = (scale * scale) as f32;
+
+    let mut output = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0.0; 3];
+            for sy in 0..scale {
+                let start = (y * scale + sy) * row + x * scale * 4;
+                for pixel in pixels[start..start + scale * 4].chunks_exact(4) {
+                    for (sum, &c) in sum.iter_mut().zip(pixel) {
+                        *sum += to_linear[usize::from(c)];
+                    }
+                }
+            }
+            // The fractal is opaque.
+            output.extend(sum.map(|c| linear_to_srgb8(c / samples)));
+            output.push(u8::MAX);
+        }
+    }
+    output
+}
+
+/// Creates a texture of the given `format` the fractal can be rendered into
+/// and copied from.
+fn create_export_texture(
+    device: &Device,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+) -> Texture {
+    device.create_texture(&TextureDescriptor {
+        label: Some("Export Texture"),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Copies the contents of the `texture` back to the CPU, returning tightly
+/// packed RGBA8 pixels.
+fn read_texture(
+    device: &Device,
+    queue: &Queue,
+    texture: &Texture,
+) -> Result<Vec<u8>, ExportError> {
+    let format = texture.format();
+    let swap_red_blue = match format {
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Rgba16Float => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        _ => return Err(ExportError::UnsupportedExportFormat(format)),
+    };
+    let is_hdr = format == TextureFormat::Rgba16Float;
+
+    let (width, height) = (texture.width(), texture.height());
+    let unpadded_row = width * if is_hdr { 8 } else { 4 };
+    // Each row copied into a buffer must be aligned to 256 bytes.
+    let padded_row =
+        unpadded_row.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("Export Buffer"),
+        size: u64::from(padded_row) * u64::from(height),
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        device.create_command_encoder(&CommandEncoderDescriptor::default());
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    queue.submit([encoder.finish()]);
+
+    let (sender, receiver) = mpsc::channel();
+    buffer.map_async(MapMode::Read, .., move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(PollType::Wait)?;
+    receiver
+        .recv()
+        .map_err(|_| ExportError::BufferMapCancelled)??;
+
+    let mapped = buffer.get_mapped_range(..);
+    let mut pixels = Vec::with_capacity((unpadded_row * height) as usize);
+    for row in mapped.chunks_exact(padded_row as usize) {
+        pixels.extend_from_slice(&row[..unpadded_row as usize]);
+    }
+    drop(mapped);
+    buffer.unmap();
+
+    // The HDR colors are linear, but the image is in sRGB.
+    if is_hdr {
+        pixels = pixels
+            .chunks_exact(8)
+            .flat_map(|pixel| {
+                let channel = |i: usize| {
+                    let bits =
+                        u16::from_le_bytes([pixel[2 * i], pixel[2 * i + 1]]);
+                    linear_to_srgb8(f16_to_f32(bits))
+                };
+                // The fractal is opaque.
+                [channel(0), channel(1), channel(2), u8::MAX]
+            })
+            .collect();
+    }
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    Ok(pixels)
+}
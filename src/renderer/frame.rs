@@ -0,0 +1,262 @@
+//! Drawing the frames: the fractal is rendered by one of the rendering modes or
+//! anti-aliased with MSAA, and drawn into the target directly, or supersampled
+//! and letterboxed into its viewport.
+
+use wgpu::{
+    Color, CommandEncoder, Extent3d, RenderPassTimestampWrites,
+    TextureDescriptor, TextureDimension, TextureUsages, TextureView,
+    TextureViewDescriptor,
+};
+
+use crate::renderer::{
+    MandelbrotRenderer, Msaa, Supersample, create_render_pipeline,
+};
+
+impl MandelbrotRenderer {
+    /// Sets the `size` of the targets the frames are drawn into, the `[x, y,
+    /// width, height]` `viewport` of them the fractal is drawn into, with the
+    /// rest cleared to the background, and the `render_scale` the fractal is
+    /// supersampled by.
+    ///
+    /// The textures of the rendering modes are recreated for the new
+    /// [`MandelbrotRenderer::render_size`], and histogram equalization is
+    /// disabled if it doesn't fit anymore, see
+    /// [`MandelbrotRenderer::histogram_fits`].
+    pub fn set_layout(
+        &mut self,
+        size: (u32, u32),
+        viewport: [u32; 4],
+        render_scale: u32,
+    ) {
+        self.target_size = size;
+        self.viewport = viewport;
+        self.render_scale = render_scale;
+        let (width, height) = self.render_size();
+        self.uniforms.resolution = [width.into(), height.into()];
+        self.write_uniforms();
+
+        if let Some(msaa) = &self.msaa {
+            let texture = self.create_msaa_texture(msaa.sample_count);
+            if let Some(msaa) = &mut self.msaa {
+                msaa.texture = texture;
+            }
+        }
+        // The letterboxed fractal is drawn into its viewport by the blit.
+        let offscreen = render_scale > 1 || viewport != [0, 0, size.0, size.1];
+        self.supersample = offscreen.then(|| {
+            let texture = self.create_supersample_texture();
+            let [x, y, ..] = viewport;
+            let bind_group = self.blit.create_bind_group(
+                &self.device,
+                &texture,
+                render_scale,
+                [x, y],
+            );
+            Supersample {
+                texture,
+                bind_group,
+            }
+        });
+        let size = self.render_size();
+        if self.slices.is_some() {
+            self.slices =
+                Some(self.orbit_pipelines.create_orbits(&self.device, size));
+        }
+        if let Some(progressive) = &mut self.progressive {
+            progressive.resize(&self.device, &self.orbit_pipelines, size);
+        }
+        if let Some(accumulation) = &mut self.accumulation {
+            accumulation.resize(&self.device, size);
+        }
+        if !self.histogram_fits() {
+            self.histogram = None;
+        }
+        if let Some(histogram) = &mut self.histogram {
+            histogram.resize(&self.device, size);
+        }
+    }
+
+    /// Returns the size the fractal is rendered at: the size of the viewport
+    /// multiplied by the render scale, see [`MandelbrotRenderer::set_layout`].
+    #[must_use]
+    pub const fn render_size(&self) -> (u32, u32) {
+        let [_, _, width, height] = self.viewport;
+        (width * self.render_scale, height * self.render_scale)
+    }
+
+    /// Sets the `color` the targets are cleared to around the viewport, in the
+    /// encoding of the colors the shader outputs.
+    pub const fn set_background(&mut self, color: Color) {
+        self.background = color;
+    }
+
+    /// Enables multisample anti-aliasing with `sample_count` samples per
+    /// pixel, which the format of the targets has to support, or disables it
+    /// if the count is 1. It's only used while none of the other rendering
+    /// modes are enabled.
+    pub fn set_msaa(&mut self, sample_count: u32) {
+        if sample_count == 1 {
+            self.msaa = None;
+            return;
+        }
+        self.msaa = Some(Msaa {
+            sample_count,
+            pipeline: create_render_pipeline(
+                &self.device,
+                &self.pipeline_layout,
+                &self.shader,
+                self.format,
+                sample_count,
+            ),
+            texture: self.create_msaa_texture(sample_count),
+        });
+    }
+
+    /// Returns the number of samples per pixel of the multisample
+    /// anti-aliasing, `1` if it's disabled.
+    #[must_use]
+    pub fn sample_count(&self) -> u32 {
+        self.msaa.as_ref().map_or(1, |msaa| msaa.sample_count)
+    }
+
+    /// Whether the progressive rendering or the accumulated anti-aliasing
+    /// refine the image in the next frames, so that they have to be drawn
+    /// even if the view doesn't change.
+    #[must_use]
+    pub fn is_refining(&self) -> bool {
+        self.progressive.as_ref().is_some_and(|p| p.is_stepping())
+            || self.accumulation.as_ref().is_some_and(|a| a.is_stepping())
+    }
+
+    /// Records the render passes drawing a frame into the texture `view` of
+    /// the size set with [`MandelbrotRenderer::set_layout`], with the enabled
+    /// rendering modes.
+    ///
+    /// Without any of them, views with more iterations than
+    /// [`MandelbrotRenderer::set_pass_iters`] are rendered in slices of that
+    /// many iterations, all but the last of which are submitted to the queue
+    /// right away, before the passes recorded into the `encoder`.
+    pub fn draw(&self, encoder: &mut CommandEncoder, view: &TextureView) {
+        self.draw_frame(encoder, view, false, None);
+    }
+
+    /// Records the render passes drawing a frame like
+    /// [`MandelbrotRenderer::draw`], but renders the fractal in a single
+    /// render pass timed by the `timestamp_writes`, if any, anti-aliased with
+    /// MSAA at most, for benchmarks.
+    pub fn draw_timed(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        self.draw_frame(encoder, view, true, timestamp_writes);
+    }
+
+    /// Records the render passes drawing a frame into the texture `view`, see
+    /// [`MandelbrotRenderer::draw`] and [`MandelbrotRenderer::draw_timed`].
+    fn draw_frame(
+        &self,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        timed: bool,
+        timestamp_writes: Option<RenderPassTimestampWrites<'_>>,
+    ) {
+        let target = self
+            .supersample
+            .as_ref()
+            .map_or(view, |supersample| &supersample.texture);
+        // The other rendering modes don't support double-double precision.
+        if self.is_deep() {
+            self.draw_single_pass(
+                encoder,
+                target,
+                true,
+                None,
+                timestamp_writes,
+            );
+        } else if timed {
+            let msaa = self.msaa.as_ref();
+            self.draw_single_pass(
+                encoder,
+                target,
+                false,
+                msaa,
+                timestamp_writes,
+            );
+        } else if let Some(histogram) = &self.histogram {
+            histogram.draw(encoder, &self.bind_group, target);
+        } else if let Some(progressive) = &self.progressive {
+            progressive.draw(
+                encoder,
+                &self.orbit_pipelines,
+                &self.bind_group,
+                target,
+            );
+        } else if let Some(accumulation) = &self.accumulation {
+            accumulation.draw(encoder, &self.bind_group, target);
+        } else if let Some(orbits) = &self.slices
+            && self.msaa.is_none()
+            && self.is_sliced(&self.uniforms)
+        {
+            self.draw_sliced(encoder, target, orbits, &self.uniforms);
+        } else {
+            let msaa = self.msaa.as_ref();
+            self.draw_single_pass(encoder, target, false, msaa, None);
+        }
+        if let Some(supersample) = &self.supersample {
+            self.blit.draw(
+                encoder,
+                &supersample.bind_group,
+                view,
+                self.viewport,
+                self.background,
+            );
+        }
+    }
+
+    /// Creates a texture of [`MandelbrotRenderer::render_size`] to render the
+    /// fractal into before downsampling it into the viewport of the target.
+    fn create_supersample_texture(&self) -> TextureView {
+        let (width, height) = self.render_size();
+        self.device
+            .create_texture(&TextureDescriptor {
+                label: Some("Supersample Texture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: self.format,
+                usage: TextureUsages::RENDER_ATTACHMENT
+                    | TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default())
+    }
+
+    /// Creates a multisampled texture of [`MandelbrotRenderer::render_size`]
+    /// to render into.
+    fn create_msaa_texture(&self, sample_count: u32) -> TextureView {
+        let (width, height) = self.render_size();
+        self.device
+            .create_texture(&TextureDescriptor {
+                label: Some("MSAA Texture"),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: TextureDimension::D2,
+                format: self.format,
+                usage: TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default())
+    }
+}
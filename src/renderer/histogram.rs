@@ -0,0 +1,299 @@
+//! Histogram equalized coloring: a compute pass computes the iteration count
+//! of every pixel and builds their histogram, a second one sums it up into the
+//! cumulative distribution, and a render pass colors each pixel by the fraction
+//! of pixels escaping faster than it. This spreads the palette evenly over the
+//! image, however the iteration counts are distributed.
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
+    BufferBindingType, BufferDescriptor, BufferUsages, ColorTargetState,
+    ColorWrites, CommandEncoder, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, Operations, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, RenderPassColorAttachment, RenderPassDescriptor,
+    RenderPipeline, ShaderModule, ShaderStages, TextureFormat, TextureView,
+};
+
+use crate::renderer::{MandelbrotRenderer, create_fragment_pipeline};
+
+/// Number of bins of the iteration count histogram, matches the
+/// `HISTOGRAM_BINS` in the shader.
+const HISTOGRAM_BINS: u64 = 4096;
+
+/// Size of the workgroups of the `cs_iterate` compute shader.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Resources for the histogram equalized coloring.
+#[derive(Debug)]
+pub struct Histogram {
+    /// Pipeline computing the iteration counts and the histogram.
+    iterate_pipeline: ComputePipeline,
+    /// Pipeline computing the cumulative distribution from the histogram.
+    cdf_pipeline: ComputePipeline,
+    /// Pipeline coloring the pixels by the cumulative distribution.
+    equalize_pipeline: RenderPipeline,
+    compute_layout: BindGroupLayout,
+    render_layout: BindGroupLayout,
+    histogram: Buffer,
+    cdf: Buffer,
+    /// Size of the image the iteration counts are computed for.
+    size: (u32, u32),
+    /// Bind groups of the buffers, the one with the iteration counts is
+    /// recreated with the size.
+    compute_bind_group: BindGroup,
+    render_bind_group: BindGroup,
+}
+
+impl Histogram {
+    /// Creates a new [`Histogram`] coloring an image of `size` into targets of
+    /// the given `format`. The pipelines use the `shader` with the
+    /// [`Uniforms`](crate::Uniforms) bound by a bind group of the
+    /// `uniforms_layout`.
+    pub fn new(
+        device: &Device,
+        shader: &ShaderModule,
+        uniforms_layout: &BindGroupLayout,
+        format: TextureFormat,
+        size: (u32, u32),
+    ) -> Self {
+        let storage_entry =
+            |binding, visibility, read_only| BindGroupLayoutEntry {
+                binding,
+                visibility,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            };
+        let compute_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Histogram Compute Bind Group Layout"),
+                entries: &[
+                    storage_entry(2, ShaderStages::COMPUTE, false),
+                    storage_entry(3, ShaderStages::COMPUTE, false),
+                    storage_entry(4, ShaderStages::COMPUTE, false),
+                ],
+            });
+        let render_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Histogram Render Bind Group Layout"),
+                entries: &[
+                    storage_entry(5, ShaderStages::FRAGMENT, true),
+                    storage_entry(6, ShaderStages::FRAGMENT, true),
+                ],
+            });
+
+        let compute_pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Histogram Compute Pipeline Layout"),
+                bind_group_layouts: &[uniforms_layout, &compute_layout],
+                push_constant_ranges: &[],
+            });
+        let create_compute_pipeline = |entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&compute_pipeline_layout),
+                module: shader,
+                entry_point: Some(entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+        let iterate_pipeline = create_compute_pipeline("cs_iterate");
+        let cdf_pipeline = create_compute_pipeline("cs_cdf");
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Histogram Render Pipeline Layout"),
+                bind_group_layouts: &[uniforms_layout, &render_layout],
+                push_constant_ranges: &[],
+            });
+        let equalize_pipeline = create_fragment_pipeline(
+            device,
+            &render_pipeline_layout,
+            shader,
+            "fg_equalize",
+            &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        );
+
+        let histogram = device.create_buffer(&BufferDescriptor {
+            label: Some("Histogram Buffer"),
+            size: HISTOGRAM_BINS * 4,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let cdf = device.create_buffer(&BufferDescriptor {
+            label: Some("CDF Buffer"),
+            size: (HISTOGRAM_BINS + 1) * 4,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let (compute_bind_group, render_bind_group) = create_bind_groups(
+            device,
+            (&compute_layout, &render_layout),
+            (&histogram, &cdf),
+            size,
+        );
+
+        Self {
+            iterate_pipeline,
+            cdf_pipeline,
+            equalize_pipeline,
+            compute_layout,
+            render_layout,
+            histogram,
+            cdf,
+            size,
+            compute_bind_group,
+            render_bind_group,
+        }
+    }
+
+    /// Recreates the buffer of the iteration counts for the new `size`.
+    pub fn resize(&mut self, device: &Device, size: (u32, u32)) {
+        self.size = size;
+        (self.compute_bind_group, self.render_bind_group) = create_bind_groups(
+            device,
+            (&self.compute_layout, &self.render_layout),
+            (&self.histogram, &self.cdf),
+            size,
+        );
+    }
+
+    /// Records the compute passes building the histogram and the render pass
+    /// drawing the equalized fractal into the `target`. The `uniforms` bind
+    /// group binds the [`Uniforms`](crate::Uniforms).
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        uniforms: &BindGroup,
+        target: &TextureView,
+    ) {
+        encoder.clear_buffer(&self.histogram, 0, None);
+        {
+            let mut compute_pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("Histogram Compute Pass"),
+                    timestamp_writes: None,
+                });
+            compute_pass.set_bind_group(0, uniforms, &[]);
+            compute_pass.set_bind_group(1, &self.compute_bind_group, &[]);
+            compute_pass.set_pipeline(&self.iterate_pipeline);
+            let (width, height) = self.size;
+            compute_pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+            compute_pass.set_pipeline(&self.cdf_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Histogram Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+        render_pass.set_pipeline(&self.equalize_pipeline);
+        render_pass.set_bind_group(0, uniforms, &[]);
+        render_pass.set_bind_group(1, &self.render_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl MandelbrotRenderer {
+    /// Enables or disables the histogram equalized coloring. While it's
+    /// enabled, neither progressive rendering nor MSAA are used. It stays
+    /// disabled if it doesn't fit, see [`MandelbrotRenderer::histogram_fits`].
+    pub fn set_histogram(&mut self, enabled: bool) {
+        if !enabled || !self.histogram_fits() {
+            self.histogram = None;
+        } else if self.histogram.is_none() {
+            self.histogram = Some(Histogram::new(
+                &self.device,
+                &self.shader,
+                &self.bind_group_layout,
+                self.format,
+                self.render_size(),
+            ));
+        }
+    }
+
+    /// Whether the histogram equalized coloring is enabled.
+    #[must_use]
+    pub const fn has_histogram(&self) -> bool {
+        self.histogram.is_some()
+    }
+
+    /// Whether the iteration counts of every pixel of the
+    /// [`MandelbrotRenderer::render_size`] fit into a storage buffer.
+    #[must_use]
+    pub fn histogram_fits(&self) -> bool {
+        let (width, height) = self.render_size();
+        let limit = self.device.limits().max_storage_buffer_binding_size;
+        u64::from(width) * u64::from(height) * 4 <= u64::from(limit)
+    }
+}
+
+/// Creates the buffer of the iteration counts of an image of the given `size`
+/// and the bind groups of the `layouts` for the compute and render passes,
+/// binding it with the `histogram` and `cdf` buffers.
+fn create_bind_groups(
+    device: &Device,
+    (compute_layout, render_layout): (&BindGroupLayout, &BindGroupLayout),
+    (histogram, cdf): (&Buffer, &Buffer),
+    (width, height): (u32, u32),
+) -> (BindGroup, BindGroup) {
+    let iterations = device.create_buffer(&BufferDescriptor {
+        label: Some("Iterations Buffer"),
+        size: u64::from(width) * u64::from(height) * 4,
+        usage: BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    });
+    let compute_bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("Histogram Compute Bind Group"),
+        layout: compute_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 2,
+                resource: iterations.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 3,
+                resource: histogram.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: cdf.as_entire_binding(),
+            },
+        ],
+    });
+    let render_bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("Histogram Render Bind Group"),
+        layout: render_layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 5,
+                resource: iterations.as_entire_binding(),
+            },
+            BindGroupEntry {
+                binding: 6,
+                resource: cdf.as_entire_binding(),
+            },
+        ],
+    });
+    (compute_bind_group, render_bind_group)
+}
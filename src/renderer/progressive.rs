@@ -0,0 +1,391 @@
+//! Progressive rendering: the orbit of every pixel is kept in textures between
+//! the frames and continued for a few iterations each frame, so that a
+//! low-iteration image shows up right after the view changes and the details
+//! fill in over the following frames.
+//!
+//! The same textures let views with more iterations than
+//! [`MandelbrotRenderer::set_pass_iters`] be rendered in slices of that many
+//! iterations within a single frame, each submitted on its own, so that
+//! iteration counts in the tens of thousands don't run into the time limits
+//! some GPU drivers reset the device after.
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource,
+    BindingType, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, Operations, PipelineLayoutDescriptor, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, ShaderModule, ShaderStages,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::renderer::{MandelbrotRenderer, Uniforms, create_fragment_pipeline};
+
+/// Format of the textures the orbits are stored in, matches the
+/// `struct StoredOrbit` in the shader.
+const ORBIT_FORMAT: TextureFormat = TextureFormat::Rgba32Uint;
+
+/// Number of iterations each step of the progressive rendering continues the
+/// orbits for by default, see [`MandelbrotRenderer::set_pass_iters`].
+pub const DEFAULT_PASS_ITERS: u32 = 128;
+
+/// Pipelines continuing the orbits stored in [`Orbits`] and coloring the
+/// pixels by them, for the progressive rendering and for rendering in slices
+/// of iterations.
+#[derive(Debug)]
+pub struct OrbitPipelines {
+    /// Pipeline continuing the orbits read from one [`Orbits`] and writing
+    /// them to the other.
+    step_pipeline: RenderPipeline,
+    /// Pipeline coloring the pixels by their orbits.
+    resolve_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+}
+
+/// Resources for rendering progressively.
+#[derive(Debug)]
+pub struct Progressive {
+    /// The orbits of the previous and the current step.
+    orbits: [Orbits; 2],
+    /// Index of the [`Orbits`] the current step writes to.
+    current: usize,
+    /// Whether the orbits are continued this frame, `false` once they reached
+    /// [`Uniforms::max_iter`].
+    stepping: bool,
+    /// Number of iterations the orbits are computed for.
+    iters: u32,
+    /// The uniforms the orbits are computed with, `None` if they have to be
+    /// started over regardless, e.g. because the textures were recreated.
+    uniforms: Option<Uniforms>,
+}
+
+/// Textures holding the orbit of every pixel, usually of
+/// [`MandelbrotRenderer::render_size`].
+#[derive(Debug)]
+pub struct Orbits {
+    z: TextureView,
+    state: TextureView,
+    /// Bind group for reading the orbits.
+    bind_group: BindGroup,
+}
+
+impl OrbitPipelines {
+    /// Creates the [`OrbitPipelines`] drawing into targets of the given
+    /// `format`. The pipelines use the `shader` with the [`Uniforms`] bound by
+    /// a bind group of the `uniforms_layout`.
+    pub fn new(
+        device: &Device,
+        shader: &ShaderModule,
+        uniforms_layout: &BindGroupLayout,
+        format: TextureFormat,
+    ) -> Self {
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Uint,
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Orbits Bind Group Layout"),
+                entries: &[texture_entry(0), texture_entry(1)],
+            });
+
+        let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Progressive Pipeline Layout"),
+            bind_group_layouts: &[uniforms_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let orbit_target = Some(ColorTargetState {
+            format: ORBIT_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        });
+        let step_pipeline = create_fragment_pipeline(
+            device,
+            &layout,
+            shader,
+            "fg_progressive",
+            &[orbit_target.clone(), orbit_target],
+        );
+        let resolve_pipeline = create_fragment_pipeline(
+            device,
+            &layout,
+            shader,
+            "fg_resolve",
+            &[Some(ColorTargetState {
+                format,
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+        );
+
+        Self {
+            step_pipeline,
+            resolve_pipeline,
+            bind_group_layout,
+        }
+    }
+
+    /// Creates the two [`Orbits`] of the given `size` the steps alternate
+    /// between.
+    pub fn create_orbits(
+        &self,
+        device: &Device,
+        size: (u32, u32),
+    ) -> [Orbits; 2] {
+        [0, 1].map(|_| Orbits::new(device, &self.bind_group_layout, size))
+    }
+
+    /// Records a render pass continuing the orbits read from the other of the
+    /// `orbits` and writing them to the `current` one, over the iteration
+    /// range of the [`Uniforms`] bound by the `uniforms` bind group.
+    pub fn step(
+        &self,
+        encoder: &mut CommandEncoder,
+        uniforms: &BindGroup,
+        orbits: &[Orbits; 2],
+        current: usize,
+    ) {
+        let previous = &orbits[1 - current];
+        let current = &orbits[current];
+        let attachment = |view| {
+            Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations::default(),
+                depth_slice: None,
+            })
+        };
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Progressive Step Render Pass"),
+                color_attachments: &[
+                    attachment(&current.z),
+                    attachment(&current.state),
+                ],
+                ..Default::default()
+            });
+        render_pass.set_pipeline(&self.step_pipeline);
+        render_pass.set_bind_group(0, uniforms, &[]);
+        render_pass.set_bind_group(1, &previous.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Records a render pass drawing the fractal colored by the `orbits` into
+    /// the `target`, with the [`Uniforms`] bound by the `uniforms` bind group.
+    pub fn resolve(
+        &self,
+        encoder: &mut CommandEncoder,
+        uniforms: &BindGroup,
+        orbits: &Orbits,
+        target: &TextureView,
+    ) {
+        let mut render_pass =
+            encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Progressive Resolve Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                    depth_slice: None,
+                })],
+                ..Default::default()
+            });
+        render_pass.set_pipeline(&self.resolve_pipeline);
+        render_pass.set_bind_group(0, uniforms, &[]);
+        render_pass.set_bind_group(1, &orbits.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Progressive {
+    /// Creates a new [`Progressive`] rendering the fractal of `size` with the
+    /// `pipelines`.
+    pub fn new(
+        device: &Device,
+        pipelines: &OrbitPipelines,
+        size: (u32, u32),
+    ) -> Self {
+        Self {
+            orbits: pipelines.create_orbits(device, size),
+            current: 0,
+            stepping: false,
+            iters: 0,
+            uniforms: None,
+        }
+    }
+
+    /// Whether the orbits are continued this frame.
+    pub const fn is_stepping(&self) -> bool {
+        self.stepping
+    }
+
+    /// Recreates the orbit textures with the new `size`, starting the orbits
+    /// over.
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        pipelines: &OrbitPipelines,
+        size: (u32, u32),
+    ) {
+        self.orbits = pipelines.create_orbits(device, size);
+        self.uniforms = None;
+    }
+
+    /// Sets the iteration range of this frame's step of the progressive
+    /// rendering in the [`Uniforms`], continuing the orbits for `pass_iters`
+    /// iterations, or starting them over if the view changed since the
+    /// previous step.
+    pub fn advance(&mut self, uniforms: &mut Uniforms, pass_iters: u32) {
+        if self
+            .uniforms
+            .is_none_or(|previous| !same_orbits(&previous, uniforms))
+        {
+            self.iters = 0;
+            self.uniforms = Some(*uniforms);
+        }
+
+        uniforms.iter_start = self.iters;
+        uniforms.iter_end =
+            self.iters.saturating_add(pass_iters).min(uniforms.max_iter);
+        self.stepping = uniforms.iter_start < uniforms.iter_end;
+        if self.stepping {
+            self.current = 1 - self.current;
+            self.iters = uniforms.iter_end;
+        }
+    }
+
+    /// Records the render passes continuing the orbits with the `pipelines`,
+    /// if they aren't finished yet, and drawing the fractal colored by them
+    /// into the `target`. The `uniforms` bind group binds the [`Uniforms`]
+    /// prepared by [`Progressive::advance`].
+    pub fn draw(
+        &self,
+        encoder: &mut CommandEncoder,
+        pipelines: &OrbitPipelines,
+        uniforms: &BindGroup,
+        target: &TextureView,
+    ) {
+        if self.stepping {
+            pipelines.step(encoder, uniforms, &self.orbits, self.current);
+        }
+        pipelines.resolve(
+            encoder,
+            uniforms,
+            &self.orbits[self.current],
+            target,
+        );
+    }
+}
+
+impl Orbits {
+    /// Creates the orbit textures of the given `size` and a bind group of the
+    /// `layout` for reading them.
+    fn new(
+        device: &Device,
+        layout: &BindGroupLayout,
+        size: (u32, u32),
+    ) -> Self {
+        let create_texture = |label| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size.0,
+                        height: size.1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: ORBIT_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT
+                        | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        let z = create_texture("Orbit Z Texture");
+        let state = create_texture("Orbit State Texture");
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Orbits Bind Group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&z),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&state),
+                },
+            ],
+        });
+        Self {
+            z,
+            state,
+            bind_group,
+        }
+    }
+}
+
+impl MandelbrotRenderer {
+    /// Enables or disables progressive rendering. While it's enabled, MSAA is
+    /// not used.
+    pub fn set_progressive(&mut self, enabled: bool) {
+        if !enabled {
+            self.progressive = None;
+        } else if self.progressive.is_none() {
+            self.progressive = Some(Progressive::new(
+                &self.device,
+                &self.orbit_pipelines,
+                self.render_size(),
+            ));
+        }
+    }
+
+    /// Returns the number of iterations the orbits of the progressive
+    /// rendering are computed for, `None` if it's disabled.
+    #[must_use]
+    pub fn progress(&self) -> Option<u32> {
+        self.progressive
+            .as_ref()
+            .map(|progressive| progressive.iters)
+    }
+
+    /// Sets the number of iterations each step of the progressive rendering
+    /// continues the orbits for, and that views with more iterations are
+    /// rendered in slices of.
+    pub fn set_pass_iters(&mut self, iters: u32) {
+        self.pass_iters = iters.max(1);
+    }
+}
+
+/// Whether the orbits computed with the uniforms `a` and `b` are the same,
+/// i.e. the uniforms only differ in how the orbits are colored.
+#[allow(clippy::float_cmp, reason = "any change restarts the orbits")]
+pub fn same_orbits(a: &Uniforms, b: &Uniforms) -> bool {
+    // The Julia constant only matters for the Julia set.
+    let julia_c = |u: &Uniforms| (u.is_mandelbrot != 1.0).then_some(u.julia_c);
+    a.resolution == b.resolution
+        && a.zooms == b.zooms
+        && a.offset == b.offset
+        && a.offset_lo == b.offset_lo
+        && a.rotation == b.rotation
+        && a.tile_offset == b.tile_offset
+        && a.is_mandelbrot == b.is_mandelbrot
+        && julia_c(a) == julia_c(b)
+        && a.max_iter == b.max_iter
+        && a.fractal_type == b.fractal_type
+        && a.power == b.power
+        && a.coloring_mode == b.coloring_mode
+        && a.trap_shape == b.trap_shape
+        && a.interior_coloring == b.interior_coloring
+        && a.escape_radius == b.escape_radius
+        && a.stripe_density == b.stripe_density
+}
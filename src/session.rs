@@ -0,0 +1,192 @@
+//! Remembering the session: the view and the display settings are saved to a
+//! state file on exit and restored from it on the next start.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState, BAND_COUNT_RANGE, COLOR_SCALE_NAMES, COLORING_NAMES,
+    ESCAPE_RADIUS_RANGE, Error, FRACTAL_NAMES, POWER_RANGE,
+    STRIPE_DENSITY_RANGE, TRAP_NAMES, Uniforms, bookmarks::Bookmark,
+    gradient::GRADIENT_PALETTE,
+};
+
+/// Path of the file the session is stored in.
+const SESSION_PATH: &str = "session.json";
+
+/// The state of the explorer saved on exit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// The position, set and formula of the view.
+    #[serde(flatten)]
+    view: Bookmark,
+    /// See [`Uniforms::julia_c`].
+    julia_c: [f64; 2],
+    /// See [`Uniforms::palette`].
+    palette: u32,
+    /// See [`Uniforms::coloring_mode`].
+    coloring_mode: u32,
+    /// See [`Uniforms::color_scale_mode`]. Linear in sessions saved before it
+    /// was configurable.
+    #[serde(default)]
+    color_scale_mode: u32,
+    /// See [`Uniforms::color_offset`]. Unshifted in sessions saved before it
+    /// was configurable.
+    #[serde(default)]
+    color_offset: f32,
+    /// See [`Uniforms::band_count`].
+    #[serde(default = "default_band_count")]
+    band_count: f32,
+    /// See [`Uniforms::trap_shape`].
+    trap_shape: u32,
+    /// See [`Uniforms::stripe_density`].
+    #[serde(default = "default_stripe_density")]
+    stripe_density: f32,
+    /// See [`Uniforms::distance_estimation`].
+    distance_estimation: bool,
+    /// See [`Uniforms::interior_coloring`].
+    interior_coloring: bool,
+    /// See [`Uniforms::interior_color`]. Black in sessions saved before it
+    /// was configurable.
+    #[serde(default)]
+    interior_color: [f32; 3],
+    /// See [`Uniforms::dithering`].
+    #[serde(default)]
+    dithering: bool,
+    /// See [`Uniforms::escape_radius`].
+    escape_radius: f32,
+    /// Whether [`Uniforms::julia_c`] is frozen instead of following the mouse.
+    julia_locked: bool,
+    /// Whether the Julia set preview is shown.
+    show_julia_inset: bool,
+    /// Whether the minimap is shown.
+    #[serde(default)]
+    show_minimap: bool,
+    /// Whether the FPS and frame time overlay is shown.
+    show_overlay: bool,
+}
+
+/// The [`Session::band_count`] of sessions saved before it was configurable.
+fn default_band_count() -> f32 {
+    Uniforms::default().band_count
+}
+
+/// The [`Session::stripe_density`] of sessions saved before it was
+/// configurable.
+fn default_stripe_density() -> f32 {
+    Uniforms::default().stripe_density
+}
+
+impl Session {
+    /// Restores the saved view and coloring into the `uniforms`.
+    pub fn apply(&self, uniforms: &mut Uniforms) {
+        self.view.apply(uniforms);
+        uniforms.julia_c = self.julia_c;
+        uniforms.palette = self.palette;
+        uniforms.coloring_mode = self.coloring_mode;
+        uniforms.color_scale_mode = self.color_scale_mode;
+        uniforms.color_offset = self.color_offset;
+        uniforms.band_count = self.band_count;
+        uniforms.trap_shape = self.trap_shape;
+        uniforms.stripe_density = self.stripe_density;
+        uniforms.distance_estimation =
+            f32::from(u8::from(self.distance_estimation));
+        uniforms.interior_coloring =
+            f32::from(u8::from(self.interior_coloring));
+        uniforms.interior_color = self.interior_color;
+        uniforms.dithering = f32::from(u8::from(self.dithering));
+        uniforms.escape_radius = self.escape_radius;
+    }
+
+    /// Whether all the saved values are in range, so that the session can be
+    /// restored.
+    fn is_valid(&self) -> bool {
+        let view = &self.view;
+        view.offset
+            .iter()
+            .chain(&self.julia_c)
+            .all(|x| x.is_finite())
+            && self.interior_color.iter().all(|x| x.is_finite())
+            && view.zooms.is_finite()
+            && view.rotation.is_finite()
+            && self.color_offset.is_finite()
+            && view.max_iter > 0
+            && (view.fractal_type as usize) < FRACTAL_NAMES.len()
+            && POWER_RANGE.contains(&view.power)
+            && self.palette <= GRADIENT_PALETTE
+            && (self.coloring_mode as usize) < COLORING_NAMES.len()
+            && (self.color_scale_mode as usize) < COLOR_SCALE_NAMES.len()
+            && BAND_COUNT_RANGE.contains(&self.band_count)
+            && (self.trap_shape as usize) < TRAP_NAMES.len()
+            && STRIPE_DENSITY_RANGE.contains(&self.stripe_density)
+            && ESCAPE_RADIUS_RANGE.contains(&self.escape_radius)
+    }
+}
+
+impl AppState {
+    /// Saves the current session to the state file.
+    pub fn save_session(&self) {
+        let uniforms = self.uniforms();
+        let session = Session {
+            view: Bookmark::new(uniforms),
+            julia_c: uniforms.julia_c,
+            palette: uniforms.palette,
+            coloring_mode: uniforms.coloring_mode,
+            color_scale_mode: uniforms.color_scale_mode,
+            color_offset: uniforms.color_offset,
+            band_count: uniforms.band_count,
+            trap_shape: uniforms.trap_shape,
+            stripe_density: uniforms.stripe_density,
+            distance_estimation: uniforms.distance_estimation == 1.0,
+            interior_coloring: uniforms.interior_coloring == 1.0,
+            interior_color: uniforms.interior_color,
+            dithering: uniforms.dithering == 1.0,
+            escape_radius: uniforms.escape_radius,
+            julia_locked: self.view.julia_locked,
+            show_julia_inset: self.view.show_julia_inset,
+            show_minimap: self.view.show_minimap,
+            show_overlay: self.view.show_overlay,
+        };
+        let result = serde_json::to_string_pretty(&session)
+            .map_err(Error::from)
+            .and_then(|json| Ok(fs::write(SESSION_PATH, json)?));
+        if let Err(e) = result {
+            eprintln!("Failed to save the session: {e}");
+        }
+    }
+
+    /// Restores the display settings of the `session`, its view is restored by
+    /// starting with the uniforms from [`Session::apply`].
+    pub fn restore_session(&mut self, session: &Session) {
+        self.view.julia_locked = session.julia_locked;
+        self.view.show_julia_inset = session.show_julia_inset;
+        self.view.show_minimap = session.show_minimap;
+        self.view.show_overlay = session.show_overlay;
+    }
+}
+
+/// Loads the session saved on the last exit, if any. A malformed file is
+/// reported and ignored.
+#[must_use]
+pub fn load_session() -> Option<Session> {
+    read_session(Path::new(SESSION_PATH)).unwrap_or_else(|e| {
+        eprintln!("Failed to load the session: {e}");
+        None
+    })
+}
+
+/// Reads the session from the file at `path`, returning `None` if the file
+/// doesn't exist.
+fn read_session(path: &Path) -> Result<Option<Session>, Error> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let session: Session = serde_json::from_str(&json)?;
+    if !session.is_valid() {
+        return Err(Error::InvalidView(json));
+    }
+    Ok(Some(session))
+}
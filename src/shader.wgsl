@@ -1,24 +1,60 @@
+// `real` is the type the fractal is computed with, `f64`, or `f32` on GPUs
+// without `f64` support. It's declared before this source by
+// `Precision::adapt_shader_source`.
+
 struct Uniforms {
-    resolution: vec2<f64>,
-    time: f64,
-    zooms: f64,
-    offset: vec2<f64>,
-    mouse_position: vec2<f64>,
+    resolution: vec2<real>,
+    time: real,
+    zooms: real,
+    offset: vec2<real>,
+    offset_lo: vec2<real>,
+    mouse_position: vec2<real>,
+    tile_offset: vec2<real>,
+    julia_c: vec2<real>,
     is_mandelbrot: f32,
     rotate_colors: f32,
     max_iter: u32,
+    palette: u32,
+    sample_count: u32,
+    fractal_type: u32,
+    power: f32,
+    coloring_mode: u32,
+    trap_shape: u32,
+    distance_estimation: f32,
+    iter_start: u32,
+    iter_end: u32,
+    interior_coloring: f32,
+    escape_radius: f32,
+    jitter: vec2f,
+    accumulated_samples: u32,
+    interior_color: vec3f,
+    rotation: f32,
+    dithering: f32,
+    output_scale: f32,
+    encode_srgb: f32,
+    color_scale_mode: u32,
+    color_offset: f32,
+    band_count: f32,
+    stripe_density: f32,
 }
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+// The gradient loaded from an image with `--palette`, a row of sRGB colors
+// the palette at index `GRADIENT` loops through.
+@group(0) @binding(1)
+var gradient: texture_2d<f32>;
+@group(0) @binding(2)
+var gradient_sampler: sampler;
+
 struct Interpolators {
     @builtin(position) pos: vec4<f32>,
-    @location(0) resolution: vec2<f64>,
-    @location(1) time: f64,
-    @location(2) zoom: f64,
-    @location(3) offset: vec2<f64>,
-    @location(4) mouse_position: vec2<f64>,
+    @location(0) resolution: vec2<real>,
+    @location(1) time: real,
+    @location(2) zoom: real,
+    @location(3) offset: vec2<real>,
+    @location(4) mouse_position: vec2<real>,
     @location(5) is_mandelbrot: f32,
     @location(6) rotate_colors: f32,
     @location(7) max_iter: u32,
@@ -47,72 +83,810 @@ fn vs_main(
 }
 
 @fragment
-fn fg_main(i: Interpolators) -> @location(0) vec4f {
+fn fg_main(
+    i: Interpolators,
+    @builtin(sample_index) sample_index: u32,
+) -> @location(0) vec4f {
     let res = i.resolution;
-    let time = i.time;
     let offset = i.offset;
-    let is_mandelbrot = i.is_mandelbrot == 1.0;
-    let rotate_colors = i.rotate_colors == 1.0;
     let max_iter = i.max_iter;
     let zoom = i.zoom;
     let pos = i.pos;
 
-    let px = vec2<f64>(pos.xy);
-    let uv = vec2<f64>(px.x, res.y - px.y);
+    // Using the sample index makes the shader run for each sample separately,
+    // so with MSAA each of them computes the fractal at its own position.
+    let sample = sample_position(uniforms.sample_count, sample_index);
+    let p = pixel_to_plane(pos.xy, sample, res);
+
+    // let c = p * automatic_zoom(time);
+    let c = p * zoom + offset;
+
+    // let iters = mandelbrot(vec2<real>(c) + vec2<real>(-1.253441321, 0.38469378));
+    // let iters = mandelbrot(vec2<real>(c) + vec2<real>(-1.768778837, 0.001738939));
+    // let iters = mandelbrot(vec2<real>(c) + vec2<real>(-0.3435595, -0.610793536));
+    // let iters = mandelbrot(vec2<real>(c) + vec2<real>(-1.940157343, 0.00000008));
+
+    let orbit = mandelbrot(c, max_iter);
+    return output(shade(orbit, 2.0 * zoom / res.x), pos.xy);
+}
+
+// The orbits of the progressive rendering, stored between the frames, see
+// `StoredOrbit`.
+@group(1) @binding(0)
+var stored_z: texture_2d<u32>;
+@group(1) @binding(1)
+var stored_state: texture_2d<u32>;
+
+// Bit of the stored iteration count that is set if the orbit escaped.
+const ESCAPED: u32 = 0x80000000u;
+
+// An `Orbit` in the two textures of the progressive rendering, with the floats
+// stored as their bits.
+struct StoredOrbit {
+    // `z`, with each coordinate split into a high and a low `f32` part to keep
+    // most of the `real` precision.
+    @location(0) z: vec4u,
+    // `dz`, the trap distance, the stripe sum or the period, which is only
+    // detected when coloring by the escape time, and the iteration count, see
+    // `ESCAPED`.
+    @location(1) state: vec4u,
+}
+
+// Continues the stored orbit of each pixel from `uniforms.iter_start` to
+// `uniforms.iter_end` iterations, starting it over if `iter_start` is 0.
+@fragment
+fn fg_progressive(i: Interpolators) -> StoredOrbit {
+    let c = pixel_to_plane(i.pos.xy, vec2f(0.5), i.resolution) * i.zoom + i.offset;
+    var orbit: Orbit;
+    if uniforms.iter_start == 0u {
+        orbit = start_orbit(c);
+    } else {
+        orbit = load_orbit(vec2u(i.pos.xy));
+    }
+    return store_orbit(continue_orbit(orbit, c, min(uniforms.iter_end, i.max_iter)));
+}
+
+// Colors each pixel by its stored orbit.
+@fragment
+fn fg_resolve(i: Interpolators) -> @location(0) vec4f {
+    let orbit = load_orbit(vec2u(i.pos.xy));
+    return output(shade(orbit, 2.0 * i.zoom / i.resolution.x), i.pos.xy);
+}
+
+fn store_orbit(orbit: Orbit) -> StoredOrbit {
+    let hi = vec2f(orbit.z);
+    let lo = vec2f(orbit.z - vec2<real>(hi));
+    var coloring = orbit.period;
+    switch uniforms.coloring_mode {
+        case ORBIT_TRAP: {
+            coloring = bitcast<u32>(orbit.trap);
+        }
+        case STRIPE_AVERAGE: {
+            coloring = bitcast<u32>(orbit.stripe);
+        }
+        default: {}
+    }
+    let iter = orbit.iter | select(0u, ESCAPED, orbit.escaped);
+    return StoredOrbit(
+        bitcast<vec4u>(vec4f(hi.x, lo.x, hi.y, lo.y)),
+        vec4u(bitcast<vec2u>(vec2f(orbit.dz)), coloring, iter),
+    );
+}
+
+fn load_orbit(pixel: vec2u) -> Orbit {
+    let z = bitcast<vec4f>(textureLoad(stored_z, pixel, 0));
+    let state = textureLoad(stored_state, pixel, 0);
+    let is_escape_time = uniforms.coloring_mode == ESCAPE_TIME;
+    return Orbit(
+        vec2<real>(real(z.x) + real(z.y), real(z.z) + real(z.w)),
+        vec2<real>(bitcast<vec2f>(state.xy)),
+        bitcast<f32>(state.z),
+        bitcast<f32>(state.z),
+        select(0u, state.z, is_escape_time),
+        state.w & ~ESCAPED,
+        (state.w & ESCAPED) != 0u,
+    );
+}
+
+// The sum of the colors of the samples accumulated so far, with the floats
+// stored as their bits, see `fg_accumulate`.
+@group(1) @binding(7)
+var accumulated: texture_2d<u32>;
+
+// Adds the color of each pixel at the `uniforms.jitter` position within it to
+// the sum of its `uniforms.accumulated_samples` previous samples, starting the
+// sum over if there are none.
+@fragment
+fn fg_accumulate(i: Interpolators) -> @location(0) vec4u {
+    let c = pixel_to_plane(i.pos.xy, uniforms.jitter, i.resolution) * i.zoom + i.offset;
+    let color = shade(mandelbrot(c, i.max_iter), 2.0 * i.zoom / i.resolution.x);
+    var sum = vec3f(0.0);
+    if uniforms.accumulated_samples != 0u {
+        sum = bitcast<vec4f>(textureLoad(accumulated, vec2u(i.pos.xy), 0)).rgb;
+    }
+    return bitcast<vec4u>(vec4f(sum + color, 1.0));
+}
+
+// Colors each pixel by the average of its accumulated samples, including the
+// one added this frame.
+@fragment
+fn fg_average(i: Interpolators) -> @location(0) vec4f {
+    let sum = bitcast<vec4f>(textureLoad(accumulated, vec2u(i.pos.xy), 0)).rgb;
+    return output(sum / f32(uniforms.accumulated_samples + 1u), i.pos.xy);
+}
+
+// Maps the fragment position `pos`, moved to the `sample` position within its
+// pixel, to the complex plane of an unzoomed view centered at 0 and rotated by
+// `uniforms.rotation`.
+fn pixel_to_plane(pos: vec2f, sample: vec2f, res: vec2<real>) -> vec2<real> {
+    let px = vec2<real>(floor(pos) + sample) + uniforms.tile_offset;
+    // The width spans [-1, 1] and the height keeps the aspect ratio, the same
+    // mapping is applied to the mouse position in `normalize_position`.
+    let uv = vec2<real>(px.x, res.y - px.y);
     let p = (uv * 2.0 - res) / res.x;
+    // The same rotation as `Uniforms::rotated`.
+    let r = vec2<real>(vec2f(cos(uniforms.rotation), sin(uniforms.rotation)));
+    return vec2<real>(p.x * r.x - p.y * r.y, p.x * r.y + p.y * r.x);
+}
 
+// Colors a pixel by its `orbit`. `pixel` is the size of a pixel in the complex
+// plane.
+fn shade(orbit: Orbit, pixel: real) -> vec3f {
+    let rot = color_rotation();
+    if uniforms.coloring_mode == ORBIT_TRAP {
+        // Avoid infinities in the coloring when the orbit hits the trap exactly.
+        return palette(uniforms.palette, -log(max(orbit.trap, 1e-6)), rot);
+    }
+    if !orbit.escaped {
+        return interior_color(orbit.period, rot);
+    }
+    if uniforms.fractal_type == NEWTON {
+        return newton_color(orbit, rot);
+    }
 
-    let c_zoom = select(f64(2.5), zoom, is_mandelbrot);
-    // let c = p * automatic_zoom(time);
-    let c = p * c_zoom;
-    let mouse_position = (i.mouse_position) * vec2<f64>(1.0, -1.0) * zoom;
+    let iters = smooth_iters(orbit);
+    var col = palette(uniforms.palette, color_position(f32(iters)), rot);
+    if uniforms.coloring_mode == STRIPE_AVERAGE {
+        // The stripes darken the gradient rather than replacing it.
+        col *= 0.25 + 0.75 * stripe_average(orbit, iters);
+    }
+    if uniforms.distance_estimation == 1.0 {
+        // Estimated distance to the boundary of the set.
+        let r = sqrt(dot(orbit.z, orbit.z));
+        let distance = r * real(log(f32(r))) / sqrt(dot(orbit.dz, orbit.dz));
+        // Fade to black within one pixel of the boundary.
+        col *= smoothstep(0.0, 1.0, f32(distance / pixel));
+    }
+    return col;
+}
 
-    // let iters = mandelbrot(vec2<f64>(c) + vec2<f64>(-1.253441321, 0.38469378));
-    // let iters = mandelbrot(vec2<f64>(c) + vec2<f64>(-1.768778837, 0.001738939));
-    // let iters = mandelbrot(vec2<f64>(c) + vec2<f64>(-0.3435595, -0.610793536));
-    // let iters = mandelbrot(vec2<f64>(c) + vec2<f64>(-1.940157343, 0.00000008));
+// Returns the phase of the color rotation.
+fn color_rotation() -> f32 {
+    let rotation = select(f32(1.0), f32(uniforms.time), uniforms.rotate_colors == 1.0);
+    return rotation + uniforms.color_offset;
+}
 
-    var col = vec3<f32>(0.0);
-    let iters = mandelbrot(c, offset, mouse_position, is_mandelbrot, max_iter);
-    let rot = select(f32(1.0), f32(time), rotate_colors);
-    if iters > 0.5 {
-        col = 0.5 + 0.5 * cos(3.0 + f32(iters) * 0.15 * 0.5 + vec3f(0.0, 0.6, 1.0) * rot * 8);
+// `uniforms.color_scale_mode` mapping the iteration count linearly.
+const LINEAR_SCALE: u32 = 0u;
+// `uniforms.color_scale_mode` mapping the logarithm of the iteration count.
+const LOG_SCALE: u32 = 1u;
+// `uniforms.color_scale_mode` mapping the square root of the iteration count.
+const SQRT_SCALE: u32 = 2u;
+
+// Maps the smooth iteration count `iters` to the position in the palette with
+// the `uniforms.color_scale_mode`, repeating every `uniforms.band_count`
+// iterations on the linear scale. The nonlinear scales spread the colors over
+// the high counts near the boundary less, and match the slope of the linear
+// one at 100 iterations.
+fn color_position(iters: f32) -> f32 {
+    let n = max(iters, 0.0);
+    let scale = TAU / uniforms.band_count;
+    switch uniforms.color_scale_mode {
+        case LOG_SCALE: {
+            return log(n + 1.0) * 100.0 * scale;
+        }
+        case SQRT_SCALE: {
+            return sqrt(n) * 20.0 * scale;
+        }
+        default: {
+            return n * scale;
+        }
+    }
+}
+
+// Returns the smooth iteration count of an escaped `orbit`, normalized by the
+// escape radius so that only the accuracy of the coloring depends on it.
+fn smooth_iters(orbit: Orbit) -> real {
+    if uniforms.fractal_type == NEWTON {
+        return newton_smooth_iters(orbit);
+    }
+    // Degree of the iterated polynomial.
+    let degree = select(2.0, uniforms.power, uniforms.fractal_type == MULTIBROT);
+    let dotz = f32(dot(orbit.z, orbit.z));
+    let radius2 = uniforms.escape_radius * uniforms.escape_radius;
+    return real(orbit.iter) + 1.0 - real(log2(log2(dotz) / log2(radius2)) / log2(degree));
+}
+
+// Color of a point inside the set whose orbit converged to a cycle of the
+// given `period`, 0 if none was detected.
+fn interior_color(period: u32, rot: f32) -> vec3f {
+    if uniforms.interior_coloring == 1.0 && period != 0u {
+        // Darker than the outside, so that the boundary stays visible.
+        return 0.5 * palette(uniforms.palette, f32(period) * 1.7, rot);
+    }
+    return uniforms.interior_color;
+}
+
+// Number of bins of the iteration count histogram, matches `HISTOGRAM_BINS`
+// in `histogram.rs`.
+const HISTOGRAM_BINS: u32 = 4096u;
+
+// The smooth iteration count of each pixel for the histogram equalization, or
+// the negated and decremented period inside the set.
+@group(1) @binding(2)
+var<storage, read_write> iterations: array<f32>;
+// Number of escaped pixels in each bin of the iteration counts up to
+// `uniforms.max_iter`.
+@group(1) @binding(3)
+var<storage, read_write> histogram: array<atomic<u32>, HISTOGRAM_BINS>;
+// The cumulative distribution of the iteration counts at the edges of the
+// histogram bins.
+@group(1) @binding(4)
+var<storage, read_write> cdf: array<f32, HISTOGRAM_BINS + 1>;
+
+// Read-only views of `iterations` and `cdf` for the fragment shader.
+@group(1) @binding(5)
+var<storage> equalize_iterations: array<f32>;
+@group(1) @binding(6)
+var<storage> equalize_cdf: array<f32, HISTOGRAM_BINS + 1>;
+
+// Computes the iteration count of each pixel and counts the escaped ones in
+// the `histogram`.
+@compute @workgroup_size(8, 8)
+fn cs_iterate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2u(uniforms.resolution);
+    if any(id.xy >= size) {
+        return;
+    }
+    let zoom = exp(uniforms.zooms / 10.0);
+    let c = pixel_to_plane(vec2f(id.xy), vec2f(0.5), uniforms.resolution) * zoom + uniforms.offset;
+    let orbit = mandelbrot(c, uniforms.max_iter);
+
+    var value = -f32(orbit.period) - 1.0;
+    if orbit.escaped {
+        value = max(f32(smooth_iters(orbit)), 0.0);
+        let bin = min(u32(histogram_position(value)), HISTOGRAM_BINS - 1u);
+        atomicAdd(&histogram[bin], 1u);
+    }
+    iterations[id.y * size.x + id.x] = value;
+}
+
+// Sums up the `histogram` into the `cdf`.
+@compute @workgroup_size(1)
+fn cs_cdf() {
+    var total = 0u;
+    for (var i = 0u; i < HISTOGRAM_BINS; i++) {
+        total += atomicLoad(&histogram[i]);
+    }
+    var sum = 0u;
+    cdf[0] = 0.0;
+    for (var i = 0u; i < HISTOGRAM_BINS; i++) {
+        sum += atomicLoad(&histogram[i]);
+        cdf[i + 1u] = f32(sum) / f32(max(total, 1u));
+    }
+}
+
+// Colors each pixel by the fraction of the escaped pixels with a lower
+// iteration count, spreading the palette evenly over the image.
+@fragment
+fn fg_equalize(i: Interpolators) -> @location(0) vec4f {
+    let pixel = vec2u(i.pos.xy);
+    let value = equalize_iterations[pixel.y * u32(i.resolution.x) + pixel.x];
+    let rot = color_rotation();
+    if value < 0.0 {
+        return output(interior_color(u32(-value - 1.0), rot), i.pos.xy);
+    }
+    // Interpolate within the bin to avoid banding.
+    let x = histogram_position(value);
+    let bin = min(u32(x), HISTOGRAM_BINS - 1u);
+    let t = mix(equalize_cdf[bin], equalize_cdf[bin + 1u], saturate(x - f32(bin)));
+    return output(palette(uniforms.palette, t * TAU, rot), i.pos.xy);
+}
+
+// Position of the smooth iteration count `value` in the histogram, in bins.
+fn histogram_position(value: f32) -> f32 {
+    return value / f32(uniforms.max_iter) * f32(HISTOGRAM_BINS);
+}
+
+// Spacing of the pixels whose orbits are counted by `cs_count_inside`,
+// matches `INSIDE_STRIDE` in `stats.rs`.
+const INSIDE_STRIDE: u32 = 4u;
+
+// Number of counted pixels that are inside the set.
+@group(1) @binding(8)
+var<storage, read_write> inside_count: atomic<u32>;
+
+// Highest iteration count at which a counted pixel escaped.
+@group(1) @binding(9)
+var<storage, read_write> max_escape_iter: atomic<u32>;
+
+// Counts the pixels of every `INSIDE_STRIDE`th row and column whose orbits
+// don't escape in `inside_count`.
+@compute @workgroup_size(8, 8)
+fn cs_count_inside(@builtin(global_invocation_id) id: vec3<u32>) {
+    let pixel = id.xy * INSIDE_STRIDE;
+    if any(pixel >= vec2u(uniforms.resolution)) {
+        return;
+    }
+    if !mandelbrot(counted_point(pixel), uniforms.max_iter).escaped {
+        atomicAdd(&inside_count, 1u);
+    }
+}
+
+// Finds the highest iteration count at which the orbits of the pixels of
+// every `INSIDE_STRIDE`th row and column escape in `max_escape_iter`.
+@compute @workgroup_size(8, 8)
+fn cs_max_escape_iter(@builtin(global_invocation_id) id: vec3<u32>) {
+    let pixel = id.xy * INSIDE_STRIDE;
+    if any(pixel >= vec2u(uniforms.resolution)) {
+        return;
+    }
+    let orbit = mandelbrot(counted_point(pixel), uniforms.max_iter);
+    if orbit.escaped {
+        atomicMax(&max_escape_iter, orbit.iter);
+    }
+}
+
+// Returns the point of the complex plane at the center of the counted
+// `pixel`.
+fn counted_point(pixel: vec2u) -> vec2<real> {
+    let zoom = exp(uniforms.zooms / 10.0);
+    return pixel_to_plane(vec2f(pixel), vec2f(0.5), uniforms.resolution) * zoom + uniforms.offset;
+}
+
+const TAU: f32 = 6.283185307179586;
+
+// Index of the palette sampling the `gradient`, after the built-in ones.
+const GRADIENT: u32 = 5u;
+
+// Maps the (smooth) iteration count `t` to a color using the palette at
+// `index`. `rot` is the color rotation phase.
+fn palette(index: u32, t: f32, rot: f32) -> vec3f {
+    // The palettes are designed in sRGB, so that e.g. 0.5 is a perceptual
+    // mid-gray, but they are shaded and blended in linear space.
+    return srgb_to_linear(palette_srgb(index, t, rot));
+}
+
+fn palette_srgb(index: u32, t: f32, rot: f32) -> vec3f {
+    // Position in a looping gradient.
+    let x = fract((t + rot) / TAU);
+    switch index {
+        // Grayscale
+        case 1u: {
+            return vec3f(0.5 + 0.5 * cos(t + rot));
+        }
+        // Fire
+        case 2u: {
+            let v = 0.5 + 0.5 * cos(t + rot);
+            return clamp(vec3f(3.0 * v, 3.0 * v - 1.0, 3.0 * v - 2.0), vec3f(0.0), vec3f(1.0));
+        }
+        // Ocean
+        case 3u: {
+            let v = 0.5 + 0.5 * cos(t + rot);
+            return mix(vec3f(0.0, 0.05, 0.2), vec3f(0.0, 0.6, 0.8), v) + vec3f(pow(v, 8.0));
+        }
+        // Ultra Fractal
+        case 4u: {
+            return ultra_fractal(x);
+        }
+        case GRADIENT: {
+            // Without a loaded gradient, e.g. in a restored session, there is
+            // only the placeholder.
+            if textureDimensions(gradient).x < 2u {
+                return rainbow(t, rot);
+            }
+            // Sampled at an explicit level, since compute passes and
+            // non-uniform control flow have no derivatives.
+            return textureSampleLevel(gradient, gradient_sampler, vec2f(x, 0.5), 0.0).rgb;
+        }
+        default: {
+            return rainbow(t, rot);
+        }
+    }
+}
+
+fn rainbow(t: f32, rot: f32) -> vec3f {
+    return 0.5 + 0.5 * cos(3.0 + t + vec3f(0.0, 0.6, 1.0) * rot * 8);
+}
+
+// The classic Ultra Fractal default gradient, `x` is in [0, 1).
+fn ultra_fractal(x: f32) -> vec3f {
+    let stops = array(0.0, 0.16, 0.42, 0.6425, 0.8575, 1.0);
+    let colors = array(
+        vec3f(0.0, 7.0, 100.0) / 255.0,
+        vec3f(32.0, 107.0, 203.0) / 255.0,
+        vec3f(237.0, 255.0, 255.0) / 255.0,
+        vec3f(255.0, 170.0, 0.0) / 255.0,
+        vec3f(0.0, 2.0, 0.0) / 255.0,
+        vec3f(0.0, 7.0, 100.0) / 255.0,
+    );
+    for (var i = 0; i < 5; i++) {
+        if x < stops[i + 1] {
+            let t = (x - stops[i]) / (stops[i + 1] - stops[i]);
+            return mix(colors[i], colors[i + 1], smoothstep(0.0, 1.0, t));
+        }
+    }
+    return colors[5];
+}
+
+// Returns the final color of the fragment at `pos` with the linear `color`,
+// dithered and scaled by `uniforms.output_scale`. The colors are kept linear
+// until here and encoded by the sRGB format of the target, or by hand if it
+// has none, so that blending and averaging the samples are correct.
+fn output(color: vec3f, pos: vec2f) -> vec4f {
+    let c = dither(color, pos) * uniforms.output_scale;
+    if uniforms.encode_srgb == 1.0 {
+        return vec4f(linear_to_srgb(saturate(c)), 1.0);
+    }
+    return vec4f(c, 1.0);
+}
+
+// Adds noise of up to one step of an 8-bit sRGB target to the linear `color`
+// of the fragment at `pos` if `uniforms.dithering` is enabled, so that smooth
+// gradients don't show bands. The noise is interleaved gradient noise, which
+// spreads evenly like blue noise, shifted every frame.
+fn dither(color: vec3f, pos: vec2f) -> vec3f {
+    if uniforms.dithering != 1.0 {
+        return color;
+    }
+    let frame = f32(u32(uniforms.time * 60.0) % 64u);
+    let p = floor(pos) + 5.588238 * frame;
+    let noise = fract(52.9829189 * fract(dot(p, vec2f(0.06711056, 0.00583715))));
+    // The steps are even in the sRGB encoding, not in linear space.
+    let encoded = linear_to_srgb(saturate(color)) + (noise - 0.5) / 255.0;
+    return srgb_to_linear(saturate(encoded));
+}
+
+fn linear_to_srgb(c: vec3f) -> vec3f {
+    return select(1.055 * pow(c, vec3f(1.0 / 2.4)) - 0.055, 12.92 * c, c <= vec3f(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3f) -> vec3f {
+    return select(pow((c + 0.055) / 1.055, vec3f(2.4)), c / 12.92, c <= vec3f(0.04045));
+}
+
+// Standard sample positions within a pixel for each supported sample count.
+fn sample_position(count: u32, index: u32) -> vec2<f32> {
+    switch count {
+        case 2u: {
+            let positions = array(vec2f(0.75, 0.75), vec2f(0.25, 0.25));
+            return positions[min(index, 1u)];
+        }
+        case 4u: {
+            let positions = array(
+                vec2f(0.375, 0.125),
+                vec2f(0.875, 0.375),
+                vec2f(0.125, 0.625),
+                vec2f(0.625, 0.875),
+            );
+            return positions[min(index, 3u)];
+        }
+        case 8u: {
+            let positions = array(
+                vec2f(0.5625, 0.3125),
+                vec2f(0.4375, 0.6875),
+                vec2f(0.8125, 0.5625),
+                vec2f(0.3125, 0.1875),
+                vec2f(0.1875, 0.8125),
+                vec2f(0.0625, 0.4375),
+                vec2f(0.6875, 0.9375),
+                vec2f(0.9375, 0.0625),
+            );
+            return positions[min(index, 7u)];
+        }
+        default: {
+            return vec2f(0.5);
+        }
     }
-    return vec4f(col, 1.0);
 }
 
 fn automatic_zoom(t: f32) -> f32 {
     return pow(0.67 + 0.5 * cos(0.21 * t), 8.0);
 }
 
-fn mandelbrot(c: vec2<f64>, offset: vec2<f64>, mouse_position: vec2<f64>, is_mandelbrot: bool, max_iter: u32) -> f64 {
-    let z0 = select(c, vec2<f64>(0), is_mandelbrot);
-    let c0 = select(mouse_position + offset, c + offset, is_mandelbrot);
+const MANDELBROT: u32 = 0u;
+const BURNING_SHIP: u32 = 1u;
+const TRICORN: u32 = 2u;
+const MULTIBROT: u32 = 3u;
+const NEWTON: u32 = 4u;
+
+// The orbit of a point after some iterations.
+struct Orbit {
+    z: vec2<real>,
+    // Derivative `dz/dc` (or `dz/dz0` for Julia sets), for the distance
+    // estimate.
+    dz: vec2<real>,
+    // Minimum distance from the orbit to the trap, see `trap_distance`.
+    trap: f32,
+    // Sum of the `stripe_term`s of the orbit, see `stripe_average`.
+    stripe: f32,
+    // Period of the cycle the orbit converged to, 0 if none was detected.
+    period: u32,
+    // Number of iterations performed.
+    iter: u32,
+    // Whether the orbit escaped, `z` and `dz` are then the first values past
+    // the bailout. For Newton's method, whether it converged to a root.
+    escaped: bool,
+}
+
+// Computes the orbit of the point `c` for up to `max_iter` iterations.
+fn mandelbrot(c: vec2<real>, max_iter: u32) -> Orbit {
+    return continue_orbit(start_orbit(c), c, max_iter);
+}
 
-    if is_mandelbrot {
+// Returns the orbit of the point `c` before the first iteration. While
+// morphing between the sets, `z` starts between `c` and `0`.
+fn start_orbit(c: vec2<real>) -> Orbit {
+    let is_mandelbrot = uniforms.is_mandelbrot == 1.0;
+    let julia = 1.0 - real(uniforms.is_mandelbrot);
+    var orbit = Orbit(
+        c * julia,
+        vec2<real>(julia, 0.0),
+        1e20,
+        0.0,
+        0u,
+        0u,
+        false,
+    );
+
+    // Newton's method starts from the point itself, it has no Julia sets.
+    if uniforms.fractal_type == NEWTON {
+        orbit.z = c;
+        return orbit;
+    }
+
+    // The orbit trap colors the inside of the set as well, so it can't be
+    // skipped then.
+    if is_mandelbrot && uniforms.fractal_type == MANDELBROT && uniforms.coloring_mode == ESCAPE_TIME {
         // Cardioid and circle optimization
-        let c2 = dot(c0, c0);
-        if 256.0 * c2 * c2 - 96.0 * c2 + 32.0 * c0.x - 3.0 < 0.0 { return 0.0; }
-        if 16.0 * (c2 + 2.0 * c0.x + 1.0) - 1.0 < 0.0 { return 0.0; }
+        let c2 = dot(c, c);
+        if 256.0 * c2 * c2 - 96.0 * c2 + 32.0 * c.x - 3.0 < 0.0 {
+            orbit.period = 1u;
+        } else if 16.0 * (c2 + 2.0 * c.x + 1.0) - 1.0 < 0.0 {
+            orbit.period = 2u;
+        }
+    }
+    return orbit;
+}
+
+// Squared distance below which two points of an orbit are considered equal by
+// the period detection.
+const PERIOD_EPSILON = 1e-20;
+
+// Iterates the `orbit` of the point `c` until it escapes, converges to a cycle
+// or `end` iterations are reached. While morphing between the sets, the
+// constant added is between the Julia `c` and the point `c`.
+fn continue_orbit(orbit: Orbit, c: vec2<real>, end: u32) -> Orbit {
+    if uniforms.fractal_type == NEWTON {
+        return continue_newton(orbit, end);
+    }
+    // Exactly `c` or `julia_c` at the ends of the morph.
+    let t = real(uniforms.is_mandelbrot);
+    let c0 = c * t + uniforms.julia_c * (1.0 - t);
+    let dc = vec2<real>(t, 0.0);
+    let fractal_type = uniforms.fractal_type;
+    let power = uniforms.power;
+    var o = orbit;
+
+    // Brent's cycle detection: the orbit is compared to a reference point,
+    // which moves to the current point whenever the number of iterations since
+    // it reaches the next power of two. The reference starts over with every
+    // call, so this also works when the orbit is continued over several frames.
+    let detect_period = uniforms.interior_coloring == 1.0 && uniforms.coloring_mode == ESCAPE_TIME;
+    var reference = o.z;
+    var since = 0u;
+    var window = 1u;
+
+    for (; !o.escaped && o.period == 0u && o.iter < end; o.iter++) {
+        o.dz = derivative(o.z, o.dz, fractal_type, power) + dc;
+        o.z = iterate(o.z, c0, fractal_type, power);
+        // The point past the bailout is included, so that the last term can
+        // be recomputed from the escaped orbit.
+        if uniforms.coloring_mode == STRIPE_AVERAGE {
+            o.stripe += stripe_term(vec2f(o.z));
+        }
+        if dot(o.z, o.z) > real(uniforms.escape_radius * uniforms.escape_radius) {
+            o.escaped = true;
+            break;
+        }
+        // The starting point is skipped, for the Mandelbrot set it is always 0.
+        if uniforms.coloring_mode == ORBIT_TRAP {
+            o.trap = min(o.trap, trap_distance(vec2f(o.z), uniforms.trap_shape));
+        }
+        if detect_period {
+            since++;
+            let d = o.z - reference;
+            if dot(d, d) < PERIOD_EPSILON {
+                o.period = since;
+                break;
+            }
+            if since == window {
+                reference = o.z;
+                since = 0u;
+                window *= 2u;
+            }
+        }
+    }
+    return o;
+}
+
+// Squared length of a Newton step below which the orbit is considered to have
+// converged to a root.
+const NEWTON_EPSILON = 1e-10;
+
+// Continues Newton's method for `z^n - 1` from the `orbit` until it converges
+// to a root or `end` iterations are reached.
+fn continue_newton(orbit: Orbit, end: u32) -> Orbit {
+    var o = orbit;
+    for (; !o.escaped && o.iter < end; o.iter++) {
+        // The derivative vanishes at 0, where the method can't continue.
+        if dot(o.z, o.z) == 0.0 {
+            break;
+        }
+        let step = newton_step(o.z);
+        o.z -= step;
+        if uniforms.coloring_mode == ORBIT_TRAP {
+            o.trap = min(o.trap, trap_distance(vec2f(o.z), uniforms.trap_shape));
+        }
+        if dot(step, step) < NEWTON_EPSILON {
+            o.escaped = true;
+            break;
+        }
+    }
+    return o;
+}
+
+// Degree `n` of the polynomial `z^n - 1` of Newton's method, the integer part
+// of `uniforms.power`, at least 3.
+fn newton_degree() -> u32 {
+    return u32(max(floor(uniforms.power), 3.0));
+}
+
+// Returns the step `p(z) / p'(z)` of Newton's method for `p(z) = z^n - 1`.
+fn newton_step(z: vec2<real>) -> vec2<real> {
+    let n = newton_degree();
+    let w = complex_pow(z, f32(n - 1u));
+    let p = complex_mul(w, z) - vec2<real>(1.0, 0.0);
+    let dp = real(n) * w;
+    return complex_mul(p, vec2<real>(dp.x, -dp.y)) / dot(dp, dp);
+}
+
+// Returns the smooth iteration count of an `orbit` of Newton's method that
+// converged, interpolated by how far below `NEWTON_EPSILON` the next step is,
+// which shrinks quadratically.
+fn newton_smooth_iters(orbit: Orbit) -> real {
+    let step = newton_step(orbit.z);
+    let d = max(f32(dot(step, step)), 1e-37);
+    return real(orbit.iter) + 1.0 - real(log2(log(d) / log(NEWTON_EPSILON)));
+}
+
+// Colors a point by the root of `z^n - 1` its `orbit` converged to, darker the
+// more iterations it took, fading over `uniforms.band_count` iterations.
+fn newton_color(orbit: Orbit, rot: f32) -> vec3f {
+    let n = newton_degree();
+    // The roots are the `n`th roots of unity, `exp(TAU * k / n * i)`.
+    let z = vec2f(orbit.z);
+    let k = (i32(round(atan2(z.y, z.x) / TAU * f32(n))) + i32(n)) % i32(n);
+    let shade = exp(-f32(newton_smooth_iters(orbit)) * TAU / uniforms.band_count);
+    return palette(uniforms.palette, f32(k) * TAU / f32(n), rot) * shade;
+}
+
+const ESCAPE_TIME: u32 = 0u;
+const ORBIT_TRAP: u32 = 1u;
+const STRIPE_AVERAGE: u32 = 2u;
+
+// Term of the stripe average of the point `z` of an orbit in [0, 1], with
+// `uniforms.stripe_density` stripes around the origin.
+fn stripe_term(z: vec2f) -> f32 {
+    return 0.5 + 0.5 * sin(uniforms.stripe_density * atan2(z.y, z.x));
+}
+
+// Returns the average of the `stripe_term`s of an escaped `orbit`, with the
+// smooth iteration count `iters`. The averages with and without the last term
+// are interpolated by the fractional part of `iters`, so that the stripes
+// don't jump between the iteration bands.
+fn stripe_average(orbit: Orbit, iters: real) -> f32 {
+    let count = f32(orbit.iter + 1u);
+    let average = orbit.stripe / count;
+    let last = stripe_term(vec2f(orbit.z));
+    let previous = (orbit.stripe - last) / max(count - 1.0, 1.0);
+    return mix(previous, average, saturate(f32(iters - real(orbit.iter))));
+}
+
+const TRAP_POINT: u32 = 0u;
+const TRAP_LINE: u32 = 1u;
+const TRAP_CROSS: u32 = 2u;
+
+// Distance from `z` to the trap of the given `shape`: the origin, the real
+// axis, or both axes.
+fn trap_distance(z: vec2f, shape: u32) -> f32 {
+    switch shape {
+        case TRAP_LINE: {
+            return abs(z.y);
+        }
+        case TRAP_CROSS: {
+            return min(abs(z.x), abs(z.y));
+        }
+        default: {
+            return length(z);
+        }
     }
+}
+
+// Performs a single iteration of the formula of the given `fractal_type`.
+fn iterate(z: vec2<real>, c: vec2<real>, fractal_type: u32, power: f32) -> vec2<real> {
+    switch fractal_type {
+        // z = (|re| + |im|i)^2 + c
+        case BURNING_SHIP: {
+            let a = abs(z);
+            return vec2<real>(a.x * a.x - a.y * a.y, 2.0 * a.x * a.y) + c;
+        }
+        // z = conj(z)^2 + c
+        case TRICORN: {
+            return vec2<real>(z.x * z.x - z.y * z.y, -2.0 * z.x * z.y) + c;
+        }
+        // z = z^d + c
+        case MULTIBROT: {
+            return complex_pow(z, power) + c;
+        }
+        // z = z^2 + c
+        default: {
+            return vec2<real>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        }
+    }
+}
+
+// Performs a single iteration of the derivative `dz` of the formula of the
+// given `fractal_type`, without the `+ dc` term. The Burning Ship and Tricorn
+// formulas aren't analytic, so their derivatives are only approximations that
+// are good enough for the distance estimate.
+fn derivative(z: vec2<real>, dz: vec2<real>, fractal_type: u32, power: f32) -> vec2<real> {
+    switch fractal_type {
+        // dz = 2 * (|re| + |im|i) * dz
+        case BURNING_SHIP: {
+            return 2.0 * complex_mul(abs(z), dz);
+        }
+        // dz = 2 * conj(z * dz)
+        case TRICORN: {
+            let w = complex_mul(z, dz);
+            return 2.0 * vec2<real>(w.x, -w.y);
+        }
+        // dz = d * z^(d-1) * dz
+        case MULTIBROT: {
+            return real(power) * complex_mul(complex_pow(z, power - 1.0), dz);
+        }
+        // dz = 2 * z * dz
+        default: {
+            return 2.0 * complex_mul(z, dz);
+        }
+    }
+}
 
-    return mandelbrot_inner(z0, c0, max_iter);
+fn complex_mul(a: vec2<real>, b: vec2<real>) -> vec2<real> {
+    return vec2<real>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
 }
 
-fn mandelbrot_inner(z0: vec2<f64>, c: vec2<f64>, max_iter: u32) -> f64 {
-    var iter: f64 = 0.0;
-    var z = z0;
-    var dotz: f64;
-    for (var i = 0u; i < max_iter; i++) {
-        z = vec2<f64>(
-            z.x * z.x - z.y * z.y,
-            2.0 * z.x * z.y
-        ) + c;
-        dotz = dot(z, z);
-        if u32(dotz) > max_iter {
-            return iter - f64(log2(log2(f32(dotz)))) + 4.0;
+// Raises `z` to the power `d`. Integer powers are computed by repeated
+// multiplication to keep the full precision, others in polar form.
+fn complex_pow(z: vec2<real>, d: f32) -> vec2<real> {
+    if fract(d) == 0.0 {
+        var w = z;
+        for (var i = 1u; i < u32(d); i++) {
+            w = complex_mul(w, z);
         }
-        iter += 1.0;
+        return w;
     }
-    return 0.0;
+    let r = pow(length(vec2f(z)), d);
+    let theta = atan2(f32(z.y), f32(z.x)) * d;
+    return vec2<real>(vec2f(cos(theta), sin(theta)) * r);
 }
@@ -0,0 +1,182 @@
+// Double-double arithmetic for zooming deeper than a `real` allows. A number
+// is the unevaluated sum of the two `real`s of a `vec2<real>`, the low one
+// below the precision of the high one, which roughly doubles the precision.
+// This is appended to `shader.wgsl`, so that the coloring is shared.
+// `DD_SPLIT`, the factor splitting a `real` into two halves of its mantissa,
+// is declared along with `real`, see `shader.wgsl`.
+
+// 1, but unknown to the shader compiler, see `opaque`. Set by `fg_deep`.
+var<private> dd_one: real;
+
+// A complex number with double-double parts.
+struct ComplexDD {
+    re: vec2<real>,
+    im: vec2<real>,
+}
+
+// Colors each pixel like `fg_main`, but computes the orbits in double-double
+// precision, with the offset split into `uniforms.offset` and
+// `uniforms.offset_lo`.
+@fragment
+fn fg_deep(i: Interpolators) -> @location(0) vec4f {
+    // `max_iter` is at least 1.
+    dd_one = real(min(uniforms.max_iter, 1u));
+    // Only the offset needs the extra precision, the distance from it is
+    // exact enough as a single `real`.
+    let p = pixel_to_plane(i.pos.xy, vec2f(0.5), i.resolution) * i.zoom;
+    let c = ComplexDD(
+        dd_add(vec2<real>(uniforms.offset.x, uniforms.offset_lo.x), vec2<real>(p.x, 0.0)),
+        dd_add(vec2<real>(uniforms.offset.y, uniforms.offset_lo.y), vec2<real>(p.y, 0.0)),
+    );
+    let orbit = mandelbrot_dd(c, i.max_iter);
+    return output(shade(orbit, 2.0 * i.zoom / i.resolution.x), i.pos.xy);
+}
+
+// Computes the orbit of the point `c` for up to `max_iter` iterations, like
+// `mandelbrot`. Only `z` is iterated with the extra precision, the derivative
+// and the returned `Orbit` use the high parts. Periods aren't detected, since
+// the points of the orbits can't be compared with enough precision.
+fn mandelbrot_dd(c: ComplexDD, max_iter: u32) -> Orbit {
+    // Newton's method is only computed in a `real`, so deep views of it break
+    // down into blocks like those of the other fractals without this.
+    if uniforms.fractal_type == NEWTON {
+        return mandelbrot(vec2<real>(c.re.x, c.im.x), max_iter);
+    }
+    // Morphs between the sets aren't blended in double-double precision, they
+    // switch halfway.
+    let is_mandelbrot = uniforms.is_mandelbrot >= 0.5;
+    let fractal_type = uniforms.fractal_type;
+    let power = uniforms.power;
+    // Only skips the points inside the main cardioid and circle.
+    var o = start_orbit(vec2<real>(c.re.x, c.im.x));
+    var z = c;
+    var c0 = c;
+    if is_mandelbrot {
+        z = ComplexDD(vec2<real>(0.0), vec2<real>(0.0));
+    } else {
+        c0 = ComplexDD(vec2<real>(uniforms.julia_c.x, 0.0), vec2<real>(uniforms.julia_c.y, 0.0));
+    }
+    let dc = select(vec2<real>(0.0), vec2<real>(1.0, 0.0), is_mandelbrot);
+    let radius2 = real(uniforms.escape_radius * uniforms.escape_radius);
+
+    for (; o.period == 0u && o.iter < max_iter; o.iter++) {
+        o.dz = derivative(o.z, o.dz, fractal_type, power) + dc;
+        z = iterate_dd(z, c0, fractal_type, power);
+        o.z = vec2<real>(z.re.x, z.im.x);
+        if uniforms.coloring_mode == STRIPE_AVERAGE {
+            o.stripe += stripe_term(vec2f(o.z));
+        }
+        if dot(o.z, o.z) > radius2 {
+            o.escaped = true;
+            break;
+        }
+        if uniforms.coloring_mode == ORBIT_TRAP {
+            o.trap = min(o.trap, trap_distance(vec2f(o.z), uniforms.trap_shape));
+        }
+    }
+    return o;
+}
+
+// Performs a single iteration of the formula of the given `fractal_type`, like
+// `iterate`. Non-integer Multibrot powers are computed in a `real` only.
+fn iterate_dd(z: ComplexDD, c: ComplexDD, fractal_type: u32, power: f32) -> ComplexDD {
+    var w: ComplexDD;
+    switch fractal_type {
+        // z = (|re| + |im|i)^2 + c
+        case BURNING_SHIP: {
+            w = complex_square_dd(ComplexDD(dd_abs(z.re), dd_abs(z.im)));
+        }
+        // z = conj(z)^2 + c
+        case TRICORN: {
+            w = complex_square_dd(z);
+            w.im = -w.im;
+        }
+        // z = z^d + c
+        case MULTIBROT: {
+            if fract(power) == 0.0 {
+                w = z;
+                for (var k = 1u; k < u32(power); k++) {
+                    w = complex_mul_dd(w, z);
+                }
+            } else {
+                let p = complex_pow(vec2<real>(z.re.x, z.im.x), power);
+                w = ComplexDD(vec2<real>(p.x, 0.0), vec2<real>(p.y, 0.0));
+            }
+        }
+        // z = z^2 + c
+        default: {
+            w = complex_square_dd(z);
+        }
+    }
+    return ComplexDD(dd_add(w.re, c.re), dd_add(w.im, c.im));
+}
+
+fn complex_square_dd(z: ComplexDD) -> ComplexDD {
+    return ComplexDD(
+        dd_add(dd_mul(z.re, z.re), -dd_mul(z.im, z.im)),
+        2.0 * dd_mul(z.re, z.im),
+    );
+}
+
+fn complex_mul_dd(a: ComplexDD, b: ComplexDD) -> ComplexDD {
+    return ComplexDD(
+        dd_add(dd_mul(a.re, b.re), -dd_mul(a.im, b.im)),
+        dd_add(dd_mul(a.re, b.im), dd_mul(a.im, b.re)),
+    );
+}
+
+fn dd_abs(a: vec2<real>) -> vec2<real> {
+    return select(a, -a, a.x < 0.0);
+}
+
+// Returns `a + b`, with the error of the sum of the high parts kept in the low
+// part, see `two_sum`.
+fn dd_add(a: vec2<real>, b: vec2<real>) -> vec2<real> {
+    let s = two_sum(a.x, b.x);
+    let t = two_sum(a.y, b.y);
+    let r = quick_two_sum(s.x, s.y + t.x);
+    return quick_two_sum(r.x, r.y + t.y);
+}
+
+// Returns `a * b`, with the error of the product of the high parts kept in the
+// low part, see `two_prod`.
+fn dd_mul(a: vec2<real>, b: vec2<real>) -> vec2<real> {
+    let p = two_prod(a.x, b.x);
+    return quick_two_sum(p.x, p.y + (a.x * b.y + a.y * b.x));
+}
+
+// Returns `a + b` and its rounding error, exactly.
+fn two_sum(a: real, b: real) -> vec2<real> {
+    let s = opaque(a + b);
+    let v = opaque(s - a);
+    return vec2<real>(s, (a - (s - v)) + (b - v));
+}
+
+// Returns `a + b` and its rounding error, exactly if `|a| >= |b|`.
+fn quick_two_sum(a: real, b: real) -> vec2<real> {
+    let s = opaque(a + b);
+    return vec2<real>(s, b - (s - a));
+}
+
+// Returns `a * b` and its rounding error, exactly, using Dekker's algorithm.
+fn two_prod(a: real, b: real) -> vec2<real> {
+    let p = a * b;
+    let x = split(a);
+    let y = split(b);
+    return vec2<real>(p, ((x.x * y.x - p) + x.x * y.y + x.y * y.x) + x.y * y.y);
+}
+
+// Splits `a` into a high and a low part with half of its mantissa each, so
+// that their products are exact.
+fn split(a: real) -> vec2<real> {
+    let t = opaque(DD_SPLIT * a);
+    let hi = t - opaque(t - a);
+    return vec2<real>(hi, a - hi);
+}
+
+// Returns `x` unchanged, but hides that from the shader compiler, which would
+// otherwise simplify away the rounding errors the functions above compute,
+// e.g. `(a + b) - a` to `b`.
+fn opaque(x: real) -> real {
+    return x * dd_one;
+}
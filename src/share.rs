@@ -0,0 +1,144 @@
+//! Sharing views as compact strings, e.g. on forums: the view is packed into a
+//! versioned binary layout and encoded as URL-safe base64 without padding, see
+//! [`encode_view`] and [`decode_view`].
+//!
+//! Layout version 1, little-endian, 47 bytes:
+//!
+//! | Bytes   | Field                                  |
+//! | ------- | -------------------------------------- |
+//! | 0       | Version, `1`                           |
+//! | 1..17   | [`Uniforms::offset`], two `f64`s       |
+//! | 17..33  | [`Uniforms::offset_lo`], two `f64`s    |
+//! | 33..41  | [`Uniforms::zooms`], `f64`             |
+//! | 41..45  | [`Uniforms::max_iter`], `u32`          |
+//! | 45      | [`Uniforms::fractal_type`], `u8`       |
+//! | 46      | [`Uniforms::palette`], `u8`            |
+//!
+//! New fields go into new versions, which must keep decoding the old ones.
+
+use crate::{Error, FRACTAL_NAMES, Uniforms, gradient::GRADIENT_PALETTE};
+
+/// The version of the layout [`encode_view`] writes.
+const VERSION: u8 = 1;
+
+/// Size of the layout of version 1.
+const V1_SIZE: usize = 47;
+
+/// The 64 characters of URL-safe base64.
+const BASE64: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes the view described by the `uniforms` as a shareable string.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "fractal types and palettes are few"
+)]
+pub fn encode_view(uniforms: &Uniforms) -> String {
+    let mut bytes = Vec::with_capacity(V1_SIZE);
+    bytes.push(VERSION);
+    for x in uniforms.offset.iter().chain(&uniforms.offset_lo) {
+        bytes.extend_from_slice(&x.to_le_bytes());
+    }
+    bytes.extend_from_slice(&uniforms.zooms.to_le_bytes());
+    bytes.extend_from_slice(&uniforms.max_iter.to_le_bytes());
+    bytes.push(uniforms.fractal_type as u8);
+    bytes.push(uniforms.palette as u8);
+    encode_base64(&bytes)
+}
+
+/// Decodes a view encoded by [`encode_view`] into the `uniforms`. Leaves the
+/// `uniforms` untouched if the `text` is invalid.
+pub fn decode_view(text: &str, uniforms: &mut Uniforms) -> Result<(), Error> {
+    let invalid = || Error::InvalidView(text.to_owned());
+    let bytes = decode_base64(text.trim()).ok_or_else(invalid)?;
+    let view = match bytes.first() {
+        Some(1) if bytes.len() == V1_SIZE => View::read_v1(&bytes),
+        _ => return Err(invalid()),
+    };
+    if ![view.offset, view.offset_lo]
+        .as_flattened()
+        .iter()
+        .chain([&view.zooms])
+        .all(|x| x.is_finite())
+        || view.max_iter == 0
+        || usize::from(view.fractal_type) >= FRACTAL_NAMES.len()
+        || u32::from(view.palette) > GRADIENT_PALETTE
+    {
+        return Err(invalid());
+    }
+
+    uniforms.offset = view.offset;
+    uniforms.offset_lo = view.offset_lo;
+    uniforms.zooms = view.zooms;
+    uniforms.max_iter = view.max_iter;
+    uniforms.fractal_type = view.fractal_type.into();
+    uniforms.palette = view.palette.into();
+    Ok(())
+}
+
+/// The fields of a decoded view.
+struct View {
+    offset: [f64; 2],
+    offset_lo: [f64; 2],
+    zooms: f64,
+    max_iter: u32,
+    fractal_type: u8,
+    palette: u8,
+}
+
+impl View {
+    /// Reads the layout of version 1 from the [`V1_SIZE`] `bytes`.
+    fn read_v1(bytes: &[u8]) -> Self {
+        let f64_at = |i: usize| {
+            f64::from_le_bytes(bytes[i..i + 8].try_into().expect("8 bytes"))
+        };
+        Self {
+            offset: [f64_at(1), f64_at(9)],
+            offset_lo: [f64_at(17), f64_at(25)],
+            zooms: f64_at(33),
+            max_iter: u32::from_le_bytes(
+                bytes[41..45].try_into().expect("4 bytes"),
+            ),
+            fractal_type: bytes[45],
+            palette: bytes[46],
+        }
+    }
+}
+
+/// Encodes the `bytes` as URL-safe base64 without padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        // Every byte needs 8 bits, so a chunk of n bytes takes n + 1
+        // characters of 6 bits.
+        for i in 0..=chunk.len() {
+            let index = (bits >> (18 - 6 * i)) & 0x3f;
+            text.push(char::from(BASE64[index as usize]));
+        }
+    }
+    text
+}
+
+/// Decodes URL-safe base64 without padding, returning `None` if the `text`
+/// isn't valid.
+#[allow(clippy::cast_possible_truncation, reason = "extracting bytes")]
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3 + 2);
+    for chunk in text.as_bytes().chunks(4) {
+        // A single character holds less than a byte.
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut bits = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|b| b == c)?;
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+        bytes.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(bytes)
+}
@@ -0,0 +1,234 @@
+//! Statistics of the rendered view: the percentage of pixels inside the set.
+//! A compute pass counts the orbits that don't escape into a storage buffer,
+//! which is read back on a later frame, so that the render loop never waits
+//! for it.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferAsyncError,
+    BufferBindingType, BufferDescriptor, BufferUsages,
+    CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, MapMode, PipelineCompilationOptions,
+    PipelineLayoutDescriptor, PollType, Queue, ShaderStages,
+};
+
+use crate::{AppState, MandelbrotRenderer, Uniforms};
+use mandelbrot_explorer_wgpu::renderer::same_orbits;
+
+/// Spacing of the pixels whose orbits are counted, matches the
+/// `INSIDE_STRIDE` in the shader. Counting every 16th pixel is accurate
+/// enough for a percentage and keeps the pass cheap.
+pub const INSIDE_STRIDE: u32 = 4;
+
+/// Size of the workgroups of the `cs_count_inside` and `cs_max_escape_iter`
+/// compute shaders.
+pub const WORKGROUP_SIZE: u32 = 8;
+
+/// Resources for counting the pixels inside the set.
+#[derive(Debug)]
+pub struct InsideStats {
+    pipeline: ComputePipeline,
+    /// The atomic counter the compute pass writes to.
+    count: Buffer,
+    count_bind_group: BindGroup,
+    /// The counter copied for reading it back.
+    readback: Buffer,
+    /// The result of mapping the `readback` buffer and the number of pixels
+    /// counted, while a count is in flight.
+    pending: Option<(Receiver<Result<(), BufferAsyncError>>, u32)>,
+    /// The fraction of the pixels inside the set in the last finished count.
+    fraction: Option<f64>,
+    /// The uniforms of the last count, so that a still view is counted once.
+    counted: Option<Uniforms>,
+}
+
+impl InsideStats {
+    /// Creates a new [`InsideStats`] counting the pixels rendered by the
+    /// `renderer`.
+    pub fn new(device: &Device, renderer: &MandelbrotRenderer) -> Self {
+        let layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Inside Count Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Inside Count Pipeline Layout"),
+                bind_group_layouts: &[renderer.bind_group_layout(), &layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("cs_count_inside"),
+                layout: Some(&pipeline_layout),
+                module: renderer.shader(),
+                entry_point: Some("cs_count_inside"),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            });
+
+        let count = device.create_buffer(&BufferDescriptor {
+            label: Some("Inside Count Buffer"),
+            size: size_of::<u32>() as u64,
+            usage: BufferUsages::STORAGE
+                | BufferUsages::COPY_SRC
+                | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let count_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Inside Count Bind Group"),
+            layout: &layout,
+            entries: &[BindGroupEntry {
+                binding: 8,
+                resource: count.as_entire_binding(),
+            }],
+        });
+        let readback = device.create_buffer(&BufferDescriptor {
+            label: Some("Inside Count Readback Buffer"),
+            size: size_of::<u32>() as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            count,
+            count_bind_group,
+            readback,
+            pending: None,
+            fraction: None,
+            counted: None,
+        }
+    }
+
+    /// Whether a count is in flight.
+    pub const fn is_counting(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Submits a count of the pixels of an image of `size` inside the set,
+    /// with the [`Uniforms`](crate::Uniforms) bound by the `uniforms` bind
+    /// group, and starts mapping its result.
+    fn submit(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        uniforms: &BindGroup,
+        size: (u32, u32),
+    ) {
+        let (width, height) = (
+            size.0.div_ceil(INSIDE_STRIDE),
+            size.1.div_ceil(INSIDE_STRIDE),
+        );
+        let mut encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Inside Count Encoder"),
+            });
+        encoder.clear_buffer(&self.count, 0, None);
+        {
+            let mut compute_pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("Inside Count Compute Pass"),
+                    timestamp_writes: None,
+                });
+            compute_pass.set_pipeline(&self.pipeline);
+            compute_pass.set_bind_group(0, uniforms, &[]);
+            compute_pass.set_bind_group(1, &self.count_bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        encoder.copy_buffer_to_buffer(&self.count, 0, &self.readback, 0, None);
+        queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        self.readback.map_async(MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.pending = Some((receiver, width * height));
+    }
+
+    /// Reads back the pending count if it's finished, without waiting for
+    /// it. Returns whether there is no count in flight anymore.
+    fn receive(&mut self, device: &Device) -> bool {
+        let Some((receiver, total)) = &self.pending else {
+            return true;
+        };
+        let _ = device.poll(PollType::Poll);
+        match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return false,
+            Ok(Ok(())) => {
+                let count: u32 = bytemuck::pod_read_unaligned(
+                    &self.readback.get_mapped_range(..),
+                );
+                self.readback.unmap();
+                self.fraction =
+                    Some(f64::from(count) / f64::from((*total).max(1)));
+            }
+            Ok(Err(e)) => eprintln!("Failed to read the inside count: {e}"),
+            Err(TryRecvError::Disconnected) => {
+                eprintln!("Failed to read the inside count: mapping cancelled");
+            }
+        }
+        self.pending = None;
+        true
+    }
+}
+
+impl AppState {
+    /// Shows or hides the percentage of pixels inside the set in the title.
+    pub fn toggle_inside_stats(&mut self) {
+        self.inside_stats = match self.inside_stats {
+            Some(_) => None,
+            None => Some(InsideStats::new(&self.device, &self.renderer)),
+        };
+        self.view.title.1 = None;
+    }
+
+    /// Reads back the last count of the pixels inside the set, if it's
+    /// finished, and starts counting them in the current view if it changed.
+    /// Must be called after the [`Uniforms`] are written.
+    pub fn update_inside_stats(&mut self) {
+        let size = self.render_size();
+        let Some(stats) = &mut self.inside_stats else {
+            return;
+        };
+        let uniforms = &self.view.uniforms;
+        if stats.receive(&self.device)
+            && !stats
+                .counted
+                .is_some_and(|counted| same_orbits(&counted, uniforms))
+        {
+            stats.submit(
+                &self.device,
+                &self.queue,
+                self.renderer.bind_group(),
+                size,
+            );
+            stats.counted = Some(*uniforms);
+        }
+    }
+
+    /// The percentage of the pixels inside the set in the last finished
+    /// count, if the statistics are shown.
+    #[must_use]
+    pub fn inside_percentage(&self) -> Option<f64> {
+        self.inside_stats
+            .as_ref()
+            .and_then(|stats| stats.fraction)
+            .map(|fraction| fraction * 100.0)
+    }
+}
@@ -0,0 +1,92 @@
+//! Navigating with a touchscreen or a trackpad: dragging a single finger pans,
+//! gliding on after it's lifted, pinching two fingers zooms around the point
+//! between them.
+
+use winit::{
+    dpi::PhysicalPosition,
+    event::{Touch, TouchPhase},
+};
+
+use crate::AppState;
+
+/// Positions of the two fingers of a pinch.
+type Fingers = (PhysicalPosition<f64>, PhysicalPosition<f64>);
+
+impl AppState {
+    /// Handles a touch event, panning while a single finger is dragged and
+    /// zooming while two fingers are pinched.
+    pub fn touch_input(&mut self, touch: Touch) {
+        let Touch {
+            id,
+            phase,
+            location,
+            ..
+        } = touch;
+        match phase {
+            TouchPhase::Started => {
+                self.save_history();
+                // A pinch doesn't glide, and a new drag catches the view.
+                self.view.inertia.stop();
+                self.view.touches.insert(id, location);
+            }
+            TouchPhase::Moved => {
+                let Some(&last) = self.view.touches.get(&id) else {
+                    return;
+                };
+                match self.view.touches.len() {
+                    1 => {
+                        let (dx, dy) =
+                            (location.x - last.x, location.y - last.y);
+                        self.drag(dx, dy);
+                        self.track_drag(dx, dy);
+                    }
+                    2 => {
+                        let other = self
+                            .view
+                            .touches
+                            .iter()
+                            .find_map(|(&other, &p)| (other != id).then_some(p))
+                            .expect("two touches");
+                        self.pinch((last, other), (location, other));
+                    }
+                    _ => {}
+                }
+                self.view.touches.insert(id, location);
+            }
+            TouchPhase::Ended => {
+                self.view.touches.remove(&id);
+                if self.view.touches.is_empty() {
+                    self.release_drag();
+                }
+            }
+            TouchPhase::Cancelled => {
+                self.view.touches.remove(&id);
+                self.view.inertia.stop();
+            }
+        }
+    }
+
+    /// Handles a trackpad pinch gesture, zooming around the cursor by the
+    /// magnification `delta`, positive when zooming in.
+    pub fn pinch_gesture(&mut self, delta: f64) {
+        self.save_history();
+        // The magnification is the reciprocal of `exp(zooms / 10)`.
+        self.mouse_zoom(-10.0 * delta.ln_1p());
+    }
+
+    /// Zooms and pans so that the points under two fingers moving from the
+    /// positions `from` to `to` follow them.
+    fn pinch(&mut self, from: Fingers, to: Fingers) {
+        let centroid = |(a, b): Fingers| {
+            PhysicalPosition::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+        };
+        let distance = |(a, b): Fingers| (a.x - b.x).hypot(a.y - b.y);
+        let (from_center, to_center) = (centroid(from), centroid(to));
+        self.drag(to_center.x - from_center.x, to_center.y - from_center.y);
+        let (from_distance, to_distance) = (distance(from), distance(to));
+        if from_distance > 0.0 && to_distance > 0.0 {
+            self.move_mouse(to_center);
+            self.mouse_zoom(-10.0 * (to_distance / from_distance).ln());
+        }
+    }
+}